@@ -0,0 +1,33 @@
+//! Times header lookups on a request with 16 headers, in lowercase, in
+//! mixed case and for a missing key, against lowercasing the query into a
+//! new `String` first as lookups used to.
+//!
+//! Run with `cargo bench --bench lookup`.
+use std::{hint::black_box, time::Instant};
+
+use heggemann_http::Request;
+
+fn main() {
+    let mut raw = String::from("GET / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n");
+    for i in 0..14 {
+        raw.push_str(&format!("X-Header-{i}: some moderately long header value\r\n"));
+    }
+    let request: Request = raw.parse().unwrap();
+    assert_eq!(request.headers.len(), 16);
+
+    let iterations = 1_000_000;
+    for (name, query) in [("lowercase", "content-length"), ("mixed case", "Content-Length"), ("missing", "If-None-Match")] {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box(black_box(&request).headers.get(black_box(query)));
+        }
+        println!("{name}: {:?} per lookup", start.elapsed() / iterations);
+    }
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let query = black_box("Content-Length").to_ascii_lowercase();
+        black_box(black_box(&request).headers.get(&query));
+    }
+    println!("mixed case, lowercased into a String: {:?} per lookup", start.elapsed() / iterations);
+}
@@ -0,0 +1,29 @@
+//! Times serialization of a response with 20 headers and a 64 KiB body.
+//!
+//! Run with `cargo bench --bench serialize`.
+use std::{hint::black_box, time::Instant};
+
+use heggemann_http::{Byteable, Response};
+
+fn main() {
+    let mut builder = Response::Ok.header("host", "example.com").unwrap();
+    for i in 0..19 {
+        builder = builder.header(format!("x-header-{i}"), "some moderately long header value").unwrap();
+    }
+    let response = builder.body(vec![b'a'; 64 * 1024]);
+
+    let iterations = 10_000;
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(black_box(&response).to_bytes());
+    }
+    let elapsed = start.elapsed();
+    println!("to_bytes: {:?} per response", elapsed / iterations);
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(black_box(response.clone()).into_bytes());
+    }
+    let elapsed = start.elapsed();
+    println!("clone + into_bytes: {:?} per response", elapsed / iterations);
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+use heggemann_http::{fuzzing::RawRequest, Request};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: RawRequest| {
+    let parsed = Request::try_from(input.0.as_slice());
+    if let Ok(text) = std::str::from_utf8(&input.0) {
+        assert_eq!(text.parse::<Request>(), parsed);
+    }
+    if let Ok(request) = parsed {
+        let reparsed = request.to_string().parse::<Request>().expect("serialized request parses");
+        assert_eq!(reparsed, request);
+    }
+});
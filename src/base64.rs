@@ -0,0 +1,64 @@
+//! Standard base64 with padding (RFC 4648, section 4).
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// `None` unless `s` is padded base64 without whitespace.
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(s.len() / 4 * 3);
+    for (index, chunk) in s.chunks(4).enumerate() {
+        let last = index == s.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &b) in chunk[..4 - padding].iter().enumerate() {
+            let sextet = ALPHABET.iter().position(|&a| a == b)? as u32;
+            n |= sextet << (18 - 6 * i);
+        }
+        decoded.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_vectors() {
+        for (plain, encoded) in [
+            ("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+    #[test]
+    fn reject_invalid() {
+        assert_eq!(decode("Zg="), None);
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Z==="), None);
+        assert_eq!(decode("Zm9v YmFy"), None);
+    }
+}
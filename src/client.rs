@@ -0,0 +1,277 @@
+//! A blocking client sending a [Request] over a [TcpStream], or any other
+//! stream, and reading the response.
+//!
+//! Timeouts are set on the stream by the caller, with
+//! [set_read_timeout][TcpStream::set_read_timeout] and
+//! [set_write_timeout][TcpStream::set_write_timeout]; running into one is
+//! reported as [ClientError::Io].
+//!
+//! # Examples
+//! ```no_run
+//! # use heggemann_http::client;
+//! let response = client::get("localhost", 8080, "/health").unwrap();
+//! assert_eq!(response.status.code, 200);
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+use crate::{
+    extensions::Extensions,
+    framing,
+    header::{HeaderError, HeaderMap, KeepAlive, Key, Value},
+    response::{ChunkedError, StatusLine, StatusLineError},
+    server::Body,
+    Request,
+    RequestMethod,
+    Version,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+/// A response as read by [send], with its body unframed.
+pub struct ParsedResponse {
+    pub status: StatusLine,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+impl ParsedResponse {
+    /// The idle timeout and request limit the server announced in
+    /// `keep-alive`, if it sent one.
+    pub fn keep_alive(&self) -> Option<KeepAlive> {
+        self.headers.get("keep-alive").map(|v| KeepAlive::parse(&v.as_str_lossy()))
+    }
+}
+
+/// Sends `request` and reads the final response, skipping interim 1xx
+/// responses other than [101][crate::Response::SwitchingProtocols].
+///
+/// A `host` header with `authority` is sent if the request has none. A
+/// [Body] in the [extensions][Request::extensions] of the request is
+/// sent after the head, with a `content-length` unless the request
+/// declares its framing itself.
+///
+/// Bytes read past the response, such as the start of the next pipelined
+/// response or the first bytes after a `101`, stay in the buffer of
+/// `stream` for the next call, or to be taken from [BufReader::buffer].
+pub fn send<S: Read + Write>(stream: &mut BufReader<S>, request: &Request, authority: &str) -> Result<ParsedResponse, ClientError> {
+    let mut out = Vec::new();
+    write_request(&mut out, request, authority);
+    stream.get_mut().write_all(&out)?;
+    stream.get_mut().flush()?;
+    read_response(stream, request.method)
+}
+
+/// Like [send], over the Unix domain socket at `path`.
+///
+/// Such a socket has no authority, so requests without a `host` header
+/// get `host: localhost`, as curl sends with `--unix-socket`.
+#[cfg(unix)]
+pub fn send_unix<P: AsRef<Path>>(path: P, request: &Request) -> Result<ParsedResponse, ClientError> {
+    send(&mut BufReader::new(UnixStream::connect(path)?), request, "localhost")
+}
+
+/// Like [send], over TLS to `host` and `port`, verifying the server's
+/// certificate against the webpki roots.
+#[cfg(feature = "tls")]
+pub fn send_tls(host: &str, port: u16, request: &Request) -> Result<ParsedResponse, ClientError> {
+    let roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    send_tls_with(tls_config(roots), host, port, request)
+}
+
+/// Like [send_tls], with a configuration such as one from [tls_config].
+#[cfg(feature = "tls")]
+pub fn send_tls_with(config: Arc<rustls::ClientConfig>, host: &str, port: u16, request: &Request) -> Result<ParsedResponse, ClientError> {
+    let name = rustls::pki_types::ServerName::try_from(host.to_owned())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let tls = rustls::ClientConnection::new(config, name).map_err(io::Error::other)?;
+    let mut stream = BufReader::new(rustls::StreamOwned::new(tls, TcpStream::connect((host, port))?));
+    let authority = if port == 443 { host.to_owned() } else { format!("{host}:{port}") };
+    send(&mut stream, request, &authority)
+}
+
+/// A client configuration trusting `roots`, offering only `http/1.1` in
+/// ALPN.
+#[cfg(feature = "tls")]
+pub fn tls_config(roots: rustls::RootCertStore) -> Arc<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        // The ring provider supports the default versions
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Arc::new(config)
+}
+
+/// Opens a connection to `host` and `port`, sends a `GET` for `path` and
+/// reads the response, closing the connection afterwards.
+pub fn get(host: &str, port: u16, path: &str) -> Result<ParsedResponse, ClientError> {
+    let mut stream = BufReader::new(TcpStream::connect((host, port))?);
+    let authority = if port == 80 { host.to_owned() } else { format!("{host}:{port}") };
+    let mut request = Request {
+        method: RequestMethod::Get,
+        path: path.to_owned(),
+        headers: HeaderMap::new(),
+        version: Version::HTTP_11,
+        extensions: Extensions::new(),
+    };
+    request.headers.insert(Key::CONNECTION, Value::new("close").unwrap());
+    send(&mut stream, &request, &authority)
+}
+
+fn write_request(out: &mut Vec<u8>, request: &Request, authority: &str) {
+    let host = Some(authority).filter(|_| !request.headers.contains_key("host"));
+    let body = request.extensions.get::<Body>().map_or(&[][..], |b| &b.0);
+    let framed = request.headers.contains_key("content-length") || request.headers.contains_key("transfer-encoding");
+    let content_length = Some(body.len()).filter(|_| !body.is_empty() && !framed);
+    request.write_head(out, host, content_length);
+    out.extend_from_slice(body);
+}
+
+fn read_response<R: BufRead>(reader: &mut R, method: RequestMethod) -> Result<ParsedResponse, ClientError> {
+    loop {
+        let mut head = Vec::new();
+        if !framing::read_head(reader, &mut head)? {
+            return Err(ClientError::HeadTooLarge);
+        }
+        let mut lines = head.split(|&b| b == b'\n').map(|l| l.strip_suffix(b"\r").unwrap_or(l));
+        let status: StatusLine = String::from_utf8_lossy(lines.next().unwrap_or_default()).parse()?;
+        let headers = HeaderMap::from_lines(lines.take_while(|l| !l.is_empty()), true)?;
+        if (100..200).contains(&status.code) && status.code != 101 {
+            continue;
+        }
+        let framing = framing::response_framing(method, status.code, &headers).ok_or(ClientError::InvalidFraming)?;
+        let body = framing::read_body(reader, framing, None)?;
+        return Ok(ParsedResponse { status, headers, body });
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// Reading or writing failed, which includes running into a timeout
+    /// and failing the TLS handshake
+    Io(io::Error),
+    /// The connection closed before the response was complete
+    UnexpectedEof,
+    /// The status line is invalid
+    StatusLine(StatusLineError),
+    /// A header is not compliant with header syntax
+    BadHeader(HeaderError),
+    /// The response head is longer than [MAX_HEAD_LEN][crate::request::MAX_HEAD_LEN]
+    HeadTooLarge,
+    /// The `content-length` is no number or the chunks are malformed
+    InvalidFraming,
+}
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::StatusLine(e) => Some(e),
+            Self::BadHeader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Io(e) => write!(f, "connection failed: {e}"),
+            Self::UnexpectedEof => write!(f, "connection closed before the response was complete"),
+            Self::StatusLine(e) => write!(f, "status line invalid: {e}"),
+            Self::BadHeader(e) => write!(f, "header invalid: {e}"),
+            Self::HeadTooLarge => write!(f, "response head too large"),
+            Self::InvalidFraming => write!(f, "response body framing invalid"),
+        }
+    }
+}
+impl From<io::Error> for ClientError {
+    fn from(value: io::Error) -> Self {
+        match value.kind() {
+            io::ErrorKind::UnexpectedEof => Self::UnexpectedEof,
+            io::ErrorKind::InvalidData if value.get_ref().is_some_and(|e| e.is::<ChunkedError>()) => Self::InvalidFraming,
+            _ => Self::Io(value),
+        }
+    }
+}
+impl From<StatusLineError> for ClientError {
+    fn from(value: StatusLineError) -> Self {
+        Self::StatusLine(value)
+    }
+}
+impl From<HeaderError> for ClientError {
+    fn from(value: HeaderError) -> Self {
+        Self::BadHeader(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_and_body_added() {
+        let mut request: Request = "POST /submit HTTP/1.1\r\n\r\n".parse().unwrap();
+        request.extensions.insert(Body(b"data".to_vec()));
+        let mut out = vec![];
+        write_request(&mut out, &request, "example.com:8080");
+        assert_eq!(out, b"POST /submit HTTP/1.1\r\nhost: example.com:8080\r\ncontent-length: 4\r\n\r\ndata");
+    }
+    #[test]
+    fn interim_responses_skipped() {
+        let mut stream = &b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokrest"[..];
+        let response = read_response(&mut stream, RequestMethod::Post).unwrap();
+        assert_eq!((response.status.code, response.body.as_slice()), (200, &b"ok"[..]));
+        assert_eq!(stream, b"rest");
+    }
+    /// A stream reading from `input` and writing to `output`.
+    struct Exchange {
+        input: &'static [u8],
+        output: Vec<u8>,
+    }
+    impl Read for Exchange {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+    impl Write for Exchange {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read_ahead_kept() {
+        let input = b"HTTP/1.1 200 OK\r\nContent-Length: 1\r\n\r\na\
+            HTTP/1.1 101 Switching Protocols\r\nUpgrade: x\r\nConnection: upgrade\r\n\r\nhello";
+        let mut stream = BufReader::new(Exchange { input, output: vec![] });
+        let request: Request = "GET /a HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(send(&mut stream, &request, "x").unwrap().body, b"a");
+        let upgrade: Request = "GET /b HTTP/1.1\r\nUpgrade: x\r\nConnection: upgrade\r\n\r\n".parse().unwrap();
+        assert_eq!(send(&mut stream, &upgrade, "x").unwrap().status.code, 101);
+        assert_eq!(stream.buffer(), b"hello");
+        assert!(stream.get_ref().output.starts_with(b"GET /a HTTP/1.1\r\nhost: x\r\n\r\nGET /b"));
+    }
+    #[test]
+    fn framing_by_method_and_status() {
+        let mut stream = &b"HTTP/1.1 200 OK\r\nContent-Length: 20\r\n\r\n"[..];
+        assert!(read_response(&mut stream, RequestMethod::Head).unwrap().body.is_empty());
+        let mut stream = &b"HTTP/1.0 200 OK\r\n\r\nuntil close"[..];
+        assert_eq!(read_response(&mut stream, RequestMethod::Get).unwrap().body, b"until close");
+        let mut stream = &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n"[..];
+        assert_eq!(read_response(&mut stream, RequestMethod::Get).unwrap().body, b"ok");
+        let mut stream = &b"HTTP/1.1 200 OK\r\nContent-Length: 20\r\n\r\ntoo short"[..];
+        assert!(matches!(read_response(&mut stream, RequestMethod::Get), Err(ClientError::UnexpectedEof)));
+    }
+}
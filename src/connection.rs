@@ -0,0 +1,801 @@
+//! A server's side of one connection, reading requests and writing their
+//! responses in turn for as long as both sides keep it alive.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::net::TcpListener;
+//! # use heggemann_http::{connection::Connection, Response};
+//! let (stream, _) = TcpListener::bind("127.0.0.1:8080").unwrap().accept().unwrap();
+//! let mut connection = Connection::new(stream);
+//! while let Some(request) = connection.next_request().unwrap() {
+//!     connection.respond(Response::Ok.body(request.path)).unwrap();
+//! }
+//! ```
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    driver::{Driver, Event},
+    framing::{self, Framing},
+    metrics::Metrics,
+    request::RequestParseError,
+    response::{ChunkedError, Complete, ResponseBuilder},
+    server::Body,
+    Request,
+    RequestMethod,
+    Response,
+    ResponseCode,
+};
+
+/// A connection on the server's side over a stream such as a
+/// [TcpStream][std::net::TcpStream].
+///
+/// Requests are read with their bodies, which end up as a [Body] in their
+/// [extensions][Request::extensions], and are answered in the order they
+/// arrived. Responses are written in the version of their request, framed
+/// with a `content-length` if they have none and get a `connection` header
+/// if keep-alive differs from the default of that version.
+///
+/// Once a response closes the connection, because either side asked to,
+/// the version does not keep connections alive, the request cap is
+/// reached or the request could not be parsed, no further requests are
+/// read.
+///
+/// The bytes read are parsed by a [Driver], which does all but the I/O.
+pub struct Connection<S> {
+    stream: S,
+    session: Session,
+    read_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    /// Sets the read timeout of the stream, once it is known to have one
+    set_timeout: Option<SetTimeout<S>>,
+}
+
+/// All of a [Connection] but its stream, which the async server reads and
+/// writes itself.
+pub(crate) struct Session {
+    pub(crate) driver: Driver,
+    /// Requests read with their bodies, not returned yet
+    ready: VecDeque<Request>,
+    /// The request whose body is being read, and the body so far
+    partial: Option<(Request, Vec<u8>)>,
+    /// Requests read but not answered yet
+    pending: VecDeque<Pending>,
+    closing: bool,
+    header_timeout: Option<Duration>,
+    /// When the first byte of the head being read arrived
+    head_started: Option<Instant>,
+    expect_continue: Option<ContinueHook>,
+    metrics: Option<Arc<dyn Metrics>>,
+}
+
+/// What a [Session] needs after the bytes read so far.
+pub(crate) enum Next {
+    Request(Request),
+    /// No further requests are read
+    Closed,
+    /// More bytes
+    Read,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How long a connection is kept open between requests and for how many,
+/// see [Connection::set_keep_alive].
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use heggemann_http::connection::KeepAlivePolicy;
+/// let policy = KeepAlivePolicy::new().idle_timeout(Duration::from_secs(5)).max_requests(100);
+/// assert_eq!(policy.get_max_requests(), Some(100));
+/// ```
+pub struct KeepAlivePolicy {
+    idle_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+}
+impl KeepAlivePolicy {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Closes a connection once no request started this long after the
+    /// last one.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+    /// Closes a connection with the response to the `max`th request.
+    pub fn max_requests(mut self, max: usize) -> Self {
+        self.max_requests = Some(max);
+        self
+    }
+    pub fn get_idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+    pub fn get_max_requests(&self) -> Option<usize> {
+        self.max_requests
+    }
+}
+
+/// A stream whose reads can time out, which a [Connection] needs for an
+/// [idle timeout][KeepAlivePolicy::idle_timeout].
+pub trait ReadTimeout {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+impl ReadTimeout for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+#[cfg(unix)]
+impl ReadTimeout for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+#[cfg(feature = "tls")]
+impl<C, T: ReadTimeout + Read + Write> ReadTimeout for rustls::StreamOwned<C, T> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.sock.set_read_timeout(timeout)
+    }
+}
+
+type SetTimeout<S> = fn(&S, Option<Duration>) -> io::Result<()>;
+type ContinueHook = Box<dyn Fn(&Request) -> ContinueDecision + Send + Sync>;
+
+#[derive(Debug, PartialEq)]
+/// What to do with a request waiting for a
+/// [100][Response::Continue] before it sends its body, see
+/// [Connection::on_expect_continue].
+pub enum ContinueDecision {
+    /// Send the `100` and read the body
+    Accept,
+    /// Answer with this final response instead, such as a
+    /// [417][Response::ExpectationFailed] or
+    /// [413][Response::PayloadTooLarge], and close the connection without
+    /// reading the body
+    Reject(ResponseBuilder<Complete>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    method: RequestMethod,
+    /// When the head arrived, for the [Metrics]
+    started: Instant,
+}
+
+impl<S: Read + Write> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream, session: Session::new(), read_timeout: None, idle_timeout: None, set_timeout: None }
+    }
+    /// Closes the connection with the response to the `max`th request.
+    pub fn set_max_requests(&mut self, max: usize) {
+        self.session.driver.set_max_requests(max);
+    }
+    /// Refuses a request whose head is not complete this long after its
+    /// first byte arrived with a [Timeout][RequestParseError::Timeout].
+    ///
+    /// The deadline is checked as bytes arrive, so a client going silent
+    /// is only noticed with a read timeout on the stream as well, see
+    /// [set_read_timeout][Connection::set_read_timeout].
+    pub fn set_header_timeout(&mut self, timeout: Option<Duration>) {
+        self.session.set_header_timeout(timeout);
+    }
+    /// Refuses a request whose body is longer than `max` bytes with
+    /// [BodyTooLarge][RequestParseError::BodyTooLarge], to be answered
+    /// with a [413][Response::PayloadTooLarge]. A longer `content-length`
+    /// is refused before the body is read, a chunked body once its decoded
+    /// bytes cross the limit.
+    pub fn set_max_body_size(&mut self, max: u64) {
+        self.session.driver.set_max_body_size(max);
+    }
+    /// Lets `decide` look at the head of every request with
+    /// `expect: 100-continue` and a body before the body is read.
+    ///
+    /// Accepted requests get their [100][Response::Continue]. A rejected
+    /// request is answered right away and the connection closes, so
+    /// [next_request][Self::next_request] returns `None` for it.
+    ///
+    /// Requests arriving while earlier ones still wait for their response,
+    /// which has to come first, and HTTP/1.0 requests, which know no `100`,
+    /// are read without asking; the client sends the body after waiting a
+    /// while.
+    ///
+    /// Without a hook, the body is read without a `100`.
+    pub fn on_expect_continue<F>(&mut self, decide: F)
+    where
+        F: Fn(&Request) -> ContinueDecision + Send + Sync + 'static,
+    {
+        self.session.expect_continue = Some(Box::new(decide));
+    }
+    /// Accepts every request waiting for a `100`, see
+    /// [on_expect_continue][Self::on_expect_continue].
+    pub fn always_continue(&mut self) {
+        self.on_expect_continue(|_| ContinueDecision::Accept);
+    }
+    /// Reports the requests read and answered and parse errors to
+    /// `metrics`.
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.session.set_metrics(metrics);
+    }
+    /// Reads the next request and its body, `None` if the client closed
+    /// the connection between requests or it is closing. A close within a
+    /// request is an [UnexpectedEof][RequestParseError::UnexpectedEof].
+    ///
+    /// After an error the connection is closing; the error's
+    /// [appropriate_response][RequestParseError::appropriate_response] can
+    /// still be sent with [respond][Self::respond].
+    pub fn next_request(&mut self) -> Result<Option<Request>, RequestParseError> {
+        if self.session.closing {
+            return Ok(None);
+        }
+        let read = self.read_request();
+        self.session.finish(read)
+    }
+    fn read_request(&mut self) -> Result<Option<Request>, RequestParseError> {
+        // Waiting for a request to start is bounded by the idle timeout,
+        // reading the rest of it by the read timeout again
+        let mut idle = self.idle_timeout.zip(self.set_timeout).filter(|_| self.session.is_waiting());
+        if let Some((timeout, set)) = idle {
+            set(&self.stream, Some(timeout)).map_err(parse_error)?;
+        }
+        let mut buf = [0; 8 * 1024];
+        let mut len = 0;
+        loop {
+            // Bytes the driver kept from before come first
+            self.session.process(&buf[..len], &mut self.stream)?;
+            if !self.session.is_waiting() {
+                if let Some((_, set)) = idle.take() {
+                    set(&self.stream, self.read_timeout).map_err(parse_error)?;
+                }
+            }
+            match self.session.next()? {
+                Next::Request(request) => return Ok(Some(request)),
+                Next::Closed => return Ok(None),
+                Next::Read => {}
+            }
+            len = match self.stream.read(&mut buf) {
+                Ok(0) => return self.session.eof(),
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => 0,
+                // An idle connection timing out ends like one closed by the client
+                Err(e) if self.session.driver.is_idle() && timed_out(&e) => return Ok(None),
+                Err(e) => return Err(parse_error(e)),
+            };
+        }
+    }
+    /// Writes the response to the oldest request not answered yet, or one
+    /// closing the connection if there is none, as after a parse error.
+    pub fn respond(&mut self, response: ResponseBuilder<Complete>) -> io::Result<()> {
+        self.session.respond(&mut self.stream, response)
+    }
+    /// Whether no further requests are read, so the connection should be
+    /// closed once the requests read are answered.
+    pub fn is_closing(&self) -> bool {
+        self.session.is_closing()
+    }
+    /// Bytes read from the stream past the last request, such as the
+    /// start of a pipelined request.
+    pub fn remaining_buffer(&self) -> &[u8] {
+        self.session.driver.remaining()
+    }
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+    /// The stream and the bytes read from it past the last request, for
+    /// speaking another protocol after a [101][crate::Response::SwitchingProtocols].
+    pub fn into_parts(self) -> (S, Vec<u8>) {
+        let buffered = self.session.driver.remaining().to_vec();
+        (self.stream, buffered)
+    }
+}
+
+impl Session {
+    pub(crate) fn new() -> Self {
+        Self {
+            driver: Driver::new(),
+            ready: VecDeque::new(),
+            partial: None,
+            pending: VecDeque::new(),
+            closing: false,
+            header_timeout: None,
+            head_started: None,
+            expect_continue: None,
+            metrics: None,
+        }
+    }
+    pub(crate) fn set_header_timeout(&mut self, timeout: Option<Duration>) {
+        self.header_timeout = timeout;
+    }
+    /// See [Connection::always_continue].
+    #[cfg(feature = "tokio")]
+    pub(crate) fn always_continue(&mut self) {
+        self.expect_continue = Some(Box::new(|_| ContinueDecision::Accept));
+    }
+    pub(crate) fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = Some(metrics);
+    }
+    /// Whether no request has started since the last one returned.
+    pub(crate) fn is_waiting(&self) -> bool {
+        self.ready.is_empty() && self.driver.is_idle()
+    }
+    /// When the head being read runs into the header timeout.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn head_deadline(&self) -> Option<Instant> {
+        self.head_started.zip(self.header_timeout).map(|(started, timeout)| started + timeout)
+    }
+    /// The next request read, once the bytes [processed][Self::process]
+    /// complete one.
+    pub(crate) fn next(&mut self) -> Result<Next, RequestParseError> {
+        if let Some(request) = self.ready.pop_front() {
+            return Ok(Next::Request(request));
+        }
+        if self.driver.is_closing() {
+            return Ok(Next::Closed);
+        }
+        if self.partial.is_none() && !self.driver.is_idle() {
+            let started = *self.head_started.get_or_insert_with(Instant::now);
+            if self.header_timeout.is_some_and(|timeout| started.elapsed() > timeout) {
+                return Err(RequestParseError::Timeout);
+            }
+        }
+        Ok(Next::Read)
+    }
+    /// Ends the input after the client closed its side.
+    pub(crate) fn eof(&mut self) -> Result<Option<Request>, RequestParseError> {
+        self.driver.eof().map_or(Ok(None), Err)
+    }
+    /// Closes the session unless `read` is a request, and reports errors
+    /// to the [Metrics].
+    pub(crate) fn finish(&mut self, read: Result<Option<Request>, RequestParseError>) -> Result<Option<Request>, RequestParseError> {
+        if !matches!(read, Ok(Some(_))) {
+            self.closing = true;
+        }
+        if let Err(e) = &read {
+            // The request cut short is answered with the error's response
+            self.driver.close();
+            self.partial = None;
+            if let Some(metrics) = &self.metrics {
+                metrics.on_parse_error(e);
+            }
+        }
+        read
+    }
+    /// Feeds `bytes` to the driver, collecting the requests they complete
+    /// and answering those waiting for a `100`.
+    pub(crate) fn process<W: Write>(&mut self, bytes: &[u8], out: &mut W) -> Result<(), RequestParseError> {
+        let mut rejected = None;
+        for event in self.driver.feed(bytes) {
+            match event {
+                Event::RequestHeadersComplete(request) => {
+                    self.head_started = None;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.on_request_start();
+                    }
+                    // Anything written now has to wait for earlier responses
+                    let waiting = framing::request_framing(&request.headers) != Ok(Framing::Length(0))
+                        && request.expects_continue()
+                        && request.version.supports_interim_responses()
+                        && self.pending.is_empty();
+                    self.pending.push_back(Pending { method: request.method, started: Instant::now() });
+                    if let Some(decide) = self.expect_continue.as_ref().filter(|_| waiting) {
+                        match decide(&request) {
+                            ContinueDecision::Accept => {
+                                let interim = ResponseBuilder::from(Response::Continue).version(request.version);
+                                interim.write_to(out).and_then(|_| out.flush()).map_err(parse_error)?;
+                            }
+                            ContinueDecision::Reject(response) => {
+                                rejected = Some(response);
+                                break;
+                            }
+                        }
+                    }
+                    self.partial = Some((request, Vec::new()));
+                }
+                Event::BodyChunk(chunk) => {
+                    if let Some((_, body)) = &mut self.partial {
+                        body.extend_from_slice(chunk);
+                    }
+                }
+                Event::RequestComplete => {
+                    if let Some((mut request, body)) = self.partial.take() {
+                        request.extensions.insert(Body(body));
+                        self.ready.push_back(request);
+                    }
+                }
+                Event::NeedsClose => {}
+                Event::Error(e) => return Err(e),
+            }
+        }
+        if let Some(response) = rejected {
+            // Without its body read, nothing after the request can be
+            self.driver.close();
+            self.partial = None;
+            self.respond(out, response).map_err(parse_error)?;
+        }
+        Ok(())
+    }
+    /// Writes the response to the oldest request not answered yet to
+    /// `out`, see [Connection::respond].
+    pub(crate) fn respond<W: Write>(&mut self, out: &mut W, response: ResponseBuilder<Complete>) -> io::Result<()> {
+        let (status, bytes_in) = (response.code(), self.driver.request_len());
+        let bufs = self.driver.queue_response(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut sent = 0;
+        for buf in bufs {
+            out.write_all(buf)?;
+            sent += buf.len() as u64;
+        }
+        out.flush()?;
+        let pending = self.pending.pop_front();
+        if let (Some(metrics), Some(pending)) = (&self.metrics, pending) {
+            metrics.on_request_end(&pending.method, status, bytes_in, sent, pending.started.elapsed());
+        }
+        if self.driver.is_closed() {
+            self.closing = true;
+            self.ready.clear();
+        }
+        Ok(())
+    }
+    pub(crate) fn is_closing(&self) -> bool {
+        self.closing || (self.driver.is_closing() && self.ready.is_empty() && self.partial.is_none())
+    }
+}
+
+impl<S: Read + Write + ReadTimeout> Connection<S> {
+    /// Bounds the wait for each read, see [TcpStream::set_read_timeout].
+    /// Running into it within a request is a
+    /// [Timeout][RequestParseError::Timeout], between requests it ends the
+    /// connection like a close by the client.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.read_timeout = timeout;
+        self.get_ref().set_read_timeout(timeout)
+    }
+    /// Applies `policy`: the idle timeout takes the place of the read
+    /// timeout while waiting for the next request, and the request limit
+    /// that of [set_max_requests][Connection::set_max_requests].
+    ///
+    /// Clients sending `connection: keep-alive` learn about the limits in
+    /// a `keep-alive` header, such as `timeout=5, max=99`, where `max`
+    /// counts the requests still answered after theirs.
+    pub fn set_keep_alive(&mut self, policy: KeepAlivePolicy) {
+        self.session.driver.set_keep_alive(policy);
+        self.idle_timeout = policy.idle_timeout;
+        self.set_timeout = Some(S::set_read_timeout);
+    }
+}
+
+impl Connection<TcpStream> {
+    /// Accepts the oldest request not answered yet, which has to be a
+    /// `CONNECT`, with a [200][Response::Ok] and relays bytes between the
+    /// client and `upstream` until both directions are closed. Bytes the
+    /// client sent past the request are forwarded first.
+    ///
+    /// Either side closing its direction is passed on to the other, so a
+    /// tunnel ends once a side closes and the other follows.
+    pub fn into_tunnel(mut self, upstream: TcpStream) -> io::Result<Tunnel> {
+        if !self.session.pending.front().is_some_and(|pending| pending.method == RequestMethod::Connect) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no CONNECT request to accept"));
+        }
+        self.respond(Response::Ok.body(""))?;
+        let (client, buffered) = self.into_parts();
+        let (mut from_client, mut to_upstream) = (client.try_clone()?, upstream.try_clone()?);
+        let upload = thread::spawn(move || -> io::Result<u64> {
+            to_upstream.write_all(&buffered)?;
+            let copied = io::copy(&mut from_client, &mut to_upstream)?;
+            // The other side may have closed the connection entirely already
+            let _ = to_upstream.shutdown(Shutdown::Write);
+            Ok(buffered.len() as u64 + copied)
+        });
+        let (mut from_upstream, mut to_client) = (upstream, client);
+        let downloaded = io::copy(&mut from_upstream, &mut to_client);
+        let _ = to_client.shutdown(Shutdown::Write);
+        let uploaded = upload.join().map_err(|_| io::Error::other("relaying to upstream panicked"))??;
+        Ok(Tunnel { from_client: uploaded, from_upstream: downloaded? })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The bytes relayed by a tunnel, see [Connection::into_tunnel].
+pub struct Tunnel {
+    pub from_client: u64,
+    pub from_upstream: u64,
+}
+
+/// Whether a read failed by running into the stream's read timeout.
+fn timed_out(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+/// The parse error behind an error reading a request: the
+/// [RequestParseError] or [ChunkedError] it wraps, a
+/// [Timeout][RequestParseError::Timeout], or the kind of a failed read.
+pub(crate) fn parse_error(e: io::Error) -> RequestParseError {
+    if timed_out(&e) {
+        return RequestParseError::Timeout;
+    }
+    let kind = e.kind();
+    let Some(inner) = e.into_inner() else {
+        return RequestParseError::Io(kind);
+    };
+    match inner.downcast::<RequestParseError>() {
+        Ok(e) => *e,
+        Err(inner) if inner.is::<ChunkedError>() => RequestParseError::InvalidFraming,
+        Err(_) => RequestParseError::Io(kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::{request::EofStage, Response};
+    use super::*;
+
+    /// A stream returning its input in the given pieces, one per read, and
+    /// collecting what is written.
+    struct Duplex {
+        reads: VecDeque<Vec<u8>>,
+        output: Vec<u8>,
+        /// How long every read takes
+        delay: Duration,
+        /// The read timeouts set, in order
+        timeouts: RefCell<Vec<Option<Duration>>>,
+    }
+    impl Duplex {
+        fn new(input: &[u8]) -> Self {
+            Self::split(input, &[])
+        }
+        /// Input cut at the ascending offsets `at`.
+        fn split(input: &[u8], at: &[usize]) -> Self {
+            let ends = at.iter().copied().chain([input.len()]);
+            let mut start = 0;
+            let reads = ends.map(|end| std::mem::replace(&mut start, end)..end).map(|r| input[r].to_vec()).collect();
+            Self { reads, output: vec![], delay: Duration::ZERO, timeouts: RefCell::default() }
+        }
+    }
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            std::thread::sleep(self.delay);
+            let Some(mut read) = self.reads.pop_front() else {
+                return Ok(0);
+            };
+            let len = read.len().min(buf.len());
+            buf[..len].copy_from_slice(&read[..len]);
+            if len < read.len() {
+                self.reads.push_front(read.split_off(len));
+            }
+            Ok(len)
+        }
+    }
+    impl ReadTimeout for Duplex {
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+            self.timeouts.borrow_mut().push(timeout);
+            Ok(())
+        }
+    }
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn echo(connection: &mut Connection<Duplex>) -> String {
+        while let Some(request) = connection.next_request().unwrap() {
+            let body = request.extensions.get::<Body>().unwrap().0.len();
+            connection.respond(Response::Ok.body(format!("{} {}", request.path, body))).unwrap();
+        }
+        String::from_utf8(connection.get_ref().output.clone()).unwrap()
+    }
+
+    #[test]
+    fn expect_continue() {
+        let upload = b"PUT /a HTTP/1.1\r\nHost: x\r\nExpect: 100-continue\r\nContent-Length: 2\r\n\r\nhi\
+            PUT /b HTTP/1.0\r\nExpect: 100-continue\r\nContent-Length: 2\r\n\r\nhi";
+        let mut connection = Connection::new(Duplex::new(upload));
+        connection.always_continue();
+        assert_eq!(echo(&mut connection), "HTTP/1.1 100 Continue\r\n\r\n\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/a 2\
+            HTTP/1.0 200 OK\r\ncontent-length: 4\r\n\r\n/b 2");
+        // Without a hook, nothing is sent ahead
+        let mut connection = Connection::new(Duplex::new(upload));
+        assert!(echo(&mut connection).starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+    #[test]
+    fn expect_continue_rejected() {
+        let head = b"PUT /big HTTP/1.1\r\nHost: x\r\nExpect: 100-continue\r\nContent-Length: 99999\r\n\r\n";
+        let mut connection = Connection::new(Duplex::new(head));
+        connection.on_expect_continue(|request| match request.headers.get("content-length") {
+            Some(len) if len.as_u64().is_some_and(|len| len > 1000) => {
+                ContinueDecision::Reject(Response::ExpectationFailed.body(""))
+            }
+            _ => ContinueDecision::Accept,
+        });
+        assert_eq!(connection.next_request(), Ok(None));
+        assert!(connection.is_closing());
+        assert_eq!(connection.get_ref().output, b"HTTP/1.1 417 Expectation Failed\r\n\
+            content-length: 0\r\nconnection: close\r\n\r\n");
+    }
+    #[test]
+    fn three_requests_until_close() {
+        let mut connection = Connection::new(Duplex::new(b"\
+            GET /a HTTP/1.1\r\nHost: x\r\n\r\n\
+            POST /b HTTP/1.1\r\nHost: x\r\nContent-Length: 3\r\n\r\nabc\
+            GET /c HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n\
+            GET /ignored HTTP/1.1\r\nHost: x\r\n\r\n"));
+        assert_eq!(echo(&mut connection), "\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/a 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/b 3\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\n/c 0");
+        assert!(connection.is_closing());
+        assert_eq!(connection.remaining_buffer(), b"GET /ignored HTTP/1.1\r\nHost: x\r\n\r\n");
+    }
+    #[test]
+    fn closed_by_version_and_cap() {
+        let mut connection = Connection::new(Duplex::new(b"GET /a HTTP/1.0\r\n\r\nGET /b HTTP/1.0\r\n\r\n"));
+        assert_eq!(echo(&mut connection), "HTTP/1.0 200 OK\r\ncontent-length: 4\r\n\r\n/a 0");
+        let mut connection = Connection::new(Duplex::new(b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nGET /b HTTP/1.1\r\nHost: x\r\n\r\n"));
+        connection.set_max_requests(1);
+        assert_eq!(echo(&mut connection), "HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\n/a 0");
+    }
+    #[test]
+    fn keep_alive_policy() {
+        let input = b"\
+            GET /a HTTP/1.0\r\nConnection: keep-alive\r\n\r\n\
+            GET /b HTTP/1.1\r\nHost: x\r\n\r\n\
+            GET /c HTTP/1.1\r\nHost: x\r\nConnection: keep-alive\r\n\r\n\
+            GET /ignored HTTP/1.1\r\nHost: x\r\n\r\n";
+        // One request per read, as requests read together are not waited for
+        let mut connection = Connection::new(Duplex::split(input, &[43, 71, 123]));
+        connection.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+        connection.set_keep_alive(KeepAlivePolicy::new().idle_timeout(Duration::from_millis(5500)).max_requests(3));
+        // Only asking clients hear about the limits, and not on the last response
+        assert_eq!(echo(&mut connection), "\
+            HTTP/1.0 200 OK\r\ncontent-length: 4\r\nconnection: keep-alive\r\nkeep-alive: timeout=5, max=2\r\n\r\n/a 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/b 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\n/c 0");
+        let (idle, read) = (Some(Duration::from_millis(5500)), Some(Duration::from_secs(30)));
+        assert_eq!(*connection.get_ref().timeouts.borrow(), [read, idle, read, idle, read, idle, read]);
+    }
+    #[test]
+    fn body_size_limit() {
+        let limited = |input: Duplex| {
+            let mut connection = Connection::new(input);
+            connection.set_max_body_size(10);
+            connection
+        };
+        // Refused before any of the body is read
+        let mut connection = limited(Duplex::new(b"POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 11\r\n\r\n01234567890"));
+        assert_eq!(connection.next_request(), Err(RequestParseError::BodyTooLarge));
+        assert_eq!(connection.remaining_buffer(), b"01234567890");
+        connection.respond(RequestParseError::BodyTooLarge.appropriate_response().unwrap().body("")).unwrap();
+        assert_eq!(connection.get_ref().output, b"HTTP/1.1 413 Content Too Large\r\n\
+            content-length: 0\r\nconnection: close\r\n\r\n");
+        assert_eq!(connection.next_request(), Ok(None));
+        // Crossing the limit with the third chunk
+        let chunked = b"POST / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n\
+            4\r\nabcd\r\n4\r\nefgh\r\n4\r\nijkl\r\n0\r\n\r\n";
+        let head = chunked.len() - 32;
+        let mut connection = limited(Duplex::split(chunked, &[head, head + 9, head + 18, head + 27]));
+        assert_eq!(connection.next_request(), Err(RequestParseError::BodyTooLarge));
+        assert_eq!(connection.get_ref().reads, [b"0\r\n\r\n"]);
+        // Exactly at the limit is fine, either way
+        let mut connection = limited(Duplex::new(b"POST /a HTTP/1.1\r\nHost: x\r\nContent-Length: 10\r\n\r\n0123456789\
+            POST /b HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+            5\r\n01234\r\n5\r\n56789\r\n0\r\n\r\n"));
+        assert_eq!(echo(&mut connection), "\
+            HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n/a 10\
+            HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\n/b 10");
+    }
+    #[test]
+    fn errors_close() {
+        let mut connection = Connection::new(Duplex::new(b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\nHost: x\r\n\r\n"));
+        let error = connection.next_request().unwrap_err();
+        assert_eq!(error, RequestParseError::MissingHost);
+        connection.respond(error.appropriate_response().unwrap().body("")).unwrap();
+        assert_eq!(connection.next_request(), Ok(None));
+        assert_eq!(connection.get_ref().output, b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+
+        let mut connection = Connection::new(Duplex::new(b"POST /a HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n"));
+        assert_eq!(connection.next_request(), Err(RequestParseError::InvalidFraming));
+        // The answer to a request cut short closes the connection
+        let mut connection = Connection::new(Duplex::new(b"POST /a HTTP/1.1\r\nHost: x\r\nContent-Length: 3\r\n\r\nab"));
+        assert!(connection.next_request().is_err());
+        connection.respond(Response::RequestTimeout.body("")).unwrap();
+        assert_eq!(connection.get_ref().output, b"HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+        let mut connection = Connection::new(Duplex::new(b"\r\n"));
+        assert_eq!(connection.next_request(), Ok(None));
+    }
+    #[test]
+    fn pipelined_in_one_read() {
+        let mut connection = Connection::new(Duplex::new(b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nGET /b HTTP/1.1\r\nHost: x\r\n\r\n"));
+        assert_eq!(connection.next_request().unwrap().unwrap().path, "/a");
+        assert!(connection.get_ref().reads.is_empty());
+        // Parsed along with the first, waiting to be returned
+        assert_eq!(connection.remaining_buffer(), b"");
+        assert_eq!(connection.next_request().unwrap().unwrap().path, "/b");
+
+        let mut connection = Connection::new(Duplex::new(b"\
+            GET /a HTTP/1.1\r\nHost: x\r\n\r\n\
+            GET /b HTTP/1.1\r\nHost: x\r\n\r\n\
+            GET /c HTTP/1.1\r\nHost: x\r\n\r\n"));
+        assert_eq!(echo(&mut connection), "\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/a 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/b 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/c 0");
+    }
+    #[test]
+    fn split_at_every_byte() {
+        let input = b"\
+            POST /a HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n\
+            POST /b HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\nde";
+        let expected = "\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/a 3\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/b 2";
+        for split in 1..input.len() {
+            let mut connection = Connection::new(Duplex::split(input, &[split]));
+            assert_eq!(echo(&mut connection), expected, "split at {split}");
+        }
+    }
+    #[test]
+    fn body_end_shares_read_with_next_request() {
+        let input = b"\
+            POST /a HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nhello\
+            GET /b HTTP/1.1\r\nHost: x\r\n\r\n";
+        let body_start = input.iter().position(|&b| b == b'h').unwrap();
+        let next_line_end = input.len() - b"Host: x\r\n\r\n".len();
+        let mut connection = Connection::new(Duplex::split(input, &[body_start + 2, next_line_end]));
+        let request = connection.next_request().unwrap().unwrap();
+        assert_eq!(request.extensions.get::<Body>().unwrap().0, b"hello");
+        assert_eq!(connection.get_ref().reads.len(), 1);
+        assert_eq!(connection.next_request().unwrap().unwrap().path, "/b");
+    }
+    #[test]
+    fn header_deadline() {
+        let input = b"GET /a HTTP/1.1\r\nHost: x\r\n\r\n";
+        let every_byte: Vec<_> = (1..input.len()).collect();
+        let mut slowloris = Duplex::split(input, &every_byte);
+        slowloris.delay = Duration::from_millis(2);
+        let mut connection = Connection::new(slowloris);
+        connection.set_header_timeout(Some(Duration::from_millis(20)));
+        let error = connection.next_request().unwrap_err();
+        assert_eq!(error, RequestParseError::Timeout);
+        connection.respond(error.appropriate_response().unwrap().body("")).unwrap();
+        assert_eq!(connection.get_ref().output, b"HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+
+        let mut connection = Connection::new(Duplex::split(input, &[10]));
+        connection.set_header_timeout(Some(Duration::from_millis(20)));
+        assert_eq!(connection.next_request().unwrap().unwrap().path, "/a");
+    }
+    #[test]
+    fn eof_positions() {
+        let eof = |input: &[u8]| Connection::new(Duplex::new(input)).next_request();
+        assert_eq!(eof(b""), Ok(None));
+        assert_eq!(eof(b"\r\n"), Ok(None));
+        let stage = |stage| Err(RequestParseError::UnexpectedEof { stage });
+        assert_eq!(eof(b"GET /a HT"), stage(EofStage::RequestLine));
+        assert_eq!(eof(b"GET /a HTTP/1.1\r\nHost"), stage(EofStage::Headers));
+        assert_eq!(eof(b"GET /a HTTP/1.1\r\nHost: x\r\nContent-Length: 3\r\n\r\nab"), stage(EofStage::Body));
+        let chunked = b"POST /a HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n";
+        assert_eq!(eof(chunked), stage(EofStage::Body));
+
+        let mut connection = Connection::new(Duplex::new(b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nGET /b"));
+        assert!(connection.next_request().unwrap().is_some());
+        connection.respond(crate::Response::Ok.body("")).unwrap();
+        assert_eq!(connection.next_request(), stage(EofStage::RequestLine));
+    }
+}
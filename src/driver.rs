@@ -0,0 +1,553 @@
+//! A server's side of one connection as a state machine without any I/O,
+//! for event loops such as mio or io_uring that read and write themselves.
+//!
+//! Bytes read are [fed][Driver::feed] to a [Driver], which returns the
+//! [Event]s they complete. Responses are
+//! [queued][Driver::queue_response] in the order of the requests and come
+//! back as the bytes to write. [Connection][crate::connection::Connection]
+//! drives one over a blocking stream.
+//!
+//! # Examples
+//! ```
+//! # use heggemann_http::{driver::{Driver, Event}, Response};
+//! let mut driver = Driver::new();
+//! let events = driver.feed(b"POST /echo HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\nhi");
+//! assert!(matches!(&events[0], Event::RequestHeadersComplete(request) if request.path == "/echo"));
+//! assert_eq!(events[1..], [Event::BodyChunk(b"hi"), Event::RequestComplete]);
+//! let response = Response::Ok.body("hi");
+//! let out: Vec<u8> = driver.queue_response(&response).unwrap().flatten().copied().collect();
+//! assert_eq!(out, b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nhi");
+//! ```
+
+use std::{collections::VecDeque, ops::Range, time::Duration};
+
+use crate::{
+    connection::KeepAlivePolicy,
+    framing::{self, ChunkedDecoder, Framing},
+    header::{KeepAlive, Key, ToHeader, Value},
+    request::{EofStage, RequestParseError, RequestParser},
+    response::{Complete, ResponseBuilder, ResponseError},
+    Request,
+    RequestMethod,
+    ResponseCode,
+    Version,
+};
+
+#[derive(Debug, PartialEq)]
+/// What the bytes fed to a [Driver] completed.
+pub enum Event<'a> {
+    /// The head of a request, whose body follows in
+    /// [BodyChunk][Event::BodyChunk]s
+    RequestHeadersComplete(Request),
+    /// The next piece of the body, decoded
+    BodyChunk(&'a [u8]),
+    /// The end of the body
+    RequestComplete,
+    /// No further requests are read, because the last one closes the
+    /// connection or could not be read. The connection is to be closed
+    /// once the responses are written.
+    NeedsClose,
+    /// The request could not be read; the error's
+    /// [appropriate_response][RequestParseError::appropriate_response]
+    /// can still be queued
+    Error(RequestParseError),
+}
+
+/// An [Event] with body chunks as ranges of the buffer, which is still
+/// written to while they are found.
+enum Found {
+    Head(Request),
+    Chunk(Range<usize>),
+    Complete,
+    Close,
+    Error(RequestParseError),
+}
+
+#[derive(Debug, Default)]
+enum State {
+    /// Reading a request head, or waiting for one
+    #[default]
+    Head,
+    /// Reading a body with this many bytes left
+    Length(u64),
+    /// Reading a chunked body, this many bytes decoded so far
+    Chunked(ChunkedDecoder, u64),
+    /// Waiting for the response to an upgrade or `CONNECT`, after which
+    /// the connection may speak another protocol
+    Paused,
+    /// Reading no further requests
+    Stopped,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Where reading goes once the body of a request is read.
+enum After {
+    #[default]
+    Head,
+    Pause,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    version: Version,
+    keep_alive: bool,
+    method: RequestMethod,
+    /// The `keep-alive` header for a client that asked for one
+    announce: Option<KeepAlive>,
+    /// Where the request starts in the bytes fed, and where it ends once
+    /// read
+    start: u64,
+    end: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+/// A connection on the server's side, fed the bytes read from the client.
+///
+/// Requests are parsed as far as the bytes allow, pipelined ones
+/// included, except after a request that closes the connection, and after
+/// an upgrade or `CONNECT` until its response is queued. The bytes not
+/// used then are kept, see [remaining][Self::remaining].
+pub struct Driver {
+    parser: RequestParser,
+    state: State,
+    after: After,
+    /// Bytes fed, the first `used` of them used already
+    buffer: Vec<u8>,
+    used: usize,
+    /// All bytes used so far
+    received: u64,
+    /// Where the request being read starts
+    request_start: u64,
+    /// Requests read but not answered yet
+    pending: VecDeque<Pending>,
+    closed: bool,
+    served: usize,
+    max_requests: Option<usize>,
+    max_body_size: Option<u64>,
+    idle_timeout: Option<Duration>,
+    /// The head of the response queued last
+    head: Vec<u8>,
+}
+
+impl Driver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Closes the connection with the response to the `max`th request.
+    pub fn set_max_requests(&mut self, max: usize) {
+        self.max_requests = Some(max);
+    }
+    /// Refuses a request whose body is longer than `max` bytes with
+    /// [BodyTooLarge][RequestParseError::BodyTooLarge]: a longer
+    /// `content-length` right after the head, a chunked body once its
+    /// decoded bytes cross the limit.
+    pub fn set_max_body_size(&mut self, max: u64) {
+        self.max_body_size = Some(max);
+    }
+    /// Applies the request limit of `policy` and announces its limits to
+    /// clients sending `connection: keep-alive`, see
+    /// [Connection::set_keep_alive][crate::connection::Connection::set_keep_alive].
+    /// Enforcing the idle timeout is up to the caller.
+    pub fn set_keep_alive(&mut self, policy: KeepAlivePolicy) {
+        self.idle_timeout = policy.get_idle_timeout();
+        self.max_requests = policy.get_max_requests();
+    }
+    /// Takes bytes read from the client and returns the events they
+    /// complete, in order.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event<'_>> {
+        self.buffer.drain(..self.used);
+        self.used = 0;
+        self.buffer.extend_from_slice(bytes);
+        let mut found = Vec::new();
+        while self.advance(&mut found) {}
+        found.into_iter().map(|found| match found {
+            Found::Head(request) => Event::RequestHeadersComplete(request),
+            Found::Chunk(range) => Event::BodyChunk(&self.buffer[range]),
+            Found::Complete => Event::RequestComplete,
+            Found::Close => Event::NeedsClose,
+            Found::Error(e) => Event::Error(e),
+        }).collect()
+    }
+    /// The client closed its side of the connection: the error for a
+    /// request cut short by that, if any. No further requests are read.
+    pub fn eof(&mut self) -> Option<RequestParseError> {
+        let stage = match self.state {
+            State::Head => self.parser.stage(),
+            State::Length(_) | State::Chunked(..) => Some(EofStage::Body),
+            State::Paused | State::Stopped => None,
+        };
+        self.state = State::Stopped;
+        stage.map(|stage| RequestParseError::UnexpectedEof { stage })
+    }
+    /// Reads no further requests and closes the connection with the
+    /// response to the last request read, such as one whose body is
+    /// refused before it arrives.
+    pub fn close(&mut self) {
+        self.state = State::Stopped;
+        if let Some(pending) = self.pending.back_mut().filter(|pending| pending.end.is_none()) {
+            pending.keep_alive = false;
+            pending.announce = None;
+        }
+    }
+    /// Frames `response` as the answer to the oldest request not answered
+    /// yet, or as one closing the connection if there is none, as after an
+    /// [Error][Event::Error], and returns the bytes to write in order.
+    ///
+    /// The response is sent in the version of its request, with a
+    /// `content-length` if it has none and a `connection` header if
+    /// keep-alive differs from the default of that version. One closing
+    /// the connection, a [101][crate::Response::SwitchingProtocols] and a
+    /// successful answer to `CONNECT` stop reading for good.
+    ///
+    /// A `content-length` disagreeing with the body is refused without
+    /// queueing anything.
+    pub fn queue_response<'a>(
+        &'a mut self,
+        response: &'a ResponseBuilder<Complete>,
+    ) -> Result<impl Iterator<Item = &'a [u8]>, ResponseError> {
+        let to_head = self.pending.front().is_some_and(|pending| pending.method == RequestMethod::Head);
+        if !to_head {
+            response.check_content_length()?;
+        }
+        let pending = self.pending.pop_front().unwrap_or(Pending {
+            version: Version::HTTP_11,
+            keep_alive: false,
+            method: RequestMethod::Get,
+            announce: None,
+            start: self.received,
+            end: None,
+        });
+        let (head, open) = prepare(response.borrow_body(), pending.version, pending.keep_alive, pending.method, pending.announce);
+        if !open || head.code() == 101 {
+            self.closed = true;
+            self.state = State::Stopped;
+            self.pending.clear();
+        } else if matches!(self.state, State::Paused) && self.pending.is_empty() {
+            self.state = State::Head;
+        }
+        self.head.clear();
+        // Writing into a `Vec` cannot fail
+        head.write_head_to(&mut self.head).unwrap();
+        let body = if to_head { &[][..] } else { response.get_body() };
+        Ok([&self.head[..], body].into_iter().filter(|buf| !buf.is_empty()))
+    }
+    /// Whether the driver waits for the next request with no part of it
+    /// fed yet.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Head) && self.parser.is_idle() && self.used == self.buffer.len()
+    }
+    /// Whether no further requests are read.
+    pub fn is_closing(&self) -> bool {
+        matches!(self.state, State::Stopped)
+    }
+    /// Whether a response closing the connection was queued, after which
+    /// nothing is to be written either.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+    /// Bytes fed but not used, such as those after a request closing the
+    /// connection or, once the response switched protocols, the first
+    /// bytes of the new protocol.
+    pub fn remaining(&self) -> &[u8] {
+        &self.buffer[self.used..]
+    }
+    /// Bytes of the oldest request not answered yet, as far as it is read.
+    pub(crate) fn request_len(&self) -> u64 {
+        self.pending.front().map_or(0, |pending| pending.end.unwrap_or(self.received) - pending.start)
+    }
+    /// Uses the next bytes of the buffer, returning whether to go on.
+    fn advance(&mut self, found: &mut Vec<Found>) -> bool {
+        let start = self.used;
+        let rest = &self.buffer[start..];
+        if rest.is_empty() {
+            return false;
+        }
+        match &mut self.state {
+            State::Paused | State::Stopped => false,
+            State::Head => match self.parser.feed(rest) {
+                Ok((used, request)) => {
+                    self.take(used);
+                    match request {
+                        Some(request) => {
+                            self.start(request, found);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                Err(e) => {
+                    self.fail(e, found);
+                    false
+                }
+            },
+            State::Length(left) => {
+                let take = (*left).min(rest.len() as u64);
+                *left -= take;
+                let done = *left == 0;
+                found.push(Found::Chunk(start..start + take as usize));
+                self.take(take as usize);
+                if done {
+                    self.complete(found);
+                }
+                true
+            }
+            State::Chunked(decoder, len) => {
+                let Ok((used, data)) = decoder.step(rest) else {
+                    self.fail(RequestParseError::InvalidFraming, found);
+                    return false;
+                };
+                let done = decoder.is_done();
+                if let Some(data) = data {
+                    *len += data.len() as u64;
+                    if self.max_body_size.is_some_and(|max| *len > max) {
+                        self.fail(RequestParseError::BodyTooLarge, found);
+                        return false;
+                    }
+                    found.push(Found::Chunk(start + data.start..start + data.end));
+                }
+                self.take(used);
+                if done {
+                    self.complete(found);
+                }
+                true
+            }
+        }
+    }
+    fn take(&mut self, len: usize) {
+        self.used += len;
+        self.received += len as u64;
+    }
+    /// Checks a request head and starts reading its body.
+    fn start(&mut self, request: Request, found: &mut Vec<Found>) {
+        let framing = match request.check_host().and_then(|_| framing::request_framing(&request.headers)) {
+            Ok(Framing::Length(len)) if self.max_body_size.is_some_and(|max| len > max) => {
+                Err(RequestParseError::BodyTooLarge)
+            }
+            framing => framing,
+        };
+        let framing = match framing {
+            Ok(framing) => framing,
+            Err(e) => return self.fail(e, found),
+        };
+        self.served += 1;
+        let capped = self.max_requests.is_some_and(|max| self.served >= max);
+        let keep_alive = request.keep_alive() && !capped;
+        let asked = request.headers.get("connection").is_some_and(|v| v.contains_token("keep-alive"));
+        let announce = KeepAlive {
+            // In whole seconds rounded down, so clients give up first, and
+            // left out below one second, which would read as expired
+            timeout: self.idle_timeout
+                .map(|timeout| Duration::from_secs(timeout.as_secs()))
+                .filter(|timeout| !timeout.is_zero()),
+            max: self.max_requests.map(|max| max.saturating_sub(self.served) as u64),
+        };
+        self.pending.push_back(Pending {
+            version: request.version,
+            keep_alive,
+            method: request.method,
+            announce: Some(announce).filter(|a| asked && *a != KeepAlive::default()),
+            start: self.request_start,
+            end: None,
+        });
+        let upgrade = request.method == RequestMethod::Connect || request.headers.contains_key("upgrade");
+        self.after = match () {
+            _ if !keep_alive => After::Stop,
+            _ if upgrade => After::Pause,
+            _ => After::Head,
+        };
+        found.push(Found::Head(request));
+        match framing {
+            Framing::Chunked => self.state = State::Chunked(ChunkedDecoder::default(), 0),
+            // Requests are never close-delimited
+            Framing::Length(0) | Framing::Close => self.complete(found),
+            Framing::Length(len) => self.state = State::Length(len),
+        }
+    }
+    fn complete(&mut self, found: &mut Vec<Found>) {
+        found.push(Found::Complete);
+        // The request may have been answered already
+        if let Some(pending) = self.pending.back_mut().filter(|pending| pending.end.is_none()) {
+            pending.end = Some(self.received);
+        }
+        self.request_start = self.received;
+        self.state = match self.after {
+            After::Head => State::Head,
+            After::Pause => State::Paused,
+            After::Stop => {
+                found.push(Found::Close);
+                State::Stopped
+            }
+        };
+    }
+    fn fail(&mut self, e: RequestParseError, found: &mut Vec<Found>) {
+        found.push(Found::Error(e));
+        found.push(Found::Close);
+        self.close();
+    }
+}
+
+/// Frames `response` to a request of `method` and `version`, see
+/// [Driver::queue_response], and adds `announce` if the connection stays
+/// open and the response has no `keep-alive`. Returns it with whether the
+/// connection stays open, which it does not after a successful `CONNECT`,
+/// which turns it into a tunnel.
+fn prepare<B: AsRef<[u8]>>(
+    mut response: ResponseBuilder<Complete, B>,
+    version: Version,
+    keep_alive: bool,
+    method: RequestMethod,
+    announce: Option<KeepAlive>,
+) -> (ResponseBuilder<Complete, B>, bool) {
+    let version = response.get_version()
+        .unwrap_or_else(|| Version::negotiate(&version, &Version::HTTP_11).unwrap_or(Version::HTTP_11));
+    response = response.version(version);
+    let (code, len) = (response.code(), response.body_len());
+    let headers = response.headers_mut();
+    let tunnel = method == RequestMethod::Connect && (200..300).contains(&code);
+    let has_body = !(100..200).contains(&code) && code != 204 && code != 304 && !tunnel;
+    if has_body && !headers.contains_key("content-length") && !headers.contains_key("transfer-encoding") {
+        headers.insert(Key::CONTENT_LENGTH, Value::new(len.to_string()).unwrap());
+    }
+    if !keep_alive || response.get_header("connection").is_none() {
+        response.set_connection(keep_alive, &version);
+    }
+    let keep_alive = !response.closes_connection() && !tunnel;
+    if let Some(announce) = announce.filter(|_| keep_alive && response.get_header("keep-alive").is_none()) {
+        response.headers_mut().insert(announce.name(), announce.value().unwrap());
+    }
+    (response, keep_alive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Response;
+
+    /// Feeds `input` one byte at a time, answering every request with its
+    /// path and body length.
+    fn bytewise(driver: &mut Driver, input: &[u8]) -> (Vec<String>, Vec<u8>) {
+        let (mut log, mut out) = (Vec::new(), Vec::new());
+        let mut current = None;
+        for byte in input {
+            let mut answers = Vec::new();
+            for event in driver.feed(&[*byte]) {
+                match event {
+                    Event::RequestHeadersComplete(request) => {
+                        log.push(format!("head {}", request.path));
+                        current = Some((request.path, 0));
+                    }
+                    Event::BodyChunk(chunk) => current.as_mut().unwrap().1 += chunk.len(),
+                    Event::RequestComplete => {
+                        log.push("complete".to_owned());
+                        answers.push(current.take().unwrap());
+                    }
+                    Event::NeedsClose => log.push("close".to_owned()),
+                    Event::Error(e) => log.push(format!("error {e}")),
+                }
+            }
+            for (path, len) in answers {
+                let response = Response::Ok.body(format!("{path} {len}"));
+                out.extend(driver.queue_response(&response).unwrap().flatten());
+            }
+        }
+        (log, out)
+    }
+
+    #[test]
+    fn keep_alive_exchange_bytewise() {
+        let mut driver = Driver::new();
+        let (log, out) = bytewise(&mut driver, b"\r\n\
+            POST /a HTTP/1.1\r\nHost: x\r\nContent-Length: 3\r\n\r\nabc\
+            PUT /b HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nab\r\n1;x=y\r\nc\r\n0\r\nT: 1\r\n\r\n\
+            GET /c HTTP/1.0\r\nConnection: keep-alive\r\n\r\n\
+            GET /d HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n\
+            GET /e HTTP/1.1\r\n");
+        assert_eq!(log, [
+            "head /a", "complete", "head /b", "complete", "head /c", "complete", "head /d", "complete", "close",
+        ]);
+        assert_eq!(String::from_utf8(out).unwrap(), "\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/a 3\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n/b 3\
+            HTTP/1.0 200 OK\r\ncontent-length: 4\r\nconnection: keep-alive\r\n\r\n/c 0\
+            HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\n/d 0");
+        assert!(driver.is_closing() && driver.is_closed());
+        assert_eq!(driver.remaining(), b"GET /e HTTP/1.1\r\n");
+        assert_eq!(driver.eof(), None);
+    }
+    #[test]
+    fn pipelined_in_one_feed() {
+        let mut driver = Driver::new();
+        let events = driver.feed(b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nPOST /b HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\nh");
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[3], Event::BodyChunk(b"h"));
+        assert_eq!(driver.feed(b"i"), [Event::BodyChunk(b"i"), Event::RequestComplete]);
+        // Byte counts go to the oldest request not answered yet
+        assert_eq!(driver.request_len(), 28);
+        let _ = driver.queue_response(&Response::Ok.body("")).unwrap();
+        assert_eq!(driver.request_len(), 50);
+        assert!(driver.is_idle());
+    }
+    #[test]
+    fn pauses_for_upgrade() {
+        let mut driver = Driver::new();
+        let upgrade = b"GET /chat HTTP/1.1\r\nHost: x\r\nUpgrade: websocket\r\nConnection: upgrade\r\n\r\n";
+        let events = driver.feed(&[&upgrade[..], b"GET /next HTTP/1.1\r\nHost: x\r\n\r\n"].concat());
+        assert_eq!(events.len(), 2);
+        assert!(!driver.is_idle() && !driver.is_closing());
+        // Declined, so the bytes after are the next request
+        let _ = driver.queue_response(&Response::Ok.body("")).unwrap();
+        assert_eq!(driver.feed(b"").len(), 2);
+        // Accepted, so they are not
+        let mut driver = Driver::new();
+        driver.feed(&[&upgrade[..], b"\x81\x05hello"].concat());
+        let _ = driver.queue_response(&Response::SwitchingProtocols.body("")).unwrap();
+        assert!(driver.feed(b"").is_empty());
+        assert_eq!(driver.remaining(), b"\x81\x05hello");
+    }
+    #[test]
+    fn errors_stop_reading() {
+        let mut driver = Driver::new();
+        assert_eq!(driver.feed(b"GET / HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\n\r\n"),
+            [Event::Error(RequestParseError::MissingHost), Event::NeedsClose]);
+        let out: Vec<u8> = driver.queue_response(&Response::BadRequest.body("")).unwrap().flatten().copied().collect();
+        assert_eq!(out, b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+        let mut driver = Driver::new();
+        driver.feed(b"POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nab");
+        assert_eq!(driver.eof(), Some(RequestParseError::UnexpectedEof { stage: EofStage::Body }));
+        let mut driver = Driver::new();
+        driver.feed(b"\r\n");
+        assert!(driver.is_idle());
+        assert_eq!(driver.eof(), None);
+    }
+    #[test]
+    fn loose_chunk_sizes_refused() {
+        for size in ["+3", " 3", "3 "] {
+            let mut driver = Driver::new();
+            let input = format!("POST / HTTP/1.1\r\nHost: x\r\nTransfer-Encoding: chunked\r\n\r\n{size}\r\nabc\r\n0\r\n\r\n");
+            let events = driver.feed(input.as_bytes());
+            assert!(matches!(events[0], Event::RequestHeadersComplete(_)));
+            assert_eq!(events[1..], [Event::Error(RequestParseError::InvalidFraming), Event::NeedsClose], "{size:?}");
+            let response = RequestParseError::InvalidFraming.appropriate_response().unwrap().body("");
+            let out: Vec<u8> = driver.queue_response(&response).unwrap().flatten().copied().collect();
+            assert_eq!(out, b"HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+        }
+    }
+    #[test]
+    fn framed_and_kept_alive() {
+        let head = |response, version, keep_alive, method| {
+            let (response, open): (ResponseBuilder<Complete>, _) = prepare(response, version, keep_alive, method, None);
+            let mut out = vec![];
+            response.write_head_to(&mut out).unwrap();
+            (String::from_utf8(out).unwrap(), open)
+        };
+        let framed = head(Response::Ok.body("hi"), Version::HTTP_11, true, RequestMethod::Get);
+        assert_eq!(framed, ("HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n".to_owned(), true));
+        let framed = head(Response::Ok.body("hi"), Version::HTTP_10, true, RequestMethod::Head);
+        assert_eq!(framed, ("HTTP/1.0 200 OK\r\ncontent-length: 2\r\nconnection: keep-alive\r\n\r\n".to_owned(), true));
+        let framed = head(Response::NoContent.body(""), Version::HTTP_11, false, RequestMethod::Get);
+        assert_eq!(framed, ("HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n".to_owned(), false));
+        let framed = head(Response::Ok.body(""), Version::HTTP_11, true, RequestMethod::Connect);
+        assert_eq!(framed, ("HTTP/1.1 200 OK\r\n\r\n".to_owned(), false));
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
+
+#[derive(Default)]
+/// Values of arbitrary types attached to a [Request][crate::Request] or
+/// [ResponseBuilder][crate::response::ResponseBuilder], at most one per type.
+///
+/// Extensions are local to the process: they take no part in comparisons,
+/// are never serialized, and a clone of a message starts out with none,
+/// as the stored values need not be [Clone].
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::Extensions;
+/// struct UserId(u64);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(UserId(7));
+/// assert_eq!(extensions.get::<UserId>().unwrap().0, 7);
+/// assert!(extensions.get::<String>().is_none());
+/// ```
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Stores `value`, returning the previous value of the same type.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map.insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map.remove(&TypeId::of::<T>())?.downcast().ok().map(|value| *value)
+    }
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Starts out empty, see [Extensions].
+impl Clone for Extensions {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Always equal, see [Extensions].
+impl PartialEq for Extensions {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Extensions").field("len", &self.map.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct UserId(u64);
+    #[derive(Debug, PartialEq)]
+    struct Route(&'static str);
+
+    #[test]
+    fn distinct_types() {
+        let mut extensions = Extensions::new();
+        assert_eq!(extensions.insert(UserId(1)), None);
+        extensions.insert(Route("/users/:id"));
+        assert_eq!(extensions.insert(UserId(2)), Some(UserId(1)));
+        assert_eq!(extensions.get::<UserId>(), Some(&UserId(2)));
+        assert_eq!(extensions.get::<Route>(), Some(&Route("/users/:id")));
+        assert_eq!(extensions.get::<u32>(), None);
+        assert_eq!(extensions.len(), 2);
+    }
+    #[test]
+    fn get_mut_and_remove() {
+        let mut extensions = Extensions::new();
+        extensions.insert(UserId(1));
+        extensions.get_mut::<UserId>().unwrap().0 = 5;
+        assert_eq!(extensions.remove::<UserId>(), Some(UserId(5)));
+        assert!(extensions.is_empty());
+    }
+    #[test]
+    fn not_cloned_or_compared() {
+        let mut extensions = Extensions::new();
+        extensions.insert(UserId(1));
+        assert!(extensions.clone().is_empty());
+        assert_eq!(extensions, Extensions::new());
+    }
+}
@@ -0,0 +1,304 @@
+//! Reading message bodies from a stream, delimited as RFC 9112, section 6.3
+//! describes.
+
+use std::{
+    io::{self, BufRead, Read},
+    ops::Range,
+};
+
+use crate::{
+    header::HeaderMap,
+    request::{RequestParseError, MAX_HEAD_LEN},
+    response::ChunkedError,
+    RequestMethod,
+};
+
+/// Reads a message head, up to and including the empty line ending the
+/// header section, into `head`. Empty lines before the start line are
+/// skipped. Returns `false` if the head does not end within
+/// [MAX_HEAD_LEN] bytes, and fails with
+/// [UnexpectedEof][io::ErrorKind::UnexpectedEof] if the input ends first.
+pub(crate) fn read_head<R: BufRead>(reader: &mut R, head: &mut Vec<u8>) -> io::Result<bool> {
+    loop {
+        let limit = (MAX_HEAD_LEN - head.len()) as u64;
+        if reader.by_ref().take(limit).read_until(b'\n', head)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if matches!(&head[..], b"\n" | b"\r\n") {
+            head.clear();
+        } else if head.ends_with(b"\n\n") || head.ends_with(b"\n\r\n") {
+            return Ok(true);
+        } else if head.len() == MAX_HEAD_LEN {
+            return Ok(false);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// How the end of a body is found.
+pub(crate) enum Framing {
+    /// Exactly this many bytes follow the head
+    Length(u64),
+    /// Chunks up to the last, empty one and a trailer section
+    Chunked,
+    /// Everything up to the end of the connection, for responses only
+    Close,
+}
+
+/// The framing of a request body. Requests without `content-length` or
+/// `transfer-encoding` have none. Both at once, a transfer coding not
+/// ending in `chunked` or a length that is no number are refused, as
+/// they would let the client smuggle a second request past a proxy.
+pub(crate) fn request_framing(headers: &HeaderMap) -> Result<Framing, RequestParseError> {
+    match (headers.get("transfer-encoding"), headers.get("content-length")) {
+        (Some(_), Some(_)) => Err(RequestParseError::InvalidFraming),
+        (Some(codings), None) => match codings.elements().last() {
+            Some(last) if last.eq_ignore_ascii_case("chunked") => Ok(Framing::Chunked),
+            _ => Err(RequestParseError::InvalidFraming),
+        },
+        (None, Some(length)) => length.as_u64().map(Framing::Length).ok_or(RequestParseError::InvalidFraming),
+        (None, None) => Ok(Framing::Length(0)),
+    }
+}
+
+/// The framing of a response body to a request of `method`, `None` if
+/// `content-length` is no number. Answers to `HEAD`, successful answers
+/// to `CONNECT`, 1xx, 204 and 304 responses have no body. A
+/// `transfer-encoding` overrides `content-length`, and without either
+/// the body lasts until the connection closes.
+pub(crate) fn response_framing(method: RequestMethod, code: u16, headers: &HeaderMap) -> Option<Framing> {
+    let connect = method == RequestMethod::Connect && (200..300).contains(&code);
+    if method == RequestMethod::Head || connect || (100..200).contains(&code) || code == 204 || code == 304 {
+        return Some(Framing::Length(0));
+    }
+    match (headers.get("transfer-encoding"), headers.get("content-length")) {
+        (Some(codings), _) => match codings.elements().last() {
+            Some(last) if last.eq_ignore_ascii_case("chunked") => Some(Framing::Chunked),
+            _ => Some(Framing::Close),
+        },
+        (None, Some(length)) => length.as_u64().map(Framing::Length),
+        (None, None) => Some(Framing::Close),
+    }
+}
+
+/// Reads a body delimited by `framing`, dropping any trailer fields.
+///
+/// A body cut short is reported as [UnexpectedEof][io::ErrorKind::UnexpectedEof],
+/// malformed chunks as [InvalidData][io::ErrorKind::InvalidData] wrapping a
+/// [ChunkedError], and a body longer than `limit` as
+/// [InvalidData][io::ErrorKind::InvalidData] wrapping
+/// [BodyTooLarge][RequestParseError::BodyTooLarge] once the limit is
+/// crossed.
+pub(crate) fn read_body<R: BufRead>(reader: &mut R, framing: Framing, limit: Option<u64>) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let too_large = |len: u64| limit.is_some_and(|max| len > max);
+    match framing {
+        Framing::Length(len) if too_large(len) => return Err(invalid(RequestParseError::BodyTooLarge)),
+        Framing::Length(len) => {
+            if reader.by_ref().take(len).read_to_end(&mut body)? as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        Framing::Chunked => {
+            let mut decoder = ChunkedDecoder::default();
+            while !decoder.is_done() {
+                let buf = reader.fill_buf()?;
+                if buf.is_empty() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                let used = decoder.feed(buf, &mut body).map_err(invalid)?;
+                reader.consume(used);
+                if too_large(body.len() as u64) {
+                    return Err(invalid(RequestParseError::BodyTooLarge));
+                }
+            }
+        }
+        Framing::Close => {
+            reader.read_to_end(&mut body)?;
+        }
+    }
+    Ok(body)
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Longest chunk size or trailer line the decoder buffers.
+const MAX_CHUNK_LINE_LEN: usize = 4096;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    /// Reading a chunk size line
+    #[default]
+    Size,
+    /// This many bytes of chunk data are left
+    Data(u64),
+    /// Expecting the line ending after the data
+    DataEnd,
+    /// Reading trailer fields up to the empty line
+    Trailer,
+    Done,
+}
+
+/// The size in a chunk size line, which is `1*HEXDIG` followed by nothing
+/// or by extensions after a `;`, with only spaces and tabs in between.
+///
+/// Anything looser, such as a sign or padding, is read differently by
+/// other parsers and refused.
+pub(crate) fn chunk_size(line: &[u8]) -> Result<u64, ChunkedError> {
+    let digits = line.iter().take_while(|b| b.is_ascii_hexdigit()).count();
+    let (size, rest) = line.split_at(digits);
+    let ext = rest.iter().position(|&b| b != b' ' && b != b'\t').map(|i| &rest[i..]);
+    if size.is_empty() || !(rest.is_empty() || ext.is_some_and(|ext| ext.starts_with(b";"))) {
+        return Err(ChunkedError::InvalidSize);
+    }
+    // Only hex digits, so valid UTF-8; the parse fails on overflow
+    let size = std::str::from_utf8(size).map_err(|_| ChunkedError::InvalidSize)?;
+    u64::from_str_radix(size, 16).map_err(|_| ChunkedError::InvalidSize)
+}
+
+#[derive(Debug, Default)]
+/// Decodes a chunked body fed in pieces, dropping chunk extensions and,
+/// unless asked to keep them, trailer fields.
+pub(crate) struct ChunkedDecoder {
+    state: ChunkState,
+    line: Vec<u8>,
+    /// The trailer field lines, if kept
+    trailers: Option<Vec<Vec<u8>>>,
+}
+
+impl ChunkedDecoder {
+    /// A decoder keeping the trailer field lines, see [trailers][Self::trailers].
+    pub(crate) fn keeping_trailers() -> Self {
+        Self { trailers: Some(Vec::new()), ..Self::default() }
+    }
+    /// Decodes from `bytes` into `body` until the end of the body, and
+    /// returns how many bytes were used.
+    pub(crate) fn feed(&mut self, bytes: &[u8], body: &mut Vec<u8>) -> Result<usize, ChunkedError> {
+        let mut used = 0;
+        while used < bytes.len() && !self.is_done() {
+            let (taken, data) = self.step(&bytes[used..])?;
+            if let Some(data) = data {
+                body.extend_from_slice(&bytes[used..][data]);
+            }
+            used += taken;
+        }
+        Ok(used)
+    }
+    /// Decodes from `bytes` up to the end of the first piece of chunk data
+    /// in them, and returns how many bytes were used and where that data
+    /// is in `bytes`.
+    pub(crate) fn step(&mut self, bytes: &[u8]) -> Result<(usize, Option<Range<usize>>), ChunkedError> {
+        let mut used = 0;
+        while used < bytes.len() && !self.is_done() {
+            let rest = &bytes[used..];
+            if let ChunkState::Data(left) = self.state {
+                let take = left.min(rest.len() as u64) as usize;
+                self.state = match left - take as u64 {
+                    0 => ChunkState::DataEnd,
+                    left => ChunkState::Data(left),
+                };
+                return Ok((used + take, Some(used..used + take)));
+            }
+            let line_end = rest.iter().position(|&b| b == b'\n');
+            let take = line_end.map_or(rest.len(), |i| i + 1);
+            self.line.extend_from_slice(&rest[..take]);
+            used += take;
+            if self.line.len() > MAX_CHUNK_LINE_LEN {
+                return Err(ChunkedError::InvalidSize);
+            }
+            if line_end.is_none() {
+                continue;
+            }
+            let mut line = std::mem::take(&mut self.line);
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+            self.state = match self.state {
+                ChunkState::Size => match chunk_size(&line)? {
+                    0 => ChunkState::Trailer,
+                    size => ChunkState::Data(size),
+                },
+                ChunkState::DataEnd if line.is_empty() => ChunkState::Size,
+                ChunkState::DataEnd => return Err(ChunkedError::MissingLineEnd),
+                ChunkState::Trailer if line.is_empty() => ChunkState::Done,
+                ChunkState::Trailer => {
+                    if let Some(trailers) = &mut self.trailers {
+                        trailers.push(line);
+                    }
+                    ChunkState::Trailer
+                }
+                state => state,
+            };
+        }
+        Ok((used, None))
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        self.state == ChunkState::Done
+    }
+    /// The trailer field lines read so far, without their line endings.
+    pub(crate) fn trailers(&self) -> &[Vec<u8>] {
+        self.trailers.as_deref().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::header::{Key, Value};
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.insert(Key::new(*k).unwrap(), Value::new(v).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn chunks_and_trailers() {
+        let mut stream = &b"4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nExpires: never\r\n\r\nnext"[..];
+        assert_eq!(read_body(&mut stream, Framing::Chunked, None).unwrap(), b"Wikipedia");
+        assert_eq!(stream, b"next");
+        let mut stream = &b"4\r\nWikiX\r\n0\r\n\r\n"[..];
+        assert_eq!(read_body(&mut stream, Framing::Chunked, None).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let mut stream = &b"4\r\nWi"[..];
+        assert_eq!(read_body(&mut stream, Framing::Chunked, None).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+    #[test]
+    fn chunks_split_at_every_byte() {
+        let input = b"4;ext=1\r\nWiki\r\n5\r\npedia\r\n0\r\nExpires: never\r\n\r\n";
+        for split in 0..input.len() {
+            let (mut decoder, mut body) = (ChunkedDecoder::default(), vec![]);
+            assert_eq!(decoder.feed(&input[..split], &mut body), Ok(split));
+            assert_eq!(decoder.feed(&input[split..], &mut body), Ok(input.len() - split));
+            assert!(decoder.is_done());
+            assert_eq!(body, b"Wikipedia");
+        }
+    }
+    #[test]
+    fn strict_chunk_sizes() {
+        for line in ["3", "03", "3;ext=1", "3 ;ext", "3\t; ext"] {
+            assert_eq!(chunk_size(line.as_bytes()), Ok(3), "{line:?}");
+        }
+        for line in ["+3", " 3", "3 ", "0x3", "3_", "", ";ext", "10000000000000000"] {
+            assert_eq!(chunk_size(line.as_bytes()), Err(ChunkedError::InvalidSize), "{line:?}");
+        }
+        let mut decoder = ChunkedDecoder::default();
+        assert_eq!(decoder.feed(b"+3\r\nabc\r\n0\r\n\r\n", &mut vec![]), Err(ChunkedError::InvalidSize));
+        let mut stream = &b"3 \r\nabc\r\n0\r\n\r\n"[..];
+        assert_eq!(read_body(&mut stream, Framing::Chunked, None).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+    #[test]
+    fn smuggling_refused() {
+        let framing = |pairs: &[(&str, &str)]| request_framing(&headers(pairs));
+        assert_eq!(framing(&[]), Ok(Framing::Length(0)));
+        assert_eq!(framing(&[("content-length", "12")]), Ok(Framing::Length(12)));
+        assert_eq!(framing(&[("transfer-encoding", "gzip, Chunked")]), Ok(Framing::Chunked));
+        assert_eq!(framing(&[("transfer-encoding", "chunked, gzip")]), Err(RequestParseError::InvalidFraming));
+        assert_eq!(framing(&[("transfer-encoding", "chunked"), ("content-length", "3")]), Err(RequestParseError::InvalidFraming));
+        assert_eq!(framing(&[("content-length", "-1")]), Err(RequestParseError::InvalidFraming));
+    }
+}
@@ -0,0 +1,151 @@
+//! [Arbitrary] impls and raw input generators for fuzzing a server built
+//! on this crate. Enabled by the `fuzzing` feature.
+//!
+//! The impls for validated types only produce valid values, so they
+//! exercise the code behind the parser. [RawRequest] and [RawKey] produce
+//! input for the validation itself.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{header::{Key, Value}, RequestMethod, Version};
+
+/// Characters allowed in a token (RFC 9110, section 5.6.2).
+const TCHARS: &[u8] = b"!#$%&'*+-.^_`|~0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+impl<'a> Arbitrary<'a> for RequestMethod {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        use RequestMethod::*;
+        u.choose(&[Get, Head, Post, Put, Delete, Connect, Options, Trace]).copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for Version {
+    /// Mostly HTTP/1.x, which parsing accepts, then versions near it and
+    /// sometimes any pair of numbers.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.ratio(1, 8)? {
+            Ok(Version(u.arbitrary()?, u.arbitrary()?))
+        } else {
+            Ok(Version(*u.choose(&[1, 1, 1, 0, 2, 3])?, u.int_in_range(0..=9)?))
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for Key {
+    /// A token of 1 to 32 characters.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(1..=32)?;
+        let key = (0..len).map(|_| u.choose(TCHARS).map(|&c| c as char)).collect::<Result<String>>()?;
+        Ok(Key::new(key).expect("tokens are valid keys"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Value {
+    /// Visible ascii of 1 to 64 characters, with spaces and tabs inside.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let len = u.int_in_range(1..=64)?;
+        let mut value = String::with_capacity(len);
+        for i in 0..len {
+            let inner = i != 0 && i != len - 1;
+            value.push(match u.int_in_range(0..=19)? {
+                0 if inner => ' ',
+                1 if inner => '\t',
+                _ => u.int_in_range(0x21..=0x7eu8)? as char,
+            });
+        }
+        Ok(Value::new(value).expect("trimmed visible ascii is a valid value"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A possibly invalid header name, for testing [Key::new].
+pub struct RawKey(pub String);
+impl<'a> Arbitrary<'a> for RawKey {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.ratio(1, 2)? {
+            Ok(Self(Key::arbitrary(u)?.into()))
+        } else {
+            Ok(Self(u.arbitrary()?))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The bytes of a request head, for testing the request parser.
+///
+/// Built from a well-formed request line and header section, which is
+/// then corrupted in some cases: bytes are flipped, inserted or removed,
+/// and line endings mangled.
+pub struct RawRequest(pub Vec<u8>);
+impl<'a> Arbitrary<'a> for RawRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let method = RequestMethod::arbitrary(u)?;
+        let path = format!("/{}", Key::arbitrary(u)?);
+        let version = Version::arbitrary(u)?;
+        let mut bytes = format!("{method} {path} {}\r\n", version.http_string()).into_bytes();
+        for _ in 0..u.int_in_range(0..=8)? {
+            let (k, v) = <(Key, Value)>::arbitrary(u)?;
+            bytes.extend(format!("{k}: {v}\r\n").into_bytes());
+        }
+        bytes.extend(b"\r\n");
+
+        if u.ratio(1, 2)? {
+            for _ in 0..u.int_in_range(1..=4)? {
+                let at = u.choose_index(bytes.len())?;
+                match u.int_in_range(0..=3)? {
+                    0 => bytes[at] ^= u.arbitrary::<u8>()?,
+                    1 => bytes.insert(at, *u.choose(b"\r\n: \t\0\x7f\xff")?),
+                    2 => {
+                        bytes.remove(at);
+                    }
+                    _ => bytes.truncate(at),
+                }
+                if bytes.is_empty() {
+                    break;
+                }
+            }
+        }
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Request;
+    use super::*;
+
+    /// Deterministic pseudo random input for the generators.
+    fn input(seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..1024).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn generated_keys_and_values_are_valid() {
+        for seed in 0..100 {
+            let data = input(seed);
+            let mut u = Unstructured::new(&data);
+            let key = Key::arbitrary(&mut u).unwrap();
+            assert!(key.as_str().bytes().all(|b| TCHARS.contains(&b)));
+            let value = Value::arbitrary(&mut u).unwrap();
+            assert_eq!(value.as_bytes().trim_ascii(), value.as_bytes());
+        }
+    }
+    #[test]
+    fn parsed_requests_round_trip() {
+        let mut parsed = 0;
+        for seed in 0..200 {
+            let data = input(seed);
+            let RawRequest(bytes) = RawRequest::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let Ok(request) = Request::try_from(bytes.as_slice()) else {
+                continue;
+            };
+            parsed += 1;
+            assert_eq!(request.to_string().parse::<Request>().unwrap(), request);
+        }
+        assert!(parsed > 50);
+    }
+}
@@ -0,0 +1,189 @@
+//! Handlers answering requests and middlewares layered around them.
+//!
+//! Any `Fn(Request) -> ResponseBuilder<Complete>` is a [Handler], and so
+//! are a [Router][crate::router::Router] and a [Stack] of middlewares.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::net::TcpListener;
+//! # use heggemann_http::{handler::{AccessLog, SecurityHeaders, Stack}, response::SecurityPreset, server, Response};
+//! let stack = Stack::new(|_| Response::Ok.body("hello"))
+//!     .layer(SecurityHeaders(SecurityPreset::default()))
+//!     .layer(AccessLog::new(|line: &str| eprintln!("{line}")));
+//! server::serve(TcpListener::bind("127.0.0.1:8080").unwrap(), stack).unwrap();
+//! ```
+
+use std::time::SystemTime;
+
+use crate::{
+    response::{Complete, ResponseBuilder, SecurityPreset},
+    server::{self, log, Peer},
+    Request,
+    RequestMethod,
+    ResponseCode,
+};
+
+/// Answers a request.
+///
+/// A closure passed where a handler is expected needs its parameter typed,
+/// as in `|request: Request| ...`, unless it ignores the request.
+pub trait Handler {
+    fn handle(&self, request: Request) -> ResponseBuilder<Complete>;
+}
+
+impl<F: Fn(Request) -> ResponseBuilder<Complete>> Handler for F {
+    fn handle(&self, request: Request) -> ResponseBuilder<Complete> {
+        self(request)
+    }
+}
+
+/// Runs around a [Handler], deciding whether and with what request to call
+/// `next` and what to make of its response.
+pub trait Middleware {
+    fn wrap(&self, request: Request, next: &dyn Handler) -> ResponseBuilder<Complete>;
+}
+
+impl<F: Fn(Request, &dyn Handler) -> ResponseBuilder<Complete>> Middleware for F {
+    fn wrap(&self, request: Request, next: &dyn Handler) -> ResponseBuilder<Complete> {
+        self(request, next)
+    }
+}
+
+/// A handler with middlewares layered around it.
+///
+/// Each [layer][Stack::layer] wraps everything added before, so the last
+/// one added sees the request first and the response last.
+pub struct Stack {
+    /// Innermost first
+    middlewares: Vec<Box<dyn Middleware + Send + Sync>>,
+    handler: Box<dyn Handler + Send + Sync>,
+}
+
+impl Stack {
+    pub fn new<H: Handler + Send + Sync + 'static>(handler: H) -> Self {
+        Self { middlewares: Vec::new(), handler: Box::new(handler) }
+    }
+    /// Wraps `middleware` around the handler and the layers so far.
+    pub fn layer<M: Middleware + Send + Sync + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+}
+
+impl Handler for Stack {
+    fn handle(&self, request: Request) -> ResponseBuilder<Complete> {
+        Next { middlewares: &self.middlewares, handler: &*self.handler }.handle(request)
+    }
+}
+
+/// The layers below a middleware, as it sees them.
+struct Next<'a> {
+    middlewares: &'a [Box<dyn Middleware + Send + Sync>],
+    handler: &'a dyn Handler,
+}
+
+impl Handler for Next<'_> {
+    fn handle(&self, request: Request) -> ResponseBuilder<Complete> {
+        match self.middlewares.split_last() {
+            Some((outer, inner)) => outer.wrap(request, &Next { middlewares: inner, handler: self.handler }),
+            None => self.handler.handle(request),
+        }
+    }
+}
+
+/// Passes a [combined][log::format_combined] access log line per request
+/// to a sink, like [Server::access_log][server::Server::access_log].
+///
+/// The client address is taken from the [Peer] the server stores in the
+/// request, and logged as `-` without one.
+pub struct AccessLog<F> {
+    sink: F,
+}
+
+impl<F: Fn(&str)> AccessLog<F> {
+    pub fn new(sink: F) -> Self {
+        Self { sink }
+    }
+}
+
+impl<F: Fn(&str)> Middleware for AccessLog<F> {
+    fn wrap(&self, request: Request, next: &dyn Handler) -> ResponseBuilder<Complete> {
+        let when = SystemTime::now();
+        let peer = request.extensions.get::<Peer>().map(|peer| peer.0);
+        let head = server::head_of(&request);
+        let response = next.handle(request);
+        let sent = if head.method == RequestMethod::Head { 0 } else { response.body_len() };
+        (self.sink)(&log::format_combined(peer.as_ref(), &head, response.code(), sent as u64, when));
+        response
+    }
+}
+
+/// Adds the headers of a [SecurityPreset] to every response, see
+/// [with_security_headers][ResponseBuilder::with_security_headers].
+pub struct SecurityHeaders(pub SecurityPreset);
+
+impl Middleware for SecurityHeaders {
+    fn wrap(&self, request: Request, next: &dyn Handler) -> ResponseBuilder<Complete> {
+        next.handle(request).with_security_headers(self.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::Response;
+
+    /// A middleware recording when it sees the request and the response.
+    fn trace(name: &'static str, events: Arc<Mutex<Vec<String>>>) -> impl Middleware {
+        move |request: Request, next: &dyn Handler| {
+            events.lock().unwrap().push(format!("{name} request"));
+            let response = next.handle(request);
+            events.lock().unwrap().push(format!("{name} response"));
+            response
+        }
+    }
+
+    #[test]
+    fn outermost_sees_response_last() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handled = Arc::clone(&events);
+        let stack = Stack::new(move |_| {
+            handled.lock().unwrap().push("handler".to_owned());
+            Response::Ok.body("")
+        })
+            .layer(trace("inner", Arc::clone(&events)))
+            .layer(trace("outer", Arc::clone(&events)));
+        stack.handle("GET / HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap());
+        assert_eq!(*events.lock().unwrap(), [
+            "outer request", "inner request", "handler", "inner response", "outer response",
+        ]);
+    }
+    #[test]
+    fn short_circuit() {
+        let deny = |request: Request, next: &dyn Handler| match request.headers.get("authorization") {
+            Some(_) => next.handle(request),
+            None => Response::Unauthorized.body(""),
+        };
+        let stack = Stack::new(|_| Response::Ok.body("secret")).layer(deny);
+        assert_eq!(stack.handle("GET / HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap()).code(), 401);
+        let authorized = "GET / HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer t\r\n\r\n".parse().unwrap();
+        assert_eq!(stack.handle(authorized).get_body(), b"secret");
+    }
+    #[test]
+    fn shipped_middlewares() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let stack = Stack::new(|_| Response::Ok.body("hello"))
+            .layer(SecurityHeaders(SecurityPreset::default()))
+            .layer(AccessLog::new(move |line: &str| sink.lock().unwrap().push(line.to_owned())));
+        let mut request: Request = "GET /page HTTP/1.1\r\nHost: x\r\nUser-Agent: t\r\n\r\n".parse().unwrap();
+        request.extensions.insert(Peer("192.0.2.1:4000".parse().unwrap()));
+        let response = stack.handle(request);
+        assert_eq!(response.get_header("x-content-type-options").unwrap(), "nosniff");
+        let lines = lines.lock().unwrap();
+        assert!(lines[0].starts_with("192.0.2.1 - - ["));
+        assert!(lines[0].ends_with(r#"] "GET /page HTTP/1.1" 200 5 "-" "t""#));
+    }
+}
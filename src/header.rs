@@ -3,25 +3,191 @@ use std::{
     fmt::{Display, Formatter, Result as FmtResult},
 };
 
+pub mod authorization;
+pub mod cache_control;
+pub mod challenge;
+pub mod content_disposition;
+pub mod cookie;
+pub mod date;
+pub mod etag;
+pub mod keep_alive;
 pub mod key;
+pub mod link;
+pub mod media_type;
+pub mod quoted;
+pub mod qvalue;
+pub mod range;
+mod registry;
+pub mod te;
+pub mod upgrade;
 pub mod value;
+pub mod via;
+mod map;
 
+pub use authorization::{Authorization, Credentials};
+pub use cache_control::CacheControl;
+pub use challenge::Challenge;
+pub use content_disposition::ContentDisposition;
+pub use cookie::{SameSite, SetCookie};
+pub use etag::EntityTag;
+pub use keep_alive::KeepAlive;
 pub use key::Key;
-pub use value::Value;
+pub use link::Link;
+pub use map::HeaderMap;
+pub use media_type::{MediaRange, MediaType};
+pub use qvalue::QValue;
+pub use range::{ByteRange, ContentRange, Unsatisfiable};
+pub use registry::HeaderInfo;
+pub use te::{Te, TransferCoding};
+pub use upgrade::Protocol;
+pub use value::{Value, ValueLimits};
+pub use via::{append_via, Via, ViaEntry};
+
+/// Longest [Key] that [Key::new] accepts, in bytes.
+pub const MAX_KEY_LEN: usize = 1024;
+/// Longest [Value] that [Value::new] accepts and that appending can grow,
+/// in bytes.
+pub const MAX_VALUE_LEN: usize = 16 * 1024;
+/// Most list members that appending can grow a [Value] to.
+pub const MAX_VALUE_ELEMENTS: usize = 256;
+
+/// A structured header value that knows its field name.
+///
+/// Typed values are well-formed by construction, but serializing them
+/// still fails if the result is longer than [MAX_VALUE_LEN].
+pub trait ToHeader {
+    fn name(&self) -> Key;
+    fn value(&self) -> Result<Value, ValueError>;
+}
+
+impl ToHeader for EntityTag {
+    fn name(&self) -> Key {
+        Key::ETAG
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for ContentRange {
+    fn name(&self) -> Key {
+        Key::CONTENT_RANGE
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for Challenge {
+    fn name(&self) -> Key {
+        Key::WWW_AUTHENTICATE
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for SetCookie {
+    fn name(&self) -> Key {
+        Key::SET_COOKIE
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for Link {
+    fn name(&self) -> Key {
+        Key::LINK
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for Authorization {
+    fn name(&self) -> Key {
+        Key::AUTHORIZATION
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for Via {
+    fn name(&self) -> Key {
+        Key::VIA
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for KeepAlive {
+    fn name(&self) -> Key {
+        Key::KEEP_ALIVE
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for ContentDisposition {
+    fn name(&self) -> Key {
+        Key::CONTENT_DISPOSITION
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+impl ToHeader for CacheControl {
+    fn name(&self) -> Key {
+        Key::CACHE_CONTROL
+    }
+    fn value(&self) -> Result<Value, ValueError> {
+        Value::new(self.to_string())
+    }
+}
+
+/// Whether `s` is a non-empty RFC 9110 token, as used for header names,
+/// methods and parameter names.
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+/// Whether `b` may appear in a token.
+pub(crate) const fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'!' | b'#' | b'$' | b'%' | b'&' | b'\''
+        | b'*' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~')
+}
 
 #[derive(PartialEq, Debug)]
+#[non_exhaustive]
 pub enum HeaderError {
     Key(KeyError),
-    Value(ValueError),
+    /// The value is invalid; `key` is the field it was given for, if known
+    Value { key: Option<Key>, error: ValueError },
     MissingKey,
-    MissingValue
+    MissingValue,
+    /// The field may not be sent in a trailer section, as it affects
+    /// framing, routing, authentication or the interpretation of the content.
+    IllegalTrailer,
+    /// The request did not announce `te: trailers`
+    TrailersNotAccepted,
+    /// The header at this position of a bulk insertion is invalid
+    AtIndex(usize, Box<HeaderError>),
+    /// A field that may occur only once, see [Key::is_singleton], is
+    /// already present
+    Duplicate(Key),
+}
+impl HeaderError {
+    /// Names `key` as the field of a value error that has none yet.
+    pub(crate) fn for_key(self, k: &Key) -> Self {
+        match self {
+            Self::Value { key: None, error } => Self::Value { key: Some(k.clone()), error },
+            e => e,
+        }
+    }
 }
 impl Error for HeaderError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             Self::Key(e) => Some(e),
-            Self::Value(e) => Some(e),
-            Self::MissingValue | Self::MissingKey => None
+            Self::Value { error, .. } => Some(error),
+            Self::AtIndex(_, e) => Some(e.as_ref()),
+            Self::MissingValue | Self::MissingKey | Self::IllegalTrailer | Self::TrailersNotAccepted | Self::Duplicate(_) => None
         }
     }
 }
@@ -29,9 +195,14 @@ impl Display for HeaderError {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         let (v, error) = match self {
             Self::Key(e) => ("Key", e.to_string()),
-            Self::Value(e) => ("Value", e.to_string()),
+            Self::Value { key: Some(k), error } => return write!(f, "Value of {k}: {error}"),
+            Self::Value { key: None, error } => ("Value", error.to_string()),
             Self::MissingKey => ("Header", "missing key".to_string()),
-            Self::MissingValue => ("Header", "missing value".to_string())
+            Self::MissingValue => ("Header", "missing value".to_string()),
+            Self::IllegalTrailer => ("Header", "not allowed in trailers".to_string()),
+            Self::TrailersNotAccepted => ("Header", "client does not accept trailers".to_string()),
+            Self::AtIndex(i, e) => return write!(f, "Header {i}: {e}"),
+            Self::Duplicate(k) => ("Header", format!("duplicate {k}")),
         };
         write!(f, "{v}: {error}")
     }
@@ -45,40 +216,312 @@ impl From<KeyError> for HeaderError {
 
 impl From<ValueError> for HeaderError {
     fn from(value: ValueError) -> Self {
-        Self::Value(value)
+        Self::Value { key: None, error: value }
+    }
+}
+
+/// Offending input kept in errors is cut to this many characters.
+const EXCERPT_LEN: usize = 64;
+
+/// `s` cut to [EXCERPT_LEN] characters, marking the cut with `...`.
+pub(crate) fn excerpt(s: &str) -> String {
+    match s.char_indices().nth(EXCERPT_LEN) {
+        Some((end, _)) => format!("{}...", &s[..end]),
+        None => s.to_owned(),
     }
 }
 
+/// Position of the first byte of `s` that is not ASCII.
+fn first_non_ascii(s: &[u8]) -> usize {
+    s.iter().position(|b| !b.is_ascii()).unwrap_or_default()
+}
+
 #[derive(PartialEq, Debug)]
+#[non_exhaustive]
 pub enum KeyError {
-    NonAsciiChars,
+    /// A non-ascii character starting at byte `at` of `input`
+    NonAsciiChars { input: String, at: usize },
     EmptyString,
-    HeaderNameWhitespace
+    HeaderNameWhitespace { input: String },
+    /// A character that is not allowed in a token, such as a space,
+    /// a colon or a delimiter
+    IllegalChar { input: String, found: char, at: usize },
+    /// The key is `len` bytes long, more than the `limit`
+    TooLong { len: usize, limit: usize },
+}
+impl KeyError {
+    pub(crate) fn non_ascii(input: &[u8]) -> Self {
+        Self::NonAsciiChars {
+            input: excerpt(&String::from_utf8_lossy(input)),
+            at: first_non_ascii(input),
+        }
+    }
 }
 impl Error for KeyError{}
 impl Display for KeyError {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}", match self {
-            Self::NonAsciiChars => "non-ascii chars",
-            Self::EmptyString => "empty key",
-            Self::HeaderNameWhitespace => "leading or trailing whitespace",
-        })
+        match self {
+            Self::NonAsciiChars { input, at } => write!(f, "non-ascii chars at {at} in {input:?}"),
+            Self::EmptyString => write!(f, "empty key"),
+            Self::HeaderNameWhitespace { input } => write!(f, "leading or trailing whitespace in {input:?}"),
+            Self::IllegalChar { input, found, at } => write!(f, "illegal character {found:?} at {at} in {input:?}"),
+            Self::TooLong { len, limit } => write!(f, "key of {len} bytes exceeds the limit of {limit}"),
+        }
     }
 }
 
 #[derive(PartialEq, Debug)]
+#[non_exhaustive]
 pub enum ValueError {
-    NonAsciiChars,
+    /// A non-ascii character starting at byte `at` of `input`
+    NonAsciiChars { input: String, at: usize },
     EmptyString,
-    IllegalChars,
+    /// A character that is not allowed here, at byte `at` of `input`
+    IllegalChars { input: String, found: char, at: usize },
+    /// A `content-length` that is not a single decimal number
+    InvalidContentLength { input: String },
+    /// The value is `len` bytes long, more than the `limit`
+    TooLong { len: usize, limit: usize },
+    /// Appending would make a list of `count` members, more than the `limit`
+    TooManyElements { count: usize, limit: usize },
+}
+impl ValueError {
+    pub(crate) fn non_ascii(input: &[u8]) -> Self {
+        Self::NonAsciiChars {
+            input: excerpt(&String::from_utf8_lossy(input)),
+            at: first_non_ascii(input),
+        }
+    }
+    /// `input` has the illegal ascii character at byte `at`.
+    pub(crate) fn illegal_char(input: &[u8], at: usize) -> Self {
+        Self::IllegalChars {
+            input: excerpt(&String::from_utf8_lossy(input)),
+            found: input[at] as char,
+            at,
+        }
+    }
+    /// The error for a string that is not a token.
+    pub(crate) fn not_token(input: &str) -> Self {
+        if !input.is_ascii() {
+            return Self::non_ascii(input.as_bytes());
+        }
+        match input.bytes().position(|b| !is_tchar(b)) {
+            Some(at) => Self::illegal_char(input.as_bytes(), at),
+            None => Self::EmptyString,
+        }
+    }
+    pub(crate) fn invalid_content_length(input: &str) -> Self {
+        Self::InvalidContentLength { input: excerpt(input) }
+    }
 }
 impl Error for ValueError{}
 impl Display for ValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::NonAsciiChars { input, at } => write!(f, "non-ascii chars at {at} in {input:?}"),
+            Self::EmptyString => write!(f, "empty value"),
+            Self::IllegalChars { input, found, at } => write!(f, "illegal character {found:?} at {at} in {input:?}"),
+            Self::InvalidContentLength { input } => write!(f, "content-length {input:?} is not a decimal number"),
+            Self::TooLong { len, limit } => write!(f, "value of {len} bytes exceeds the limit of {limit}"),
+            Self::TooManyElements { count, limit } => write!(f, "list of {count} members exceeds the limit of {limit}"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum EntityTagError {
+    IllegalChars,
+    Unquoted,
+}
+impl Error for EntityTagError{}
+impl Display for EntityTagError {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", match self {
-            Self::NonAsciiChars => "non-ascii chars",
-            Self::EmptyString => "empty value",
-            Self::IllegalChars => "illegal characters (\\r, \\n or \\0)",
+            Self::IllegalChars => "illegal characters in entity tag",
+            Self::Unquoted => "entity tag not enclosed in quotes",
         })
     }
-}
\ No newline at end of file
+}
+
+#[derive(PartialEq, Debug)]
+pub enum RangeError {
+    /// Only `bytes` ranges are supported
+    UnsupportedUnit,
+    Invalid,
+    /// More than [MAX_RANGES][ByteRange::MAX_RANGES] ranges
+    TooMany,
+}
+impl Error for RangeError{}
+impl Display for RangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::UnsupportedUnit => "unsupported range unit",
+            Self::Invalid => "invalid range",
+            Self::TooMany => "too many ranges",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum LinkError {
+    IllegalChars,
+    Malformed,
+}
+impl Error for LinkError{}
+impl Display for LinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::IllegalChars => "illegal characters in link",
+            Self::Malformed => "malformed link header",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum DateError {
+    /// Not in any of the HTTP-date formats
+    Malformed,
+    /// A day or time that does not exist, or a date before 1970
+    OutOfRange,
+}
+impl Error for DateError{}
+impl Display for DateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Malformed => "malformed HTTP-date",
+            Self::OutOfRange => "HTTP-date out of range",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum QuotedError {
+    /// Not a quoted string or token, or followed by more input
+    Malformed,
+    /// The closing quote is missing
+    Unterminated,
+    /// A control character other than horizontal tab
+    IllegalChars,
+}
+impl Error for QuotedError{}
+impl Display for QuotedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Malformed => "malformed quoted string",
+            Self::Unterminated => "unterminated quoted string",
+            Self::IllegalChars => "control character in quoted string",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum QValueError {
+    /// Not a decimal number
+    Malformed,
+    /// Above 1
+    OutOfRange,
+    /// More than three decimals
+    TooPrecise,
+}
+impl Error for QValueError{}
+impl Display for QValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Malformed => "malformed qvalue",
+            Self::OutOfRange => "qvalue above 1",
+            Self::TooPrecise => "qvalue with more than three decimals",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum MediaTypeError {
+    /// Not `type/subtype` followed by parameters
+    Malformed,
+    /// A parameter name that is not a token, or a value with control or
+    /// non-ascii characters
+    IllegalChars,
+    /// The weight of a media range is invalid
+    QValue(QValueError),
+}
+impl From<QValueError> for MediaTypeError {
+    fn from(e: QValueError) -> Self {
+        Self::QValue(e)
+    }
+}
+impl Error for MediaTypeError{}
+impl Display for MediaTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Malformed => write!(f, "malformed media type"),
+            Self::IllegalChars => write!(f, "illegal characters in media type parameter"),
+            Self::QValue(e) => write!(f, "invalid media range weight: {e}"),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum CacheControlError {
+    /// Not a list of directives
+    Malformed,
+    /// A directive argument that should be seconds is not a number
+    InvalidSeconds,
+}
+impl Error for CacheControlError{}
+impl Display for CacheControlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Malformed => "malformed cache-control",
+            Self::InvalidSeconds => "cache-control argument is not a number of seconds",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum AuthorizationError {
+    /// Not a scheme followed by a token68 or a list of parameters
+    Malformed,
+    /// The credentials do not fit the `Basic` or `Bearer` scheme
+    InvalidCredentials,
+}
+impl Error for AuthorizationError{}
+impl Display for AuthorizationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Malformed => "malformed authorization",
+            Self::InvalidCredentials => "invalid credentials for the scheme",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum DispositionError {
+    IllegalChars,
+    Malformed,
+}
+impl Error for DispositionError{}
+impl Display for DispositionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::IllegalChars => "illegal characters in content-disposition",
+            Self::Malformed => "malformed content-disposition",
+        })
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum CookieError {
+    InvalidName,
+    InvalidValue,
+    InvalidAttribute,
+}
+impl Error for CookieError{}
+impl Display for CookieError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::InvalidName => "cookie name is not a token",
+            Self::InvalidValue => "illegal characters in cookie value",
+            Self::InvalidAttribute => "illegal characters in cookie attribute",
+        })
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use crate::base64;
+use super::{is_token, quoted, AuthorizationError};
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// The credentials of an `authorization` header.
+///
+/// Schemes are matched ignoring case. Values are written as given, so a
+/// bearer token or [Credentials] built by hand have to follow the syntax
+/// of the scheme; parsed values always do.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::Authorization;
+/// let basic = Authorization::Basic { user: "Aladdin".to_owned(), password: "open sesame".to_owned() };
+/// assert_eq!(basic.to_string(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+/// assert_eq!("basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".parse(), Ok(basic));
+/// ```
+pub enum Authorization {
+    /// The `Basic` scheme of RFC 7617, decoded as UTF-8
+    Basic { user: String, password: String },
+    /// The `Bearer` scheme of RFC 6750
+    Bearer(String),
+    /// Any other scheme, such as `Digest`
+    Other { scheme: String, params_or_token: Credentials },
+}
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// The credentials following the scheme of an [Authorization::Other].
+pub enum Credentials {
+    /// A single token, such as base64 data
+    Token68(String),
+    /// Named parameters, with quoted strings unescaped
+    Params(Vec<(String, String)>),
+}
+impl Credentials {
+    /// The value of the first parameter called `name`, ignoring case.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        match self {
+            Self::Token68(_) => None,
+            Self::Params(params) => params.iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str()),
+        }
+    }
+    fn parse(s: &str) -> Result<Self, AuthorizationError> {
+        if is_token68(s) {
+            return Ok(Self::Token68(s.to_owned()));
+        }
+        let mut params = vec![];
+        let mut rest = s;
+        loop {
+            rest = rest.trim_start_matches([' ', '\t', ',']);
+            if rest.is_empty() {
+                return Ok(Self::Params(params));
+            }
+            let (name, tail) = rest.split_once('=').ok_or(AuthorizationError::Malformed)?;
+            let name = name.trim_end();
+            if !is_token(name) {
+                return Err(AuthorizationError::Malformed);
+            }
+            let (value, tail) = quoted::parse_token_or_quoted(tail.trim_start())
+                .map_err(|_| AuthorizationError::Malformed)?;
+            params.push((name.to_owned(), value));
+            rest = tail.trim_start();
+            if !rest.is_empty() && !rest.starts_with(',') {
+                return Err(AuthorizationError::Malformed);
+            }
+        }
+    }
+}
+
+/// Whether `s` is a token68, the syntax of base64-like credentials.
+fn is_token68(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    !data.is_empty() && data.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b))
+}
+
+impl FromStr for Authorization {
+    type Err = AuthorizationError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (scheme, rest) = s.split_once(' ').unwrap_or((s, ""));
+        if !is_token(scheme) {
+            return Err(AuthorizationError::Malformed);
+        }
+        let credentials = Credentials::parse(rest.trim_start())?;
+        if scheme.eq_ignore_ascii_case("basic") {
+            let Credentials::Token68(token) = credentials else {
+                return Err(AuthorizationError::InvalidCredentials);
+            };
+            let decoded = base64::decode(&token)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or(AuthorizationError::InvalidCredentials)?;
+            let (user, password) = decoded.split_once(':').ok_or(AuthorizationError::InvalidCredentials)?;
+            Ok(Self::Basic { user: user.to_owned(), password: password.to_owned() })
+        } else if scheme.eq_ignore_ascii_case("bearer") {
+            match credentials {
+                Credentials::Token68(token) => Ok(Self::Bearer(token)),
+                Credentials::Params(_) => Err(AuthorizationError::InvalidCredentials),
+            }
+        } else {
+            Ok(Self::Other { scheme: scheme.to_owned(), params_or_token: credentials })
+        }
+    }
+}
+impl Display for Authorization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Basic { user, password } => write!(f, "Basic {}", base64::encode(format!("{user}:{password}").as_bytes())),
+            Self::Bearer(token) => write!(f, "Bearer {token}"),
+            Self::Other { scheme, params_or_token: Credentials::Token68(token) } => write!(f, "{scheme} {token}"),
+            Self::Other { scheme, params_or_token: Credentials::Params(params) } => {
+                write!(f, "{scheme}")?;
+                for (i, (name, value)) in params.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{separator}{name}={}", quoted::quote(value))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic() {
+        let parsed: Authorization = "BASIC dXNlcjpwYTpzcw==".parse().unwrap();
+        assert_eq!(parsed, Authorization::Basic { user: "user".to_owned(), password: "pa:ss".to_owned() });
+        assert_eq!(parsed.to_string(), "Basic dXNlcjpwYTpzcw==");
+        assert_eq!("Basic bm9jb2xvbg==".parse::<Authorization>(), Err(AuthorizationError::InvalidCredentials));
+        assert_eq!("Basic realm=x".parse::<Authorization>(), Err(AuthorizationError::InvalidCredentials));
+    }
+    #[test]
+    fn bearer() {
+        let parsed: Authorization = "bearer mF_9.B5f-4.1JqM".parse().unwrap();
+        assert_eq!(parsed, Authorization::Bearer("mF_9.B5f-4.1JqM".to_owned()));
+        assert_eq!(parsed.to_string(), "Bearer mF_9.B5f-4.1JqM");
+        assert_eq!("Bearer".parse::<Authorization>(), Err(AuthorizationError::InvalidCredentials));
+    }
+    #[test]
+    fn digest_is_other() {
+        let header = r#"Digest username="Mufasa", realm="http-auth@example.org", uri="/dir/index.html", nc=00000001, response="8ca5""#;
+        let parsed: Authorization = header.parse().unwrap();
+        let Authorization::Other { scheme, params_or_token } = &parsed else {
+            panic!("{parsed:?}");
+        };
+        assert_eq!(scheme, "Digest");
+        assert_eq!(params_or_token.get_param("USERNAME"), Some("Mufasa"));
+        assert_eq!(params_or_token.get_param("nc"), Some("00000001"));
+        assert_eq!(params_or_token.get_param("uri"), Some("/dir/index.html"));
+        assert_eq!(parsed.to_string().parse::<Authorization>(), Ok(parsed));
+    }
+    #[test]
+    fn other_token68() {
+        let parsed: Authorization = "Negotiate YIIB==".parse().unwrap();
+        assert_eq!(parsed, Authorization::Other {
+            scheme: "Negotiate".to_owned(),
+            params_or_token: Credentials::Token68("YIIB==".to_owned()),
+        });
+        assert_eq!(parsed.to_string(), "Negotiate YIIB==");
+    }
+    #[test]
+    fn reject_malformed() {
+        assert_eq!("".parse::<Authorization>(), Err(AuthorizationError::Malformed));
+        assert_eq!("Digest a=b c".parse::<Authorization>(), Err(AuthorizationError::Malformed));
+        assert_eq!("Digest a=\"open".parse::<Authorization>(), Err(AuthorizationError::Malformed));
+    }
+}
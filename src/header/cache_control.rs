@@ -0,0 +1,227 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{is_token, quoted, CacheControlError, Value};
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// A single `cache-control` directive.
+pub enum Directive {
+    MaxAge(u64),
+    SMaxAge(u64),
+    NoCache,
+    NoStore,
+    NoTransform,
+    MustRevalidate,
+    ProxyRevalidate,
+    Public,
+    Private,
+    Immutable,
+    /// Request directive: a stale response is acceptable, if given, by at
+    /// most that many seconds
+    MaxStale(Option<u64>),
+    /// Request directive: the response has to stay fresh for that many seconds
+    MinFresh(u64),
+    /// Request directive: only a stored response is acceptable
+    OnlyIfCached,
+    /// Any other directive, with its name in lowercase and the argument
+    /// unquoted
+    Extension { name: String, value: Option<String> },
+}
+impl Directive {
+    /// Reads one directive like `max-age=60`. Seconds may be quoted and
+    /// saturate at [u64::MAX].
+    fn parse(s: &str) -> Result<Self, CacheControlError> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name.trim_end(), Some(value.trim_start())),
+            None => (s, None),
+        };
+        if !is_token(name) {
+            return Err(CacheControlError::Malformed);
+        }
+        let value = match value {
+            Some(v) => match quoted::parse_token_or_quoted(v) {
+                Ok((v, "")) => Some(v),
+                _ => return Err(CacheControlError::Malformed),
+            },
+            None => None,
+        };
+        let name = name.to_ascii_lowercase();
+        let seconds = |v: Option<&String>| match v {
+            Some(v) if !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()) => Ok(v.parse().unwrap_or(u64::MAX)),
+            _ => Err(CacheControlError::InvalidSeconds),
+        };
+        Ok(match (name.as_str(), &value) {
+            ("max-age", v) => Self::MaxAge(seconds(v.as_ref())?),
+            ("s-maxage", v) => Self::SMaxAge(seconds(v.as_ref())?),
+            ("max-stale", None) => Self::MaxStale(None),
+            ("max-stale", v) => Self::MaxStale(Some(seconds(v.as_ref())?)),
+            ("min-fresh", v) => Self::MinFresh(seconds(v.as_ref())?),
+            ("no-cache", None) => Self::NoCache,
+            ("no-store", None) => Self::NoStore,
+            ("no-transform", None) => Self::NoTransform,
+            ("must-revalidate", None) => Self::MustRevalidate,
+            ("proxy-revalidate", None) => Self::ProxyRevalidate,
+            ("public", None) => Self::Public,
+            ("private", None) => Self::Private,
+            ("immutable", None) => Self::Immutable,
+            ("only-if-cached", None) => Self::OnlyIfCached,
+            _ => Self::Extension { name, value },
+        })
+    }
+}
+impl Display for Directive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::MaxAge(secs) => write!(f, "max-age={secs}"),
+            Self::SMaxAge(secs) => write!(f, "s-maxage={secs}"),
+            Self::NoCache => write!(f, "no-cache"),
+            Self::NoStore => write!(f, "no-store"),
+            Self::NoTransform => write!(f, "no-transform"),
+            Self::MustRevalidate => write!(f, "must-revalidate"),
+            Self::ProxyRevalidate => write!(f, "proxy-revalidate"),
+            Self::Public => write!(f, "public"),
+            Self::Private => write!(f, "private"),
+            Self::Immutable => write!(f, "immutable"),
+            Self::MaxStale(None) => write!(f, "max-stale"),
+            Self::MaxStale(Some(secs)) => write!(f, "max-stale={secs}"),
+            Self::MinFresh(secs) => write!(f, "min-fresh={secs}"),
+            Self::OnlyIfCached => write!(f, "only-if-cached"),
+            Self::Extension { name, value: None } => write!(f, "{name}"),
+            Self::Extension { name, value: Some(v) } => write!(f, "{name}={}", quoted::quote(v)),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// The directives of a `cache-control` header. Never empty.
+///
+/// Parsed headers keep every directive in order, including repeated
+/// ones; the accessors like [max_age][CacheControl::max_age] use the
+/// first occurrence, as RFC 9111 recommends. Field-qualified forms like
+/// `no-cache="set-cookie"` are kept as [extensions][Directive::Extension].
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{CacheControl, cache_control::Directive};
+/// let cache = CacheControl::new(Directive::Public)
+///     .with(Directive::MaxAge(3600))
+///     .with(Directive::MustRevalidate);
+/// assert_eq!(cache.to_string(), "public, max-age=3600, must-revalidate");
+/// ```
+pub struct CacheControl(Vec<Directive>);
+impl CacheControl {
+    pub fn new(first: Directive) -> Self {
+        Self(vec![first])
+    }
+    /// Adds another directive. Directives already present are not repeated.
+    pub fn with(mut self, directive: Directive) -> Self {
+        if !self.0.contains(&directive) {
+            self.0.push(directive);
+        }
+        self
+    }
+    pub fn directives(&self) -> &[Directive] {
+        &self.0
+    }
+    /// Parses a `cache-control` value, as sent in requests or responses.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{CacheControl, Value};
+    /// let cache = CacheControl::parse(&Value::new("no-cache, max-age=0, min-fresh=30").unwrap()).unwrap();
+    /// assert!(cache.no_cache());
+    /// assert_eq!(cache.max_age(), Some(0));
+    /// assert_eq!(cache.min_fresh(), Some(30));
+    /// ```
+    pub fn parse(value: &Value) -> Result<Self, CacheControlError> {
+        let directives = value.elements().map(Directive::parse).collect::<Result<Vec<_>, _>>()?;
+        if directives.is_empty() {
+            return Err(CacheControlError::Malformed);
+        }
+        Ok(Self(directives))
+    }
+    fn find<'a, T>(&'a self, f: impl FnMut(&'a Directive) -> Option<T>) -> Option<T> {
+        self.0.iter().find_map(f)
+    }
+    pub fn max_age(&self) -> Option<u64> {
+        self.find(|d| match d { Directive::MaxAge(secs) => Some(*secs), _ => None })
+    }
+    pub fn s_maxage(&self) -> Option<u64> {
+        self.find(|d| match d { Directive::SMaxAge(secs) => Some(*secs), _ => None })
+    }
+    /// `Some(None)` for a bare `max-stale`, accepting any staleness.
+    pub fn max_stale(&self) -> Option<Option<u64>> {
+        self.find(|d| match d { Directive::MaxStale(secs) => Some(*secs), _ => None })
+    }
+    pub fn min_fresh(&self) -> Option<u64> {
+        self.find(|d| match d { Directive::MinFresh(secs) => Some(*secs), _ => None })
+    }
+    pub fn no_cache(&self) -> bool {
+        self.0.contains(&Directive::NoCache)
+    }
+    pub fn no_store(&self) -> bool {
+        self.0.contains(&Directive::NoStore)
+    }
+    pub fn only_if_cached(&self) -> bool {
+        self.0.contains(&Directive::OnlyIfCached)
+    }
+    /// The argument of the first extension directive called `name`,
+    /// ignoring case; `Some(None)` if it has none.
+    pub fn extension(&self, name: &str) -> Option<Option<&str>> {
+        self.find(|d| match d {
+            Directive::Extension { name: n, value } if n.eq_ignore_ascii_case(name) => Some(value.as_deref()),
+            _ => None,
+        })
+    }
+}
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, directive) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{directive}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<CacheControl, CacheControlError> {
+        CacheControl::parse(&Value::new(s).unwrap())
+    }
+
+    #[test]
+    fn request_directives() {
+        let cache = parse("No-Cache, max-stale, only-if-cached").unwrap();
+        assert!(cache.no_cache() && cache.only_if_cached() && !cache.no_store());
+        assert_eq!(cache.max_stale(), Some(None));
+        assert_eq!(parse("max-stale=60").unwrap().max_stale(), Some(Some(60)));
+    }
+    #[test]
+    fn quoted_arguments() {
+        let cache = parse(r#"max-age="60", ext="a, \"b\"", flag, no-cache="set-cookie""#).unwrap();
+        assert_eq!(cache.max_age(), Some(60));
+        assert_eq!(cache.extension("EXT"), Some(Some(r#"a, "b""#)));
+        assert_eq!(cache.extension("flag"), Some(None));
+        assert_eq!(cache.extension("no-cache"), Some(Some("set-cookie")));
+        assert!(!cache.no_cache());
+        assert_eq!(cache.to_string(), r#"max-age=60, ext="a, \"b\"", flag, no-cache=set-cookie"#);
+    }
+    #[test]
+    fn first_duplicate_wins() {
+        let cache = parse("max-age=10, max-age=20").unwrap();
+        assert_eq!(cache.max_age(), Some(10));
+        assert_eq!(cache.directives().len(), 2);
+    }
+    #[test]
+    fn reject_invalid() {
+        assert_eq!(parse("max-age=soon"), Err(CacheControlError::InvalidSeconds));
+        assert_eq!(parse("max-age"), Err(CacheControlError::InvalidSeconds));
+        assert_eq!(parse("a=b c"), Err(CacheControlError::Malformed));
+        assert_eq!(parse(", ,"), Err(CacheControlError::Malformed));
+        assert_eq!(parse("max-age=99999999999999999999").unwrap().max_age(), Some(u64::MAX));
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{is_token, quoted, ValueError};
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// An authentication challenge as sent in `www-authenticate` and
+/// `proxy-authenticate` headers.
+///
+/// Parameter values are always written as quoted strings.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::Challenge;
+/// let challenge = Challenge::basic("api").unwrap().param("charset", "UTF-8").unwrap();
+/// assert_eq!(challenge.to_string(), r#"Basic realm="api", charset="UTF-8""#);
+/// ```
+pub struct Challenge {
+    scheme: String,
+    params: Vec<(String, String)>,
+}
+impl Challenge {
+    /// A challenge for an arbitrary authentication scheme, which has to be a token.
+    pub fn new<S: AsRef<str>>(scheme: S) -> Result<Self, ValueError> {
+        let scheme = scheme.as_ref();
+        if !is_token(scheme) {
+            return Err(ValueError::not_token(scheme));
+        }
+        Ok(Self { scheme: scheme.to_owned(), params: vec![] })
+    }
+    /// The `Basic` scheme for the given protection space.
+    pub fn basic<S: AsRef<str>>(realm: S) -> Result<Self, ValueError> {
+        Self::new("Basic")?.param("realm", realm)
+    }
+    /// The `Bearer` scheme of RFC 6750, without parameters.
+    pub fn bearer() -> Self {
+        Self::new("Bearer").unwrap()
+    }
+    /// Adds an auth-param. The name has to be a token, the value may not
+    /// contain control characters other than horizontal tab.
+    pub fn param<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Result<Self, ValueError> {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        if !is_token(name) {
+            return Err(ValueError::not_token(name));
+        }
+        if !value.is_ascii() {
+            return Err(ValueError::non_ascii(value.as_bytes()));
+        }
+        if let Some(at) = value.bytes().position(|b| b.is_ascii_control() && b != b'\t') {
+            return Err(ValueError::illegal_char(value.as_bytes(), at));
+        }
+        self.params.push((name.to_owned(), value.to_owned()));
+        Ok(self)
+    }
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+}
+impl Display for Challenge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.scheme)?;
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{separator}{name}={}", quoted::quote_always(value))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn realm_with_quote_is_escaped() {
+        let challenge = Challenge::basic(r#"my "special" realm"#).unwrap();
+        assert_eq!(challenge.to_string(), r#"Basic realm="my \"special\" realm""#);
+    }
+    #[test]
+    fn bearer_with_error() {
+        let challenge = Challenge::bearer().param("error", "invalid_token").unwrap();
+        assert_eq!(challenge.to_string(), r#"Bearer error="invalid_token""#);
+        assert_eq!(Challenge::bearer().to_string(), "Bearer");
+    }
+    #[test]
+    fn reject_invalid_names() {
+        assert!(Challenge::new("Not a token").is_err());
+        assert!(Challenge::bearer().param("a=b", "c").is_err());
+        assert!(Challenge::bearer().param("a", "line\nbreak").is_err());
+    }
+}
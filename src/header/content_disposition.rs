@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use super::{is_token, quoted, DispositionError};
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// The `content-disposition` header, naming a download or a part of a
+/// `multipart/form-data` upload (RFC 6266, RFC 7578).
+///
+/// Parameters are kept decoded. Extended parameters like `filename*`
+/// are written as RFC 8187 ext-values, `UTF-8''` followed by the
+/// percent-encoded name.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::ContentDisposition;
+/// let download = ContentDisposition::attachment().filename("report.pdf");
+/// assert_eq!(download.to_string(), r#"attachment; filename=report.pdf"#);
+///
+/// let upload = ContentDisposition::parse(r#"form-data; name="avatar"; filename="me.png""#).unwrap();
+/// assert_eq!(upload.disposition(), "form-data");
+/// assert_eq!(upload.get_param("name"), Some("avatar"));
+/// assert_eq!(upload.get_filename(), Some("me.png"));
+/// ```
+pub struct ContentDisposition {
+    disposition: String,
+    params: Vec<(String, String)>,
+}
+impl ContentDisposition {
+    /// A disposition of the given type, which has to be a token.
+    pub fn new<S: AsRef<str>>(disposition: S) -> Result<Self, DispositionError> {
+        let disposition = disposition.as_ref();
+        if !is_token(disposition) {
+            return Err(DispositionError::IllegalChars);
+        }
+        Ok(Self { disposition: disposition.to_ascii_lowercase(), params: vec![] })
+    }
+    /// Asks the browser to download the content instead of showing it.
+    pub fn attachment() -> Self {
+        Self::new("attachment").unwrap()
+    }
+    /// Lets the browser show the content.
+    pub fn inline() -> Self {
+        Self::new("inline").unwrap()
+    }
+    /// Adds a parameter. The name has to be a token; values of extended
+    /// parameters, ending in `*`, may be any text, others have to be ascii
+    /// without control characters.
+    pub fn param<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Result<Self, DispositionError> {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        let extended = name.ends_with('*');
+        if !is_token(name)
+            || value.chars().any(|c| c.is_control() && c != '\t')
+            || (!extended && !value.is_ascii())
+        {
+            return Err(DispositionError::IllegalChars);
+        }
+        self.params.push((name.to_ascii_lowercase(), value.to_owned()));
+        Ok(self)
+    }
+    /// Sets the file name to suggest, replacing any previous one.
+    ///
+    /// Non-ascii names are sent as `filename*`, with `filename` holding an
+    /// ascii fallback for old clients. Control characters are replaced by
+    /// `_`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::ContentDisposition;
+    /// let download = ContentDisposition::attachment().filename("réport.pdf");
+    /// assert_eq!(download.to_string(), "attachment; filename=r_port.pdf; filename*=UTF-8''r%C3%A9port.pdf");
+    /// ```
+    pub fn filename<S: AsRef<str>>(mut self, name: S) -> Self {
+        let name = name.as_ref().replace(|c: char| c.is_control(), "_");
+        self.params.retain(|(n, _)| n != "filename" && n != "filename*");
+        let fallback = name.replace(|c: char| !c.is_ascii(), "_");
+        let extended = fallback != name;
+        self.params.push(("filename".to_owned(), fallback));
+        if extended {
+            self.params.push(("filename*".to_owned(), name));
+        }
+        self
+    }
+    /// The disposition type in lowercase, e.g. `attachment` or `form-data`.
+    pub fn disposition(&self) -> &str {
+        &self.disposition
+    }
+    /// The decoded value of the first parameter called `name`, ignoring case.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    /// The suggested file name, preferring `filename*` over `filename`.
+    pub fn get_filename(&self) -> Option<&str> {
+        self.get_param("filename*").or_else(|| self.get_param("filename"))
+    }
+    /// Parses a `content-disposition` value, unescaping quoted strings and
+    /// decoding extended parameters in UTF-8 or ISO-8859-1.
+    pub fn parse(s: &str) -> Result<Self, DispositionError> {
+        let (disposition, mut rest) = s.split_once(';').unwrap_or((s, ""));
+        let mut parsed = Self::new(disposition.trim())?;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                return Ok(parsed);
+            }
+            let (name, tail) = rest.split_once('=').ok_or(DispositionError::Malformed)?;
+            let name = name.trim_end();
+            if !is_token(name) {
+                return Err(DispositionError::Malformed);
+            }
+            let tail = tail.trim_start();
+            let (value, tail) = if tail.starts_with('"') {
+                quoted::split(tail).map_err(|_| DispositionError::Malformed)?
+            } else {
+                let end = tail.find(';').unwrap_or(tail.len());
+                (tail[..end].trim_end().to_owned(), &tail[end..])
+            };
+            let value = if name.ends_with('*') { decode_ext_value(&value)? } else { value };
+            parsed.params.push((name.to_ascii_lowercase(), value));
+            rest = tail.trim_start();
+            rest = match rest.strip_prefix(';') {
+                Some(tail) => tail,
+                None if rest.is_empty() => rest,
+                None => return Err(DispositionError::Malformed),
+            };
+        }
+    }
+}
+impl Display for ContentDisposition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.disposition)?;
+        for (name, value) in &self.params {
+            if name.ends_with('*') {
+                write!(f, "; {name}=UTF-8''{}", encode_ext_value(value))?;
+            } else {
+                write!(f, "; {name}={}", quoted::quote(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `b` may stay unencoded in an ext-value (`attr-char`).
+fn is_attr_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b)
+}
+
+fn encode_ext_value(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if is_attr_char(b) {
+            encoded.push(b as char);
+        } else {
+            // Writing into a `String` cannot fail
+            write!(encoded, "%{b:02X}").unwrap();
+        }
+    }
+    encoded
+}
+
+/// Decodes `charset'language'value`, where the charset is UTF-8 or
+/// ISO-8859-1 and the language is ignored.
+fn decode_ext_value(s: &str) -> Result<String, DispositionError> {
+    let mut parts = s.splitn(3, '\'');
+    let (Some(charset), Some(_language), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(DispositionError::Malformed);
+    };
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b == b'%' {
+            let hex = rest.get(..2).and_then(|h| std::str::from_utf8(h).ok()).ok_or(DispositionError::Malformed)?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| DispositionError::Malformed)?);
+            rest = &rest[2..];
+        } else if is_attr_char(b) {
+            bytes.push(b);
+        } else {
+            return Err(DispositionError::Malformed);
+        }
+    }
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).map_err(|_| DispositionError::Malformed)
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Ok(bytes.into_iter().map(char::from).collect())
+    } else {
+        Err(DispositionError::Malformed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaped_quote_in_filename() {
+        let parsed = ContentDisposition::parse(r#"attachment; filename="say \"hi\".txt"; size=12"#).unwrap();
+        assert_eq!(parsed.get_filename(), Some(r#"say "hi".txt"#));
+        assert_eq!(parsed.get_param("SIZE"), Some("12"));
+        assert_eq!(parsed.to_string(), r#"attachment; filename="say \"hi\".txt"; size=12"#);
+    }
+    #[test]
+    fn utf8_filename_roundtrip() {
+        let built = ContentDisposition::attachment().filename("r\u{e9}port €.pdf");
+        let written = built.to_string();
+        assert_eq!(written, "attachment; filename=\"r_port _.pdf\"; filename*=UTF-8''r%C3%A9port%20%E2%82%AC.pdf");
+        let parsed = ContentDisposition::parse(&written).unwrap();
+        assert_eq!(parsed.get_filename(), Some("r\u{e9}port €.pdf"));
+        assert_eq!(parsed, built);
+    }
+    #[test]
+    fn latin1_ext_value() {
+        let parsed = ContentDisposition::parse("inline; FILENAME*=iso-8859-1'en'caf%E9.txt").unwrap();
+        assert_eq!(parsed.get_filename(), Some("caf\u{e9}.txt"));
+    }
+    #[test]
+    fn reject_malformed() {
+        assert_eq!(ContentDisposition::parse("attachment; filename"), Err(DispositionError::Malformed));
+        assert_eq!(ContentDisposition::parse("attachment; filename=\"open"), Err(DispositionError::Malformed));
+        assert_eq!(ContentDisposition::parse("attachment; filename*=UTF-8''%E9"), Err(DispositionError::Malformed));
+        assert_eq!(ContentDisposition::parse("attachment; filename*=koi8-r''x"), Err(DispositionError::Malformed));
+        assert_eq!(ContentDisposition::parse("attach ment"), Err(DispositionError::IllegalChars));
+    }
+}
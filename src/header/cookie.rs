@@ -0,0 +1,195 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::date;
+use super::{is_token, CookieError};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// The `SameSite` cookie attribute.
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Sent on cross-site requests too, which browsers only allow for
+    /// [secure][SetCookie::secure] cookies.
+    None,
+}
+impl Display for SameSite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A `set-cookie` header (RFC 6265).
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{SameSite, SetCookie};
+/// # use std::time::Duration;
+/// let cookie = SetCookie::new("session", "abc123").unwrap()
+///     .path("/").unwrap()
+///     .max_age(Duration::from_secs(3600))
+///     .secure(true)
+///     .http_only(true)
+///     .same_site(SameSite::Lax);
+/// assert_eq!(cookie.to_string(), "session=abc123; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=Lax");
+/// ```
+pub struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+impl SetCookie {
+    /// A session cookie. The name has to be a token, the value may only
+    /// contain printable ascii except whitespace, `"`, `,`, `;` and `\`,
+    /// optionally wrapped in double quotes.
+    pub fn new<N: AsRef<str>, V: AsRef<str>>(name: N, value: V) -> Result<Self, CookieError> {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        if !is_token(name) {
+            return Err(CookieError::InvalidName);
+        }
+        let unquoted = value.strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if !unquoted.bytes().all(is_cookie_octet) {
+            return Err(CookieError::InvalidValue);
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    pub fn expires(mut self, at: SystemTime) -> Self {
+        self.expires = Some(at);
+        self
+    }
+    /// Lifetime in whole seconds. Takes precedence over
+    /// [expires][Self::expires] in current browsers.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+    pub fn domain<S: AsRef<str>>(mut self, domain: S) -> Result<Self, CookieError> {
+        self.domain = Some(attribute_value(domain.as_ref())?);
+        Ok(self)
+    }
+    pub fn path<S: AsRef<str>>(mut self, path: S) -> Result<Self, CookieError> {
+        self.path = Some(attribute_value(path.as_ref())?);
+        Ok(self)
+    }
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+    /// Turns the cookie into one deleting it in the browser: the value is
+    /// emptied and it expires immediately. Domain and path are kept, as
+    /// they have to match the cookie being deleted.
+    pub fn expire_now(mut self) -> Self {
+        self.value.clear();
+        self.max_age = Some(Duration::ZERO);
+        self.expires = Some(UNIX_EPOCH);
+        self
+    }
+}
+impl Display for SetCookie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", date::format(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+fn attribute_value(s: &str) -> Result<String, CookieError> {
+    if s.is_empty() || s.bytes().any(|b| !b.is_ascii() || b.is_ascii_control() || b == b';') {
+        return Err(CookieError::InvalidAttribute);
+    }
+    Ok(s.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fully_loaded() {
+        let cookie = SetCookie::new("id", "\"a3fWa\"").unwrap()
+            .expires(UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+            .max_age(Duration::from_secs(3600))
+            .domain("example.com").unwrap()
+            .path("/").unwrap()
+            .secure(true)
+            .http_only(true)
+            .same_site(SameSite::Strict);
+        assert_eq!(cookie.to_string(), "id=\"a3fWa\"; Expires=Wed, 21 Oct 2015 07:28:00 GMT; \
+            Max-Age=3600; Domain=example.com; Path=/; Secure; HttpOnly; SameSite=Strict");
+    }
+    #[test]
+    fn rejects_illegal_octets() {
+        assert_eq!(SetCookie::new("id", "a;b"), Err(CookieError::InvalidValue));
+        assert_eq!(SetCookie::new("id", "a b"), Err(CookieError::InvalidValue));
+        assert_eq!(SetCookie::new("i d", "a"), Err(CookieError::InvalidName));
+        assert_eq!(SetCookie::new("id", "a").unwrap().path("/; Secure").unwrap_err(), CookieError::InvalidAttribute);
+    }
+    #[test]
+    fn expire_now() {
+        let cookie = SetCookie::new("id", "abc").unwrap().path("/app").unwrap().expire_now();
+        assert_eq!(cookie.to_string(), "id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/app");
+    }
+}
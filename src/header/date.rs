@@ -0,0 +1,225 @@
+//! HTTP-date handling (RFC 9110, section 5.6.7).
+//!
+//! Dates are always sent as IMF-fixdate, but the obsolete RFC 850 and
+//! asctime formats are still accepted.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::DateError;
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+pub(crate) const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Formats `time` as IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+///
+/// Times before the unix epoch are clamped to it, sub-second precision is dropped.
+pub fn format(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (days, rem) = (secs / 86400, secs % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    // 1970-01-01 was a Thursday
+    let weekday = DAYS[((days + 3) % 7) as usize];
+    format!("{weekday}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        MONTHS[month as usize - 1], rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Parses an HTTP-date in any of its three formats:
+/// - IMF-fixdate: `Sun, 06 Nov 1994 08:49:37 GMT`
+/// - RFC 850: `Sunday, 06-Nov-94 08:49:37 GMT`
+/// - asctime: `Sun Nov  6 08:49:37 1994`
+///
+/// The day name is not checked against the date. Two-digit RFC 850 years
+/// more than 50 years in the future are taken to be in the past century.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::date;
+/// let imf = date::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+/// assert_eq!(date::parse("Sunday, 06-Nov-94 08:49:37 GMT"), Ok(imf));
+/// assert_eq!(date::parse("Sun Nov  6 08:49:37 1994"), Ok(imf));
+/// assert_eq!(date::format(imf), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+pub fn parse(s: &str) -> Result<SystemTime, DateError> {
+    let today = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 86400;
+    parse_in_year(s, civil_from_days(today as i64).0)
+}
+
+/// [parse] with `current_year` for the RFC 850 year pivot.
+fn parse_in_year(s: &str, current_year: i64) -> Result<SystemTime, DateError> {
+    let s = s.trim();
+    match s.split_once(", ") {
+        Some((name, rest)) if DAYS.contains(&name) => parse_imf_fixdate(rest),
+        Some((name, rest)) if LONG_DAYS.contains(&name) => parse_rfc850(rest, current_year),
+        Some(_) => Err(DateError::Malformed),
+        None => parse_asctime(s),
+    }
+}
+
+/// `06 Nov 1994 08:49:37 GMT`
+fn parse_imf_fixdate(s: &str) -> Result<SystemTime, DateError> {
+    let mut parts = s.split(' ');
+    let day = digits(parts.next(), 2)?;
+    let month = month(parts.next())?;
+    let year = digits(parts.next(), 4)?;
+    let time = parts.next().ok_or(DateError::Malformed)?;
+    if parts.next() != Some("GMT") || parts.next().is_some() {
+        return Err(DateError::Malformed);
+    }
+    to_system_time(year as i64, month, day, time)
+}
+
+/// `06-Nov-94 08:49:37 GMT`
+fn parse_rfc850(s: &str, current_year: i64) -> Result<SystemTime, DateError> {
+    let mut parts = s.split(' ');
+    let mut date = parts.next().ok_or(DateError::Malformed)?.split('-');
+    let day = digits(date.next(), 2)?;
+    let month = month(date.next())?;
+    let short_year = digits(date.next(), 2)? as i64;
+    let time = parts.next().ok_or(DateError::Malformed)?;
+    if date.next().is_some() || parts.next() != Some("GMT") || parts.next().is_some() {
+        return Err(DateError::Malformed);
+    }
+    let mut year = current_year - current_year.rem_euclid(100) + short_year;
+    if year > current_year + 50 {
+        year -= 100;
+    }
+    to_system_time(year, month, day, time)
+}
+
+/// `Sun Nov  6 08:49:37 1994`, with the day padded by a space
+fn parse_asctime(s: &str) -> Result<SystemTime, DateError> {
+    let (name, rest) = s.split_once(' ').ok_or(DateError::Malformed)?;
+    let (month_name, rest) = rest.split_once(' ').ok_or(DateError::Malformed)?;
+    let (Some(day), Some(rest)) = (rest.get(..2), rest.get(2..)) else {
+        return Err(DateError::Malformed);
+    };
+    if !DAYS.contains(&name) {
+        return Err(DateError::Malformed);
+    }
+    let day = day.strip_prefix(' ').unwrap_or(day);
+    let day = digits(Some(day), day.len())?;
+    let mut parts = rest.strip_prefix(' ').ok_or(DateError::Malformed)?.split(' ');
+    let time = parts.next().ok_or(DateError::Malformed)?;
+    let year = digits(parts.next(), 4)?;
+    if parts.next().is_some() {
+        return Err(DateError::Malformed);
+    }
+    to_system_time(year as i64, month(Some(month_name))?, day, time)
+}
+
+/// A number of exactly `len` digits.
+fn digits(s: Option<&str>, len: usize) -> Result<u32, DateError> {
+    s.filter(|s| s.len() == len && s.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|s| s.parse().ok())
+        .ok_or(DateError::Malformed)
+}
+
+fn month(s: Option<&str>) -> Result<u32, DateError> {
+    s.and_then(|m| MONTHS.iter().position(|&name| name == m))
+        .map(|i| i as u32 + 1)
+        .ok_or(DateError::Malformed)
+}
+
+/// Seconds since the epoch for a `hh:mm:ss` time on the given date.
+fn to_system_time(year: i64, month: u32, day: u32, time: &str) -> Result<SystemTime, DateError> {
+    let mut hms = time.split(':').map(|p| digits(Some(p), 2));
+    let (h, m, s) = match (hms.next(), hms.next(), hms.next(), hms.next()) {
+        (Some(h), Some(m), Some(s), None) => (h? as u64, m? as u64, s? as u64),
+        _ => return Err(DateError::Malformed),
+    };
+    if h > 23 || m > 59 || s > 60 || day == 0 || day > days_in_month(year, month) {
+        return Err(DateError::OutOfRange);
+    }
+    let days = u64::try_from(days_from_civil(year, month, day)).map_err(|_| DateError::OutOfRange)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(days * 86400 + h * 3600 + m * 60 + s))
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between days since the epoch and the proleptic Gregorian
+// calendar, after Howard Hinnant's `chrono`-compatible date algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: u64 = 784111777;
+
+    #[test]
+    fn format_rfc_example() {
+        let time = UNIX_EPOCH + Duration::from_secs(EXAMPLE);
+        assert_eq!(format(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+    #[test]
+    fn parse_rfc_example() {
+        let time = UNIX_EPOCH + Duration::from_secs(EXAMPLE);
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 GMT"), Ok(time));
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT"), Ok(time));
+        assert_eq!(parse("Sun Nov  6 08:49:37 1994"), Ok(time));
+    }
+    #[test]
+    fn asctime_two_digit_day() {
+        let time = parse("Wed Nov 16 08:49:37 1994").unwrap();
+        assert_eq!(format(time), "Wed, 16 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse("Wed Nov 016 08:49:37 1994"), Err(DateError::Malformed));
+    }
+    #[test]
+    fn roundtrip_leap_day() {
+        let time = parse("Thu, 29 Feb 2024 23:59:59 GMT").unwrap();
+        assert_eq!(format(time), "Thu, 29 Feb 2024 23:59:59 GMT");
+        assert_eq!(parse("Thursday, 29-Feb-24 23:59:59 GMT"), Ok(time));
+        assert_eq!(parse("Thu Feb 29 23:59:59 2024"), Ok(time));
+    }
+    #[test]
+    fn past_2038() {
+        let time = parse("Sat, 01 Jan 2050 00:00:00 GMT").unwrap();
+        assert_eq!(time, UNIX_EPOCH + Duration::from_secs(2524608000));
+        assert_eq!(format(time), "Sat, 01 Jan 2050 00:00:00 GMT");
+    }
+    #[test]
+    fn rfc850_year_pivot() {
+        let in_year = |s, year| parse_in_year(s, year).map(format);
+        assert_eq!(in_year("Sunday, 06-Nov-94 08:49:37 GMT", 2026).unwrap(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(in_year("Saturday, 01-Jan-77 00:00:00 GMT", 2026).unwrap(), "Sat, 01 Jan 1977 00:00:00 GMT");
+        assert_eq!(in_year("Wednesday, 01-Jan-76 00:00:00 GMT", 2026).unwrap(), "Wed, 01 Jan 2076 00:00:00 GMT");
+    }
+    #[test]
+    fn reject_invalid() {
+        assert_eq!(parse("Fri, 29 Feb 2023 00:00:00 GMT"), Err(DateError::OutOfRange));
+        assert_eq!(parse("Thu, 01 Jan 1969 00:00:00 GMT"), Err(DateError::OutOfRange));
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 UTC"), Err(DateError::Malformed));
+        assert_eq!(parse("Sun, 6 Nov 1994 08:49:37 GMT"), Err(DateError::Malformed));
+        assert_eq!(parse("Sun, 06 Nov 1994 8:49:37 GMT"), Err(DateError::Malformed));
+        assert_eq!(parse("Someday, 06-Nov-94 08:49:37 GMT"), Err(DateError::Malformed));
+        assert_eq!(parse("yesterday"), Err(DateError::Malformed));
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use super::EntityTagError;
+
+#[derive(PartialEq, Debug, Clone, Eq, Hash)]
+/// An entity tag as used by the `etag`, `if-match` and `if-none-match` headers.
+///
+/// The opaque tag may only contain the visible ascii characters except `"`.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::EntityTag;
+/// let strong = EntityTag::strong("v1").unwrap();
+/// let weak: EntityTag = "W/\"v1\"".parse().unwrap();
+/// assert_eq!(weak.to_string(), "W/\"v1\"");
+/// assert!(strong.weak_eq(&weak));
+/// assert!(!strong.strong_eq(&weak));
+/// ```
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+impl EntityTag {
+    /// A strong validator, changing whenever the representation changes.
+    pub fn strong<S: AsRef<str>>(tag: S) -> Result<Self, EntityTagError> {
+        Self::new(false, tag.as_ref())
+    }
+    /// A weak validator, only changing when the meaning of the representation changes.
+    pub fn weak<S: AsRef<str>>(tag: S) -> Result<Self, EntityTagError> {
+        Self::new(true, tag.as_ref())
+    }
+    /// A strong tag derived from the FNV-1a hash of `body`.
+    pub fn from_body(body: &[u8]) -> Self {
+        let hash = body.iter().fold(0xcbf29ce484222325_u64, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        });
+        Self { weak: false, tag: format!("{hash:016x}") }
+    }
+    fn new(weak: bool, tag: &str) -> Result<Self, EntityTagError> {
+        if tag.bytes().all(is_etagc) {
+            Ok(Self { weak, tag: tag.to_owned() })
+        } else {
+            Err(EntityTagError::IllegalChars)
+        }
+    }
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+    /// The opaque tag without quotes and weakness indicator.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    /// Strong comparison: both tags are strong and equal.
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+    /// Weak comparison: the opaque tags are equal, weakness is ignored.
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+    /// Parses a comma-separated list of entity tags, as found in `if-match`
+    /// and `if-none-match`.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, EntityTagError> {
+        let mut tags = vec![];
+        let mut rest = s;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+            if rest.is_empty() {
+                return Ok(tags);
+            }
+            let (tag, tail) = Self::parse_one(rest)?;
+            tags.push(tag);
+            rest = tail;
+        }
+    }
+    fn parse_one(s: &str) -> Result<(Self, &str), EntityTagError> {
+        let (weak, s) = match s.strip_prefix("W/") {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let s = s.strip_prefix('"').ok_or(EntityTagError::Unquoted)?;
+        let end = s.find('"').ok_or(EntityTagError::Unquoted)?;
+        Ok((Self::new(weak, &s[..end])?, &s[end + 1..]))
+    }
+}
+fn is_etagc(b: u8) -> bool {
+    b == 0x21 || (0x23..=0x7e).contains(&b)
+}
+impl Display for EntityTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.weak {
+            write!(f, "W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+impl FromStr for EntityTag {
+    type Err = EntityTagError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::parse_one(s.trim())? {
+            (tag, "") => Ok(tag),
+            _ => Err(EntityTagError::Unquoted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_quote_and_space() {
+        assert_eq!(EntityTag::strong("a\"b"), Err(EntityTagError::IllegalChars));
+        assert_eq!(EntityTag::weak("a b"), Err(EntityTagError::IllegalChars));
+    }
+    #[test]
+    fn comparison_table() {
+        // Example table from RFC 9110, section 8.8.3.2
+        let w1: EntityTag = "W/\"1\"".parse().unwrap();
+        let w2: EntityTag = "W/\"2\"".parse().unwrap();
+        let s1: EntityTag = "\"1\"".parse().unwrap();
+        assert!(!w1.strong_eq(&w1) && w1.weak_eq(&w1));
+        assert!(!w1.strong_eq(&w2) && !w1.weak_eq(&w2));
+        assert!(!w1.strong_eq(&s1) && w1.weak_eq(&s1));
+        assert!(s1.strong_eq(&s1) && s1.weak_eq(&s1));
+    }
+    #[test]
+    fn parse_list_with_commas_in_tag() {
+        let tags = EntityTag::parse_list("\"a,b\", W/\"c\" ,\"d\"").unwrap();
+        assert_eq!(tags, [
+            EntityTag::strong("a,b").unwrap(),
+            EntityTag::weak("c").unwrap(),
+            EntityTag::strong("d").unwrap(),
+        ]);
+    }
+    #[test]
+    fn unquoted_rejected() {
+        assert_eq!("abc".parse::<EntityTag>(), Err(EntityTagError::Unquoted));
+    }
+    #[test]
+    fn body_tag_is_stable() {
+        assert_eq!(EntityTag::from_body(b"abc"), EntityTag::from_body(b"abc"));
+        assert_ne!(EntityTag::from_body(b"abc"), EntityTag::from_body(b"abd"));
+    }
+}
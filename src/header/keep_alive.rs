@@ -0,0 +1,66 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration,
+};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+/// The parameters of a `keep-alive` header, with which a server announces
+/// how long it keeps an idle connection open and how many more requests
+/// it answers on it.
+///
+/// Parsing is lenient: unknown parameters and invalid numbers are skipped.
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use heggemann_http::header::KeepAlive;
+/// let keep_alive = KeepAlive::parse("timeout=5, max=100");
+/// assert_eq!(keep_alive.timeout, Some(Duration::from_secs(5)));
+/// assert_eq!(keep_alive.max, Some(100));
+/// assert_eq!(keep_alive.to_string(), "timeout=5, max=100");
+/// ```
+pub struct KeepAlive {
+    /// How long the connection may be idle, in whole seconds
+    pub timeout: Option<Duration>,
+    /// How many more requests are answered on the connection
+    pub max: Option<u64>,
+}
+impl KeepAlive {
+    pub fn parse(s: &str) -> Self {
+        let mut keep_alive = Self::default();
+        for param in s.split(',') {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            let Ok(value) = value.trim().trim_matches('"').parse() else {
+                continue;
+            };
+            match name.trim() {
+                name if name.eq_ignore_ascii_case("timeout") => keep_alive.timeout = Some(Duration::from_secs(value)),
+                name if name.eq_ignore_ascii_case("max") => keep_alive.max = Some(value),
+                _ => {}
+            }
+        }
+        keep_alive
+    }
+}
+impl Display for KeepAlive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let timeout = self.timeout.map(|timeout| format!("timeout={}", timeout.as_secs()));
+        let max = self.max.map(|max| format!("max={max}"));
+        f.write_str(&[timeout, max].into_iter().flatten().collect::<Vec<_>>().join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenient() {
+        let keep_alive = KeepAlive::parse("MAX=\"3\", timeout=x, foo=1, bare");
+        assert_eq!(keep_alive, KeepAlive { timeout: None, max: Some(3) });
+        assert_eq!(keep_alive.to_string(), "max=3");
+        assert_eq!(KeepAlive::parse(""), KeepAlive::default());
+    }
+}
@@ -1,41 +1,160 @@
 use std::{
-    borrow::Borrow,
+    borrow::{Borrow, Cow},
     fmt::{Display, Formatter, Result as FmtResult},
 };
 
-use super::KeyError;
+use super::{excerpt, is_tchar, registry, HeaderInfo, KeyError, MAX_KEY_LEN};
 
 #[derive(PartialEq, Debug, Hash, Eq, Clone)]
 /// Struct with all requirements encoded.
 /// Always stores as its ascii lowercase item.
 /// - Can't contain the empty string.
 /// - Equals with any case of the same characters.
-/// - cannot have leading or trailing whitespace
-pub struct Key (String);
+/// - Only consists of token characters (RFC 9110, section 5.6.2),
+///   so no whitespace, colons or other delimiters
+pub struct Key (Cow<'static, str>);
+
 impl Key {
+    // Common field names, usable without validation or allocation
+    pub const ACCEPT: Key = Key::from_static("accept");
+    pub const ACCEPT_ENCODING: Key = Key::from_static("accept-encoding");
+    pub const ACCEPT_RANGES: Key = Key::from_static("accept-ranges");
+    pub const ACCESS_CONTROL_ALLOW_CREDENTIALS: Key = Key::from_static("access-control-allow-credentials");
+    pub const ACCESS_CONTROL_ALLOW_HEADERS: Key = Key::from_static("access-control-allow-headers");
+    pub const ACCESS_CONTROL_ALLOW_METHODS: Key = Key::from_static("access-control-allow-methods");
+    pub const ACCESS_CONTROL_ALLOW_ORIGIN: Key = Key::from_static("access-control-allow-origin");
+    pub const ACCESS_CONTROL_EXPOSE_HEADERS: Key = Key::from_static("access-control-expose-headers");
+    pub const ACCESS_CONTROL_MAX_AGE: Key = Key::from_static("access-control-max-age");
+    pub const ALLOW: Key = Key::from_static("allow");
+    pub const AUTHORIZATION: Key = Key::from_static("authorization");
+    pub const CACHE_CONTROL: Key = Key::from_static("cache-control");
+    pub const CONNECTION: Key = Key::from_static("connection");
+    pub const CONTENT_DISPOSITION: Key = Key::from_static("content-disposition");
+    pub const CONTENT_ENCODING: Key = Key::from_static("content-encoding");
+    pub const CONTENT_LENGTH: Key = Key::from_static("content-length");
+    pub const CONTENT_RANGE: Key = Key::from_static("content-range");
+    pub const CONTENT_TYPE: Key = Key::from_static("content-type");
+    pub const COOKIE: Key = Key::from_static("cookie");
+    pub const DATE: Key = Key::from_static("date");
+    pub const ETAG: Key = Key::from_static("etag");
+    pub const HOST: Key = Key::from_static("host");
+    pub const IF_MODIFIED_SINCE: Key = Key::from_static("if-modified-since");
+    pub const IF_NONE_MATCH: Key = Key::from_static("if-none-match");
+    pub const KEEP_ALIVE: Key = Key::from_static("keep-alive");
+    pub const LAST_MODIFIED: Key = Key::from_static("last-modified");
+    pub const LINK: Key = Key::from_static("link");
+    pub const LOCATION: Key = Key::from_static("location");
+    pub const ORIGIN: Key = Key::from_static("origin");
+    pub const RANGE: Key = Key::from_static("range");
+    pub const RETRY_AFTER: Key = Key::from_static("retry-after");
+    pub const SERVER: Key = Key::from_static("server");
+    pub const SET_COOKIE: Key = Key::from_static("set-cookie");
+    pub const TE: Key = Key::from_static("te");
+    pub const TRAILER: Key = Key::from_static("trailer");
+    pub const TRANSFER_ENCODING: Key = Key::from_static("transfer-encoding");
+    pub const UPGRADE: Key = Key::from_static("upgrade");
+    pub const VARY: Key = Key::from_static("vary");
+    pub const VIA: Key = Key::from_static("via");
+    pub const WWW_AUTHENTICATE: Key = Key::from_static("www-authenticate");
+
     /// Verifies compliance with the HTTP/1.1 header
     /// standard, ensuring that [Key] always matches it.
+    ///
+    /// Keys longer than [MAX_KEY_LEN] bytes are refused.
     pub fn new<S: AsRef<str>>(s: S) -> Result<Self, KeyError> {
+        Self::new_with_limit(s, MAX_KEY_LEN)
+    }
+    /// Like [new][Key::new], but refusing keys longer than `limit` bytes
+    /// instead.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{Key, KeyError};
+    /// assert!(Key::new_with_limit("accept", 6).is_ok());
+    /// assert_eq!(Key::new_with_limit("accept", 5), Err(KeyError::TooLong { len: 6, limit: 5 }));
+    /// ```
+    pub fn new_with_limit<S: AsRef<str>>(s: S, limit: usize) -> Result<Self, KeyError> {
         let s = s.as_ref();
-        if !s.is_ascii() {
-            Err(KeyError::NonAsciiChars) 
+        if s.len() > limit {
+            Err(KeyError::TooLong { len: s.len(), limit })
+        } else if !s.is_ascii() {
+            Err(KeyError::non_ascii(s.as_bytes()))
         } else if s.is_empty() {
             Err(KeyError::EmptyString)
         } else if s.trim() != s {
-            Err(KeyError::HeaderNameWhitespace)
+            Err(KeyError::HeaderNameWhitespace { input: excerpt(s) })
+        } else if let Some(at) = s.bytes().position(|b| !is_tchar(b)) {
+            Err(KeyError::IllegalChar { input: excerpt(s), found: s.as_bytes()[at] as char, at })
         } else {
-            Ok(Self(s.to_ascii_lowercase()))
+            Ok(Self(Cow::Owned(s.to_ascii_lowercase())))
         }
     }
+    /// A key from a lowercase token known at compile time, without
+    /// allocating.
+    ///
+    /// # Panics
+    /// If `s` is not a lowercase token, at compile time when used in a
+    /// constant.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Key;
+    /// const REQUEST_ID: Key = Key::from_static("x-request-id");
+    /// assert_eq!(REQUEST_ID, Key::new("X-Request-Id").unwrap());
+    /// ```
+    pub const fn from_static(s: &'static str) -> Self {
+        let bytes = s.as_bytes();
+        assert!(!bytes.is_empty(), "empty header name");
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            assert!(is_tchar(b) && !b.is_ascii_uppercase(), "header name is not a lowercase token");
+            i += 1;
+        }
+        Self(Cow::Borrowed(s))
+    }
+    /// The key in lowercase.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    /// Whether the field may occur only once in a message, like
+    /// `content-type` or `host`, instead of holding a comma-separated list.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Key;
+    /// assert!(Key::CONTENT_TYPE.is_singleton());
+    /// assert!(!Key::ACCEPT.is_singleton());
+    /// ```
+    pub fn is_singleton(&self) -> bool {
+        self.info().is_some_and(|info| info.singleton)
+    }
+    /// What is known about the field if it is a standard one the crate
+    /// handles, `None` for custom or misspelled names.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Key;
+    /// assert!(Key::CONNECTION.info().unwrap().hop_by_hop);
+    /// assert_eq!(Key::new("contnet-type").unwrap().info(), None);
+    /// ```
+    pub fn info(&self) -> Option<&'static HeaderInfo> {
+        registry::lookup(self.as_str())
+    }
+    /// Whether the field is in the registry of [info][Key::info].
+    pub fn is_standard(&self) -> bool {
+        self.info().is_some()
+    }
 }
 impl Display for Key {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.0)
     }
 }
+/// Compares without allocating, as the key is already lowercase.
 impl<S: AsRef<str>> PartialEq<S> for Key {
     fn eq(&self, other: &S) -> bool {
-        self.0 == other.as_ref().to_ascii_lowercase()
+        self.0.eq_ignore_ascii_case(other.as_ref())
     }
 }
 impl Borrow<str> for Key {
@@ -46,7 +165,21 @@ impl Borrow<str> for Key {
 
 impl From<Key> for String {
     fn from(value: Key) -> String {
-        value.0
+        value.0.into_owned()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Key {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Key {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Key::new(s).map_err(serde::de::Error::custom)
     }
 }
 
@@ -68,6 +201,47 @@ mod tests {
         assert_eq!(Key::new("ABC"), Key::new("abc"));
     }
     #[test]
+    fn constants_equal_parsed() {
+        assert_eq!(Key::CONTENT_TYPE, Key::new("Content-Type").unwrap());
+        let mut map = crate::header::HeaderMap::new();
+        map.append(Key::new("Content-Type").unwrap(), "text/plain").unwrap();
+        map.append(Key::SET_COOKIE, "a=1").unwrap();
+        assert_eq!(map.get(Key::CONTENT_TYPE.as_str()).unwrap(), "text/plain");
+        assert_eq!(map.get("set-cookie").unwrap(), "a=1");
+    }
+    #[test]
+    #[should_panic]
+    fn from_static_rejects_uppercase() {
+        Key::from_static("Content-Type");
+    }
+    #[test]
+    fn registry_lookups() {
+        let info = Key::new("Content-Length").unwrap().info().unwrap();
+        assert!(info.singleton && info.forbidden_in_trailers && !info.hop_by_hop);
+        assert!(Key::TRANSFER_ENCODING.info().unwrap().hop_by_hop);
+        assert!(!Key::ACCEPT.is_singleton());
+        assert!(Key::VARY.is_standard());
+        assert_eq!(Key::new("x-request-id").unwrap().info(), None);
+        assert!(!Key::new("contnet-type").unwrap().is_standard());
+        assert!(!Key::new("x-request-id").unwrap().is_singleton());
+    }
+    #[test]
+    fn length_limit() {
+        assert!(Key::new("a".repeat(MAX_KEY_LEN)).is_ok());
+        assert_eq!(Key::new("a".repeat(MAX_KEY_LEN + 1)), Err(KeyError::TooLong { len: MAX_KEY_LEN + 1, limit: MAX_KEY_LEN }));
+    }
+    #[test]
+    fn refuse_non_tokens() {
+        let illegal = |input: &str, found, at| Err(KeyError::IllegalChar { input: input.to_owned(), found, at });
+        assert_eq!(Key::new("bad key"), illegal("bad key", ' ', 3));
+        assert_eq!(Key::new("a:b"), illegal("a:b", ':', 1));
+        assert_eq!(Key::new("a\tb"), illegal("a\tb", '\t', 1));
+        assert_eq!(Key::new("weird{}"), illegal("weird{}", '{', 5));
+        assert_eq!(Key::new("gr\u{fc}n"), Err(KeyError::NonAsciiChars { input: "gr\u{fc}n".to_owned(), at: 2 }));
+        assert!(Key::new("other_stuff").is_ok());
+        assert!(Key::new("X-Request-Id").is_ok());
+    }
+    #[test]
     fn refuse_whitespace() {
         assert!(Key::new("      abc         ").is_err())
     }
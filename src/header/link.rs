@@ -0,0 +1,138 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{is_token, quoted, LinkError};
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// A web link as carried by the `link` header (RFC 8288).
+///
+/// Several links in one header are written comma-separated. Parameter
+/// values are written as tokens where possible, otherwise quoted.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::Link;
+/// let link = Link::new("/style.css").unwrap().rel("preload").unwrap().param("as", "style").unwrap();
+/// assert_eq!(link.to_string(), "</style.css>; rel=preload; as=style");
+/// ```
+pub struct Link {
+    target: String,
+    params: Vec<(String, String)>,
+}
+impl Link {
+    /// A link to `target`, which may not contain whitespace, control
+    /// characters or `<>`.
+    pub fn new<S: AsRef<str>>(target: S) -> Result<Self, LinkError> {
+        let target = target.as_ref();
+        if target.is_empty() || !target.bytes().all(|b| b.is_ascii_graphic() && b != b'<' && b != b'>') {
+            return Err(LinkError::IllegalChars);
+        }
+        Ok(Self { target: target.to_owned(), params: vec![] })
+    }
+    /// Sets the relation type, e.g. `preload` or `next`.
+    pub fn rel<S: AsRef<str>>(self, rel: S) -> Result<Self, LinkError> {
+        self.param("rel", rel)
+    }
+    /// Adds a target attribute. The name has to be a token.
+    pub fn param<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Result<Self, LinkError> {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        if !is_token(name) || value.bytes().any(|b| !b.is_ascii() || (b.is_ascii_control() && b != b'\t')) {
+            return Err(LinkError::IllegalChars);
+        }
+        self.params.push((name.to_ascii_lowercase(), value.to_owned()));
+        Ok(self)
+    }
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+    /// The value of the first parameter called `name`, ignoring case.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    /// Parses a `link` header value holding one or more comma-separated links.
+    ///
+    /// Values of several `link` header lines combined with commas parse the same way.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, LinkError> {
+        let mut links = vec![];
+        let mut rest = s.trim_start();
+        while !rest.is_empty() {
+            let inner = rest.strip_prefix('<').ok_or(LinkError::Malformed)?;
+            let end = inner.find('>').ok_or(LinkError::Malformed)?;
+            let mut link = Self::new(&inner[..end])?;
+            rest = inner[end + 1..].trim_start();
+            while let Some(param) = rest.strip_prefix(';') {
+                let param = param.trim_start();
+                let name_end = param.find(|c: char| c == '=' || c == ';' || c == ',' || c.is_ascii_whitespace())
+                    .unwrap_or(param.len());
+                let name = &param[..name_end];
+                rest = param[name_end..].trim_start();
+                let value = match rest.strip_prefix('=') {
+                    Some(value) => {
+                        let value = value.trim_start();
+                        if value.starts_with('"') {
+                            let (value, tail) = quoted::split(value).map_err(|_| LinkError::Malformed)?;
+                            rest = tail;
+                            value
+                        } else {
+                            let end = value.find([';', ',']).unwrap_or(value.len());
+                            rest = &value[end..];
+                            value[..end].trim_end().to_owned()
+                        }
+                    },
+                    None => String::new(),
+                };
+                link = link.param(name, value)?;
+                rest = rest.trim_start();
+            }
+            links.push(link);
+            rest = match rest.strip_prefix(',') {
+                Some(tail) => tail.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return Err(LinkError::Malformed),
+            };
+        }
+        Ok(links)
+    }
+}
+impl Display for Link {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "<{}>", self.target)?;
+        for (name, value) in &self.params {
+            write!(f, "; {name}={}", quoted::quote(value))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_non_token_values() {
+        let link = Link::new("/a").unwrap().param("title", "A title").unwrap();
+        assert_eq!(link.to_string(), "</a>; title=\"A title\"");
+    }
+    #[test]
+    fn parse_multiple_links() {
+        let links = Link::parse_list(
+            "</style.css>; rel=preload; as=style, </font.woff2>;rel=\"preload\";as=font;crossorigin"
+        ).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target(), "/style.css");
+        assert_eq!(links[0].get_param("as"), Some("style"));
+        assert_eq!(links[1].get_param("rel"), Some("preload"));
+        assert_eq!(links[1].get_param("crossorigin"), Some(""));
+    }
+    #[test]
+    fn parse_roundtrip() {
+        let link = Link::new("/x,y").unwrap().rel("next").unwrap().param("title", "a, \"b\"").unwrap();
+        assert_eq!(Link::parse_list(&link.to_string()).unwrap(), [link]);
+    }
+    #[test]
+    fn reject_malformed() {
+        assert_eq!(Link::parse_list("/style.css; rel=preload"), Err(LinkError::Malformed));
+        assert_eq!(Link::new("has space"), Err(LinkError::IllegalChars));
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+
+use super::{HeaderError, Key, KeyError, Value, ValueError, ValueLimits};
+
+/// Longest mixed-case key that lookups lowercase without allocating.
+const FOLD_BUFFER_LEN: usize = 64;
+
+#[derive(PartialEq, Debug, Clone, Default)]
+/// Header fields of a request or response, remembering the order in
+/// which keys were first inserted.
+///
+/// Lookups go through a hash index, so they stay as cheap as with a plain
+/// [HashMap], and accept keys in any case without allocating.
+///
+/// Every key has a single field line. [insert][HeaderMap::insert] replaces
+/// its value, [append][HeaderMap::append] combines the values with a comma
+/// and [try_insert][HeaderMap::try_insert] refuses to touch an existing
+/// one, which suits [singleton][Key::is_singleton] fields. `set-cookie` cannot be combined
+/// that way, so appending to it adds another field line instead. Lookups
+/// of such a key see its first line. Two maps are equal if they have the
+/// same lines in the same order.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{HeaderMap, Key};
+/// let mut headers = HeaderMap::new();
+/// headers.append(Key::new("Accept").unwrap(), "text/html").unwrap();
+/// headers.append(Key::ACCEPT, "*/*").unwrap();
+/// assert_eq!(headers.get("ACCEPT").unwrap(), "text/html,*/*");
+/// assert_eq!(headers.len(), 1);
+/// ```
+pub struct HeaderMap {
+    entries: Vec<(Key, Value)>,
+    index: HashMap<Key, usize>,
+}
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Looks `k` up by its lowercase form, which is folded on the stack
+    /// unless the key is unusually long.
+    fn position(&self, k: &str) -> Option<usize> {
+        if !k.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self.index.get(k).copied();
+        }
+        let mut buffer = [0; FOLD_BUFFER_LEN];
+        let Some(folded) = buffer.get_mut(..k.len()) else {
+            return self.index.get(k.to_ascii_lowercase().as_str()).copied();
+        };
+        folded.copy_from_slice(k.as_bytes());
+        folded.make_ascii_lowercase();
+        // Lowercasing ascii letters keeps the bytes UTF-8
+        self.index.get(std::str::from_utf8(folded).ok()?).copied()
+    }
+    pub fn get<K: AsRef<str>>(&self, k: K) -> Option<&Value> {
+        self.position(k.as_ref()).map(|i| &self.entries[i].1)
+    }
+    pub fn contains_key<K: AsRef<str>>(&self, k: K) -> bool {
+        self.position(k.as_ref()).is_some()
+    }
+    /// All values of `k`, one per field line.
+    pub fn get_all<'a>(&'a self, k: &'a str) -> impl Iterator<Item = &'a Value> {
+        self.entries.iter().filter(move |(key, _)| key.as_str().eq_ignore_ascii_case(k)).map(|(_, v)| v)
+    }
+    /// Sets the value of `k`, keeping the original position if it was
+    /// already present. Further field lines of `k` are dropped.
+    pub fn insert(&mut self, k: Key, v: Value) -> Option<Value> {
+        match self.index.get(&k) {
+            Some(&i) => {
+                let old = std::mem::replace(&mut self.entries[i].1, v);
+                if self.entries[i + 1..].iter().any(|(key, _)| key == &k) {
+                    let mut first = true;
+                    self.entries.retain(|(key, _)| key != &k || std::mem::take(&mut first));
+                    self.reindex();
+                }
+                Some(old)
+            },
+            None => {
+                self.index.insert(k.clone(), self.entries.len());
+                self.entries.push((k, v));
+                None
+            }
+        }
+    }
+    /// Inserts `v` only if `k` is not present yet.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{HeaderError, HeaderMap, Key, Value};
+    /// let mut headers = HeaderMap::new();
+    /// headers.try_insert(Key::CONTENT_TYPE, Value::new("text/html").unwrap()).unwrap();
+    /// assert_eq!(
+    ///     headers.try_insert(Key::CONTENT_TYPE, Value::new("text/plain").unwrap()),
+    ///     Err(HeaderError::Duplicate(Key::CONTENT_TYPE))
+    /// );
+    /// assert_eq!(headers.get("content-type").unwrap(), "text/html");
+    /// ```
+    pub fn try_insert(&mut self, k: Key, v: Value) -> Result<(), HeaderError> {
+        if self.index.contains_key(&k) {
+            return Err(HeaderError::Duplicate(k));
+        }
+        self.insert(k, v);
+        Ok(())
+    }
+    /// Adds `v` to the list of values of `k`, or inserts it if `k` is new.
+    ///
+    /// Fails if the combined value would break the
+    /// [default limits][ValueLimits::default], leaving the map unchanged.
+    pub fn append<V: AsRef<str>>(&mut self, k: Key, v: V) -> Result<(), ValueError> {
+        self.append_value(k, Value::new(v)?)
+    }
+    /// [append][HeaderMap::append] for an already validated value.
+    pub fn append_value(&mut self, k: Key, v: Value) -> Result<(), ValueError> {
+        self.append_value_with_limits(k, v, ValueLimits::default())
+    }
+    /// [append_value][HeaderMap::append_value] with other limits on the
+    /// combined value. Separate `set-cookie` lines count as its elements.
+    pub fn append_value_with_limits(&mut self, k: Key, v: Value, limits: ValueLimits) -> Result<(), ValueError> {
+        if k == "set-cookie" && self.index.contains_key(&k) {
+            let count = self.get_all("set-cookie").count() + 1;
+            if count > limits.elements {
+                return Err(ValueError::TooManyElements { count, limit: limits.elements });
+            }
+            self.entries.push((k, v));
+            return Ok(());
+        }
+        match self.index.get(&k) {
+            Some(&i) => self.entries[i].1.append_value_with_limits(&v, limits)?,
+            None => {
+                self.insert(k, v);
+            }
+        }
+        Ok(())
+    }
+    /// Like [append][HeaderMap::append], but skips members of `v` that the
+    /// value of `k` already lists, ignoring case. Returns whether anything
+    /// was added.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{HeaderMap, Key};
+    /// let mut headers = HeaderMap::new();
+    /// assert_eq!(headers.append_unique(Key::VARY, "accept-encoding"), Ok(true));
+    /// assert_eq!(headers.append_unique(Key::VARY, "Accept-Encoding"), Ok(false));
+    /// assert_eq!(headers.get("vary").unwrap(), "accept-encoding");
+    /// ```
+    pub fn append_unique<V: AsRef<str>>(&mut self, k: Key, v: V) -> Result<bool, ValueError> {
+        match self.index.get(&k) {
+            Some(&i) => self.entries[i].1.append_unique(v),
+            None => {
+                self.insert(k, Value::new(v)?);
+                Ok(true)
+            }
+        }
+    }
+    /// Parses field lines without their line endings, as they follow the
+    /// start line of a message.
+    ///
+    /// Values have to be ASCII unless `lenient`, which also accepts a
+    /// [singleton][Key::is_singleton] field repeated with the same value.
+    pub(crate) fn from_lines<'a, I: Iterator<Item = &'a [u8]>>(lines: I, lenient: bool) -> Result<Self, HeaderError> {
+        let mut h = HeaderMap::new();
+        for line in lines {
+            let colon = line.iter().position(|&b| b == b':').ok_or(HeaderError::MissingValue)?;
+            let (key, value) = (&line[..colon], &line[colon + 1..]);
+            let key = Key::new(std::str::from_utf8(key).map_err(|_| KeyError::non_ascii(key))?)?;
+            let value = if lenient || value.is_ascii() {
+                Value::from_bytes_with_obs_text(value)
+            } else {
+                Err(ValueError::non_ascii(value.trim_ascii()))
+            };
+            let value = value.map_err(|e| HeaderError::from(e).for_key(&key))?;
+            if !key.is_singleton() {
+                h.append_value(key.clone(), value).map_err(|e| HeaderError::from(e).for_key(&key))?;
+            } else if !(lenient && h.get(key.as_str()) == Some(&value)) {
+                h.try_insert(key, value)?;
+            }
+        }
+        Ok(h)
+    }
+    /// Removes all field lines of `k`, returning the first value.
+    pub fn remove<K: AsRef<str>>(&mut self, k: K) -> Option<Value> {
+        let i = self.position(k.as_ref())?;
+        let (k, v) = self.entries.remove(i);
+        self.entries.retain(|(key, _)| key != &k);
+        self.reindex();
+        Some(v)
+    }
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (k, _)) in self.entries.iter().enumerate() {
+            self.index.entry(k.clone()).or_insert(i);
+        }
+    }
+    /// Keeps only the field lines for which `f` returns `true`.
+    pub fn retain<F: FnMut(&Key, &Value) -> bool>(&mut self, mut f: F) {
+        self.entries.retain(|(k, v)| f(k, v));
+        self.reindex();
+    }
+    /// Removes the fields that only concern one connection: those the
+    /// registry marks [hop-by-hop][super::HeaderInfo::hop_by_hop] and
+    /// those named in `connection`, as a proxy must before forwarding a
+    /// message. Returns the removed keys in order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{HeaderMap, Key};
+    /// let mut headers = HeaderMap::new();
+    /// headers.append(Key::CONNECTION, "close, x-hop").unwrap();
+    /// headers.append(Key::new("x-hop").unwrap(), "1").unwrap();
+    /// headers.append(Key::HOST, "example.com").unwrap();
+    /// assert_eq!(headers.strip_hop_by_hop(), [Key::CONNECTION, Key::new("x-hop").unwrap()]);
+    /// assert_eq!(headers.len(), 1);
+    /// ```
+    pub fn strip_hop_by_hop(&mut self) -> Vec<Key> {
+        let named: Vec<Key> = self.get_all(Key::CONNECTION.as_str())
+            .flat_map(|v| v.as_str_lossy().split(',').filter_map(|name| Key::new(name.trim()).ok()).collect::<Vec<_>>())
+            .collect();
+        let mut removed = Vec::new();
+        self.retain(|k, _| {
+            let hop = k.info().is_some_and(|info| info.hop_by_hop) || named.contains(k);
+            if hop && !removed.contains(k) {
+                removed.push(k.clone());
+            }
+            !hop
+        });
+        removed
+    }
+    /// The field lines in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+    /// Number of field lines, which only differs from the number of keys
+    /// for `set-cookie`.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// [Appends][HeaderMap::append] every pair in order.
+///
+/// # Panics
+/// If a combined value gets longer than [MAX_VALUE_LEN][super::MAX_VALUE_LEN].
+impl Extend<(Key, Value)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (Key, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.append_value(k, v).expect("combined header value too long");
+        }
+    }
+}
+impl FromIterator<(Key, Value)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (Key, Value)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderMap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+/// Reads a map of keys to values, or a list of `[key, value]` pairs to
+/// repeat a key without relying on the format to allow duplicate map keys.
+/// Pairs are [append][HeaderMap::append]ed in order, and invalid keys or
+/// values fail with the message of their [KeyError][super::KeyError] or
+/// [ValueError].
+///
+/// As the shape is only known from the input, this needs a self-describing
+/// format like JSON.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::HeaderMap;
+/// let headers: HeaderMap = serde_json::from_str(r#"[["accept", "text/html"], ["Accept", "*/*"]]"#).unwrap();
+/// assert_eq!(headers.get("accept").unwrap(), "text/html,*/*");
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = HeaderMap;
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a map of header keys to values or a list of key-value pairs")
+            }
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<HeaderMap, A::Error> {
+                let mut headers = HeaderMap::new();
+                while let Some((k, v)) = map.next_entry::<Key, Value>()? {
+                    headers.append_value(k, v).map_err(serde::de::Error::custom)?;
+                }
+                Ok(headers)
+            }
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<HeaderMap, A::Error> {
+                let mut headers = HeaderMap::new();
+                while let Some((k, v)) = seq.next_element::<(Key, Value)>()? {
+                    headers.append_value(k, v).map_err(serde::de::Error::custom)?;
+                }
+                Ok(headers)
+            }
+        }
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (Key, Value);
+    type IntoIter = std::vec::IntoIter<(Key, Value)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a Key, &'a Value);
+    type IntoIter = std::iter::Map<std::slice::Iter<'a, (Key, Value)>, fn(&'a (Key, Value)) -> (&'a Key, &'a Value)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut m = HeaderMap::new();
+        for (k, v) in pairs {
+            m.append(Key::new(k).unwrap(), v).unwrap();
+        }
+        m
+    }
+    #[test]
+    fn strips_hop_by_hop() {
+        let mut m = map(&[
+            ("Connection", "close, X-Custom-Hop"),
+            ("x-custom-hop", "1"),
+            ("x-custom-keep", "2"),
+            ("transfer-encoding", "chunked"),
+            ("Proxy-Authorization", "Basic eA=="),
+            ("connection", "Keep-Alive"),
+            ("keep-alive", "timeout=5"),
+        ]);
+        let removed = m.strip_hop_by_hop();
+        let removed: Vec<_> = removed.iter().map(Key::as_str).collect();
+        assert_eq!(removed, ["connection", "x-custom-hop", "transfer-encoding", "proxy-authorization", "keep-alive"]);
+        assert_eq!(m, map(&[("x-custom-keep", "2")]));
+        assert!(m.strip_hop_by_hop().is_empty());
+    }
+    #[test]
+    fn keeps_insertion_order() {
+        let m = map(&[("b", "1"), ("a", "2"), ("c", "3"), ("B", "4")]);
+        let keys = m.iter().map(|(k, _)| k.to_string()).collect::<Vec<_>>();
+        assert_eq!(keys, ["b", "a", "c"]);
+        assert_eq!(m.get("b").unwrap(), "1,4");
+    }
+    #[test]
+    fn lookup_ignores_case() {
+        let m = map(&[("Content-Type", "text/plain")]);
+        assert_eq!(m.get("CONTENT-TYPE").unwrap(), "text/plain");
+        assert!(m.contains_key("content-type"));
+    }
+    #[test]
+    fn mixed_case_lookup_hits() {
+        let long = "x-".repeat(40) + "key";
+        let m = map(&[("content-length", "3"), (&long, "long")]);
+        for query in ["content-length", "Content-Length", "CONTENT-LENGTH", "cOnTeNt-LeNgTh"] {
+            assert_eq!(m.get(query).unwrap(), "3", "{query}");
+        }
+        assert_eq!(m.get(long.to_ascii_uppercase()).unwrap(), "long");
+        assert_eq!(m.get("Content-Lengthy"), None);
+        assert_eq!(m.get("K\u{212a}"), None);
+    }
+    #[test]
+    fn remove_keeps_index_consistent() {
+        let mut m = map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        assert_eq!(m.remove("A").unwrap(), "1");
+        assert_eq!(m.get("b").unwrap(), "2");
+        assert_eq!(m.get("c").unwrap(), "3");
+        assert_eq!(m.get("a"), None);
+    }
+    #[test]
+    fn insert_replaces_in_place() {
+        let mut m = map(&[("a", "1"), ("b", "2")]);
+        assert_eq!(m.insert(Key::new("A").unwrap(), Value::new("3").unwrap()).unwrap(), "1");
+        assert_eq!(m.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>(), ["a=3", "b=2"]);
+        assert_eq!(m.insert(Key::new("c").unwrap(), Value::new("4").unwrap()), None);
+        assert_eq!(m.len(), 3);
+    }
+    #[test]
+    fn insert_replaces_list() {
+        let mut m = map(&[("accept", "text/html"), ("Accept", "*/*")]);
+        assert_eq!(m.insert(Key::ACCEPT, Value::new("application/json").unwrap()).unwrap(), "text/html,*/*");
+        assert_eq!(m.get("accept").unwrap(), "application/json");
+        assert_eq!(m.try_insert(Key::ACCEPT, Value::new("text/plain").unwrap()), Err(HeaderError::Duplicate(Key::ACCEPT)));
+        assert_eq!(m.len(), 1);
+    }
+    #[test]
+    fn extend_appends() {
+        let mut m = map(&[("a", "1")]);
+        m.extend(map(&[("b", "2"), ("a", "3"), ("set-cookie", "x=1"), ("set-cookie", "y=2")]));
+        assert_eq!(m.get("a").unwrap(), "1,3");
+        assert_eq!(m.len(), 4);
+        assert_eq!(m, map(&[("a", "1,3"), ("b", "2"), ("set-cookie", "x=1"), ("set-cookie", "y=2")]));
+        assert_ne!(m, map(&[("b", "2"), ("a", "1,3"), ("set-cookie", "x=1"), ("set-cookie", "y=2")]));
+    }
+    #[test]
+    fn retain_reindexes() {
+        let mut m = map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        m.retain(|k, _| k.as_str() != "a");
+        assert_eq!(m.get("c").unwrap(), "3");
+        assert!(!m.contains_key("a"));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_map_or_pairs() {
+        let from_map: HeaderMap = serde_json::from_str(r#"{"Content-Type": "application/json"}"#).unwrap();
+        assert_eq!(from_map, map(&[("content-type", "application/json")]));
+        let from_pairs: HeaderMap = serde_json::from_str(r#"[["set-cookie", "a=1"], ["set-cookie", "b=2"]]"#).unwrap();
+        assert_eq!(from_pairs.get_all("set-cookie").collect::<Vec<_>>(), ["a=1", "b=2"]);
+        let error = serde_json::from_str::<HeaderMap>(r#"{"bad key": "x"}"#).unwrap_err();
+        assert!(error.to_string().contains(&crate::header::KeyError::IllegalChar { input: "bad key".to_owned(), found: ' ', at: 3 }.to_string()));
+        assert!(serde_json::from_str::<HeaderMap>(r#"[["accept"]]"#).is_err());
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_tokens() {
+        use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_tokens, Token};
+        assert_tokens(&Key::CONTENT_TYPE, &[Token::Str("content-type")]);
+        assert_de_tokens(&Key::CONTENT_TYPE, &[Token::Str("Content-Type")]);
+        assert_tokens(&Value::new("text/html").unwrap(), &[Token::Str("text/html")]);
+        assert_de_tokens_error::<Value>(&[Token::Str("a\nb")], &ValueError::illegal_char(b"a\nb", 1).to_string());
+        assert_tokens(&map(&[("a", "1"), ("b", "2")]), &[
+            Token::Map { len: Some(2) },
+            Token::Str("a"), Token::Str("1"),
+            Token::Str("b"), Token::Str("2"),
+            Token::MapEnd,
+        ]);
+        assert_de_tokens(&map(&[("a", "1,2")]), &[
+            Token::Seq { len: Some(2) },
+            Token::Tuple { len: 2 }, Token::Str("a"), Token::Str("1"), Token::TupleEnd,
+            Token::Tuple { len: 2 }, Token::Str("A"), Token::Str("2"), Token::TupleEnd,
+            Token::SeqEnd,
+        ]);
+    }
+    #[test]
+    fn set_cookie_lines_stay_separate() {
+        let mut m = map(&[("set-cookie", "a=1"), ("x", "y"), ("Set-Cookie", "b=2")]);
+        assert_eq!(m.get_all("set-cookie").collect::<Vec<_>>(), ["a=1", "b=2"]);
+        assert_eq!(m.iter().count(), 3);
+        assert_eq!(m.get("set-cookie").unwrap(), "a=1");
+        m.insert(Key::SET_COOKIE, Value::new("c=3").unwrap());
+        assert_eq!(m.get_all("set-cookie").collect::<Vec<_>>(), ["c=3"]);
+        assert_eq!(m.get("x").unwrap(), "y");
+        m.append(Key::SET_COOKIE, "d=4").unwrap();
+        assert_eq!(m.remove("set-cookie").unwrap(), "c=3");
+        assert_eq!(m.iter().count(), 1);
+        assert_eq!(m.get("x").unwrap(), "y");
+    }
+}
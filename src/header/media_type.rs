@@ -0,0 +1,284 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use super::{is_token, quoted, qvalue, MediaTypeError, QValue, Value};
+
+type Params = Vec<(String, String)>;
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// A media type like `text/html; charset=utf-8`, as in `content-type`.
+///
+/// Type, subtype and parameter names are kept in lowercase.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::MediaType;
+/// let html: MediaType = "Text/HTML; Charset=\"UTF-8\"".parse().unwrap();
+/// assert_eq!((html.type_(), html.subtype()), ("text", "html"));
+/// assert_eq!(html.get_param("charset"), Some("UTF-8"));
+/// assert_eq!(html.to_string(), "text/html; charset=UTF-8");
+/// ```
+pub struct MediaType {
+    type_: String,
+    subtype: String,
+    params: Params,
+}
+impl MediaType {
+    /// A media type without parameters; both parts have to be tokens.
+    pub fn new<T: AsRef<str>, S: AsRef<str>>(type_: T, subtype: S) -> Result<Self, MediaTypeError> {
+        let (type_, subtype) = (type_.as_ref(), subtype.as_ref());
+        if !is_token(type_) || !is_token(subtype) || type_ == "*" || subtype == "*" {
+            return Err(MediaTypeError::Malformed);
+        }
+        Ok(Self { type_: type_.to_ascii_lowercase(), subtype: subtype.to_ascii_lowercase(), params: vec![] })
+    }
+    /// Adds a parameter. The name has to be a token, the value ascii
+    /// without control characters.
+    pub fn param<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Result<Self, MediaTypeError> {
+        self.params.push(checked_param(name.as_ref(), value.as_ref())?);
+        Ok(self)
+    }
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+    /// The value of the first parameter called `name`, ignoring case.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        get_param(&self.params, name)
+    }
+}
+impl FromStr for MediaType {
+    type Err = MediaTypeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (type_, subtype, params) = parse_parts(s)?;
+        Ok(Self { params, ..Self::new(type_, subtype)? })
+    }
+}
+impl Display for MediaType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        write_params(f, &self.params)
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Eq)]
+/// A media range of `accept`, such as `text/*` or `text/html;level=1`.
+///
+/// `*/*` matches any media type, `type/*` any subtype of the type. A
+/// range with parameters only matches media types that have all of them.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{MediaRange, MediaType};
+/// let range: MediaRange = "text/*".parse().unwrap();
+/// assert!(range.matches(&"text/html; charset=utf-8".parse::<MediaType>().unwrap()));
+/// assert!(!range.matches(&"image/png".parse::<MediaType>().unwrap()));
+/// ```
+pub struct MediaRange {
+    type_: String,
+    subtype: String,
+    params: Params,
+}
+impl MediaRange {
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+    /// The value of the first parameter called `name`, ignoring case.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        get_param(&self.params, name)
+    }
+    /// Whether `media_type` falls into the range. Types and parameter
+    /// names are compared ignoring case, parameter values exactly, except
+    /// for `charset`.
+    pub fn matches(&self, media_type: &MediaType) -> bool {
+        (self.type_ == "*" || self.type_ == media_type.type_)
+            && (self.subtype == "*" || self.subtype == media_type.subtype)
+            && self.params.iter().all(|(name, value)| media_type.get_param(name).is_some_and(|v| {
+                if name == "charset" { v.eq_ignore_ascii_case(value) } else { v == value }
+            }))
+    }
+    /// Orders ranges from `*/*` over `type/*` and `type/subtype` to ranges
+    /// with more parameters.
+    fn specificity(&self) -> (u8, usize) {
+        let wildcards = match (self.type_.as_str(), self.subtype.as_str()) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ => 2,
+        };
+        (wildcards, self.params.len())
+    }
+    /// Parses an `accept` value into its ranges and their weights.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{MediaRange, Value};
+    /// let accept = MediaRange::parse_accept(&Value::new("text/html, */*;q=0.1").unwrap()).unwrap();
+    /// assert_eq!(accept[1].0.to_string(), "*/*");
+    /// assert_eq!(accept[1].1.thousandths(), 100);
+    /// ```
+    pub fn parse_accept(value: &Value) -> Result<Vec<(Self, QValue)>, MediaTypeError> {
+        value.elements()
+            .map(|element| {
+                let (range, q) = qvalue::split(element);
+                Ok((range.parse()?, q?))
+            })
+            .collect()
+    }
+}
+impl FromStr for MediaRange {
+    type Err = MediaTypeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (type_, subtype, params) = parse_parts(s)?;
+        if type_ == "*" && subtype != "*" {
+            return Err(MediaTypeError::Malformed);
+        }
+        Ok(Self { type_: type_.to_ascii_lowercase(), subtype: subtype.to_ascii_lowercase(), params })
+    }
+}
+impl Display for MediaRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+        write_params(f, &self.params)
+    }
+}
+
+/// The weight `accept` gives to `media_type`, taken from the most specific
+/// matching range; zero if none matches.
+pub fn quality(accept: &[(MediaRange, QValue)], media_type: &MediaType) -> QValue {
+    accept.iter()
+        .filter(|(range, _)| range.matches(media_type))
+        .max_by_key(|(range, _)| range.specificity())
+        .map_or(QValue::ZERO, |(_, q)| *q)
+}
+
+/// Picks the media type of `offered` with the highest [quality] under
+/// `accept`, the first one on ties. An empty `accept` accepts anything;
+/// `None` means nothing offered is acceptable, which calls for a
+/// [406][crate::Response::NotAcceptable].
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{media_type, MediaRange, MediaType, Value};
+/// let accept = MediaRange::parse_accept(&Value::new("text/*;q=0.5, application/json").unwrap()).unwrap();
+/// let offered = ["text/html".parse::<MediaType>().unwrap(), "application/json".parse().unwrap()];
+/// assert_eq!(media_type::negotiate(&accept, &offered), Some(&offered[1]));
+/// ```
+pub fn negotiate<'a>(accept: &[(MediaRange, QValue)], offered: &'a [MediaType]) -> Option<&'a MediaType> {
+    if accept.is_empty() {
+        return offered.first();
+    }
+    let mut best: Option<(&MediaType, QValue)> = None;
+    for media_type in offered {
+        let q = quality(accept, media_type);
+        if !q.is_zero() && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((media_type, q));
+        }
+    }
+    best.map(|(media_type, _)| media_type)
+}
+
+/// Splits `type/subtype; name=value` into its parts, with parameter names
+/// in lowercase and values unquoted. Either part may be `*`.
+fn parse_parts(s: &str) -> Result<(&str, &str, Params), MediaTypeError> {
+    let (essence, mut rest) = s.split_once(';').map_or((s, ""), |(e, r)| (e, r));
+    let (type_, subtype) = essence.trim().split_once('/').ok_or(MediaTypeError::Malformed)?;
+    if !is_token(type_) || !is_token(subtype) {
+        return Err(MediaTypeError::Malformed);
+    }
+    let mut params = vec![];
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok((type_, subtype, params));
+        }
+        let (name, tail) = rest.split_once('=').ok_or(MediaTypeError::Malformed)?;
+        let (value, tail) = quoted::parse_token_or_quoted(tail).map_err(|_| MediaTypeError::Malformed)?;
+        if !is_token(name) {
+            return Err(MediaTypeError::Malformed);
+        }
+        params.push((name.to_ascii_lowercase(), value));
+        rest = tail.trim_start();
+        rest = match rest.strip_prefix(';') {
+            Some(tail) => tail,
+            None if rest.is_empty() => rest,
+            None => return Err(MediaTypeError::Malformed),
+        };
+    }
+}
+
+fn checked_param(name: &str, value: &str) -> Result<(String, String), MediaTypeError> {
+    if !is_token(name) || value.bytes().any(|b| !b.is_ascii() || (b.is_ascii_control() && b != b'\t')) {
+        return Err(MediaTypeError::IllegalChars);
+    }
+    Ok((name.to_ascii_lowercase(), value.to_owned()))
+}
+
+fn get_param<'a>(params: &'a Params, name: &str) -> Option<&'a str> {
+    params.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+fn write_params(f: &mut Formatter<'_>, params: &Params) -> FmtResult {
+    for (name, value) in params {
+        write!(f, "; {name}={}", quoted::quote(value))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media_type(s: &str) -> MediaType {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn rfc_precedence_example() {
+        let accept = MediaRange::parse_accept(&Value::new(
+            "text/*;q=0.3, text/html;q=0.7, text/html;level=1, text/html;level=2;q=0.4, */*;q=0.5"
+        ).unwrap()).unwrap();
+        for (offered, q) in [
+            ("text/html;level=1", 1000),
+            ("text/html", 700),
+            ("text/plain", 300),
+            ("image/jpeg", 500),
+            ("text/html;level=2", 400),
+            ("text/html;level=3", 700),
+        ] {
+            assert_eq!(quality(&accept, &media_type(offered)).thousandths(), q, "{offered}");
+        }
+    }
+    #[test]
+    fn specific_beats_wildcard() {
+        let accept = MediaRange::parse_accept(&Value::new("*/*, image/*;q=0").unwrap()).unwrap();
+        let offered = [media_type("image/png"), media_type("text/plain")];
+        assert_eq!(negotiate(&accept, &offered), Some(&offered[1]));
+        assert_eq!(negotiate(&accept, &offered[..1]), None);
+        assert_eq!(negotiate(&[], &offered), Some(&offered[0]));
+    }
+    #[test]
+    fn parameters_must_be_present() {
+        let range: MediaRange = "text/plain; charset=UTF-8".parse().unwrap();
+        assert!(range.matches(&media_type("TEXT/plain;charset=utf-8;format=flowed")));
+        assert!(!range.matches(&media_type("text/plain")));
+        assert!(!range.matches(&media_type("text/plain;charset=latin1")));
+    }
+    #[test]
+    fn reject_malformed() {
+        assert_eq!("*/html".parse::<MediaRange>(), Err(MediaTypeError::Malformed));
+        assert_eq!("text/*".parse::<MediaType>(), Err(MediaTypeError::Malformed));
+        assert_eq!("text".parse::<MediaType>(), Err(MediaTypeError::Malformed));
+        assert_eq!("text/html; level".parse::<MediaType>(), Err(MediaTypeError::Malformed));
+        assert_eq!(
+            MediaRange::parse_accept(&Value::new("text/html;q=2").unwrap()),
+            Err(MediaTypeError::QValue(crate::header::QValueError::OutOfRange))
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use super::{is_tchar, is_token, QuotedError};
+
+/// Writes `s` bare if it is a token, otherwise as a quoted string.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::quoted;
+/// assert_eq!(quoted::quote("utf-8"), "utf-8");
+/// assert_eq!(quoted::quote(r#"say "hi""#), r#""say \"hi\"""#);
+/// assert_eq!(quoted::quote(""), r#""""#);
+/// ```
+pub fn quote(s: &str) -> String {
+    if is_token(s) {
+        s.to_owned()
+    } else {
+        quote_always(s)
+    }
+}
+
+/// Writes `s` as a quoted string, escaping `"` and `\`, for fields like
+/// the `realm` of a challenge that only allow the quoted form.
+pub fn quote_always(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The unescaped content of `s`, which has to be a single quoted string.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{quoted, QuotedError};
+/// assert_eq!(quoted::unquote(r#""a \"b\" \\ c""#), Ok(r#"a "b" \ c"#.to_owned()));
+/// assert_eq!(quoted::unquote(r#""open"#), Err(QuotedError::Unterminated));
+/// assert_eq!(quoted::unquote("\"line\nbreak\""), Err(QuotedError::IllegalChars));
+/// ```
+pub fn unquote(s: &str) -> Result<String, QuotedError> {
+    match split(s)? {
+        (content, "") => Ok(content),
+        _ => Err(QuotedError::Malformed),
+    }
+}
+
+/// Reads a token or a quoted string from the start of `s`, as parameter
+/// values are written, returning the unescaped value and the remaining
+/// input.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::quoted;
+/// assert_eq!(quoted::parse_token_or_quoted("utf-8; x=y"), Ok(("utf-8".to_owned(), "; x=y")));
+/// assert_eq!(quoted::parse_token_or_quoted(r#""a;b"; x=y"#), Ok(("a;b".to_owned(), "; x=y")));
+/// ```
+pub fn parse_token_or_quoted(s: &str) -> Result<(String, &str), QuotedError> {
+    if s.starts_with('"') {
+        return split(s);
+    }
+    let end = s.bytes().position(|b| !is_tchar(b)).unwrap_or(s.len());
+    if end == 0 {
+        return Err(QuotedError::Malformed);
+    }
+    Ok((s[..end].to_owned(), &s[end..]))
+}
+
+/// Reads a quoted string from the start of `s`, returning its unescaped
+/// content and the remaining input.
+pub(crate) fn split(s: &str) -> Result<(String, &str), QuotedError> {
+    let mut chars = s.strip_prefix('"').ok_or(QuotedError::Malformed)?.char_indices();
+    let mut content = String::new();
+    while let Some((i, c)) = chars.next() {
+        let c = match c {
+            '"' => return Ok((content, &s[i + 2..])),
+            '\\' => chars.next().ok_or(QuotedError::Unterminated)?.1,
+            c => c,
+        };
+        if c.is_ascii_control() && c != '\t' {
+            return Err(QuotedError::IllegalChars);
+        }
+        content.push(c);
+    }
+    Err(QuotedError::Unterminated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_escapes() {
+        let original = r#"\"quoted\" \\"#;
+        let quoted = quote(original);
+        assert_eq!(quoted, r#""\\\"quoted\\\" \\\\""#);
+        assert_eq!(unquote(&quoted), Ok(original.to_owned()));
+        assert_eq!(unquote(r#""\\\"""#), Ok(r#"\""#.to_owned()));
+    }
+    #[test]
+    fn token_stays_bare() {
+        assert_eq!(quote("flowed"), "flowed");
+        assert_eq!(parse_token_or_quoted(&quote("flowed")), Ok(("flowed".to_owned(), "")));
+        assert_eq!(quote_always("flowed"), r#""flowed""#);
+    }
+    #[test]
+    fn reject_invalid() {
+        assert_eq!(unquote(r#""a"b"#), Err(QuotedError::Malformed));
+        assert_eq!(unquote("bare"), Err(QuotedError::Malformed));
+        assert_eq!(unquote(r#""trailing\"#), Err(QuotedError::Unterminated));
+        assert_eq!(unquote("\"a\\\rb\""), Err(QuotedError::IllegalChars));
+        assert_eq!(parse_token_or_quoted("; x"), Err(QuotedError::Malformed));
+    }
+}
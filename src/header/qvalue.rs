@@ -0,0 +1,138 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::QValueError;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+/// The weight (`q`) of an element in `accept`, `accept-encoding`,
+/// `accept-language` or `te`, between 0 and 1 with at most three decimals.
+///
+/// Stored in thousandths, so weights compare exactly.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{qvalue, QValue};
+/// let q = qvalue::parse("0.850").unwrap();
+/// assert_eq!(q.thousandths(), 850);
+/// assert!(q < QValue::ONE);
+/// assert_eq!(q.to_string(), "0.85");
+/// ```
+pub struct QValue(u16);
+impl QValue {
+    /// Not acceptable
+    pub const ZERO: QValue = QValue(0);
+    /// Most preferred, and the weight of elements without `q`
+    pub const ONE: QValue = QValue(1000);
+
+    /// `None` if `n` is above 1000.
+    pub const fn from_thousandths(n: u16) -> Option<Self> {
+        if n > 1000 {
+            None
+        } else {
+            Some(Self(n))
+        }
+    }
+    pub fn thousandths(self) -> u16 {
+        self.0
+    }
+    /// Whether the element is refused.
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+    pub fn as_f32(self) -> f32 {
+        self.0 as f32 / 1000.0
+    }
+}
+impl Default for QValue {
+    fn default() -> Self {
+        Self::ONE
+    }
+}
+/// The shortest decimal form, e.g. `1`, `0.5` or `0.125`.
+impl Display for QValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.0 {
+            1000 => write!(f, "1"),
+            0 => write!(f, "0"),
+            n => write!(f, "{}", format!("0.{n:03}").trim_end_matches('0')),
+        }
+    }
+}
+
+/// Parses a qvalue as in `q=0.5`, without the `q=`.
+///
+/// Following RFC 9110, the integer part is `0` or `1`, followed by at
+/// most three decimals, which have to be zeros after `1`.
+pub fn parse(s: &str) -> Result<QValue, QValueError> {
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    if int.is_empty() || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+        return Err(QValueError::Malformed);
+    }
+    if frac.len() > 3 {
+        return Err(QValueError::TooPrecise);
+    }
+    let frac = frac.bytes()
+        .zip([100, 10, 1])
+        .map(|(d, place)| (d - b'0') as u16 * place)
+        .sum::<u16>();
+    match int {
+        "0" => Ok(QValue(frac)),
+        "1" if frac == 0 => Ok(QValue::ONE),
+        _ => Err(QValueError::OutOfRange),
+    }
+}
+
+/// Splits a list element like `gzip;q=0.5` into the item before the
+/// weight and the weight, which is [QValue::ONE] if absent. The item
+/// keeps its own parameters, e.g. `text/html;level=1` of
+/// `text/html;level=1;q=0.5`, while extension parameters after the weight
+/// are dropped. The parameter name is case-insensitive.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{qvalue, QValue, QValueError};
+/// assert_eq!(qvalue::split(" gzip ; Q=0.5"), ("gzip", qvalue::parse("0.5")));
+/// assert_eq!(qvalue::split("br"), ("br", Ok(QValue::ONE)));
+/// assert_eq!(qvalue::split("br;q=2"), ("br", Err(QValueError::OutOfRange)));
+/// ```
+pub fn split(element: &str) -> (&str, Result<QValue, QValueError>) {
+    let element = element.trim();
+    for (i, _) in element.match_indices(';') {
+        let param = element[i + 1..].split(';').next().unwrap_or_default().trim();
+        if let Some(q) = param.strip_prefix("q=").or_else(|| param.strip_prefix("Q=")) {
+            return (element[..i].trim_end(), parse(q));
+        }
+    }
+    (element, Ok(QValue::ONE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds() {
+        assert_eq!(parse("1.000"), Ok(QValue::ONE));
+        assert_eq!(parse("1."), Ok(QValue::ONE));
+        assert_eq!(parse("0"), Ok(QValue::ZERO));
+        assert_eq!(parse("0.001").unwrap().thousandths(), 1);
+        assert_eq!(parse("1.5"), Err(QValueError::OutOfRange));
+        assert_eq!(parse("2"), Err(QValueError::OutOfRange));
+        assert_eq!(parse("0.1234"), Err(QValueError::TooPrecise));
+        assert_eq!(parse(".5"), Err(QValueError::Malformed));
+        assert_eq!(parse("-0"), Err(QValueError::Malformed));
+    }
+    #[test]
+    fn split_parameters() {
+        assert_eq!(split("gzip;Q=0.5"), ("gzip", Ok(QValue(500))));
+        assert_eq!(split("text/html;level=1;q=0.2;ext=x"), ("text/html;level=1", Ok(QValue(200))));
+        assert_eq!(split("text/html;level=1"), ("text/html;level=1", Ok(QValue::ONE)));
+        assert_eq!(split("a;q=0"), ("a", Ok(QValue::ZERO)));
+    }
+    #[test]
+    fn display() {
+        assert_eq!(QValue(1000).to_string(), "1");
+        assert_eq!(QValue(500).to_string(), "0.5");
+        assert_eq!(QValue(5).to_string(), "0.005");
+        assert_eq!(QValue(0).to_string(), "0");
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::RangeInclusive,
+};
+
+use super::RangeError;
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+/// A single range out of a `range: bytes=...` request header.
+pub enum ByteRange {
+    /// `first-last`, both inclusive
+    FromTo(u64, u64),
+    /// `first-`, up to the end of the representation
+    From(u64),
+    /// `-length`, the last `length` bytes
+    Suffix(u64),
+}
+impl ByteRange {
+    /// Most ranges [parse_header][Self::parse_header] accepts in one header
+    pub const MAX_RANGES: usize = 100;
+    /// Parses the value of a `range` header into its ranges, refusing
+    /// more than [MAX_RANGES][Self::MAX_RANGES] of them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::ByteRange;
+    /// let ranges = ByteRange::parse_header("bytes=0-9, 20-, -5").unwrap();
+    /// assert_eq!(ranges, [ByteRange::FromTo(0, 9), ByteRange::From(20), ByteRange::Suffix(5)]);
+    /// ```
+    pub fn parse_header(s: &str) -> Result<Vec<Self>, RangeError> {
+        let (unit, ranges) = s.trim().split_once('=').ok_or(RangeError::Invalid)?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return Err(RangeError::UnsupportedUnit);
+        }
+        let ranges = ranges.split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .take(Self::MAX_RANGES + 1)
+            .map(Self::parse_one)
+            .collect::<Result<Vec<_>, _>>()?;
+        if ranges.is_empty() {
+            return Err(RangeError::Invalid);
+        }
+        if ranges.len() > Self::MAX_RANGES {
+            return Err(RangeError::TooMany);
+        }
+        Ok(ranges)
+    }
+    fn parse_one(s: &str) -> Result<Self, RangeError> {
+        let (first, last) = s.split_once('-').ok_or(RangeError::Invalid)?;
+        let number = |n: &str| n.trim().parse::<u64>().map_err(|_| RangeError::Invalid);
+        match (first.trim().is_empty(), last.trim().is_empty()) {
+            (true, false) => Ok(Self::Suffix(number(last)?)),
+            (false, true) => Ok(Self::From(number(first)?)),
+            (false, false) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    return Err(RangeError::Invalid);
+                }
+                Ok(Self::FromTo(first, last))
+            },
+            (true, true) => Err(RangeError::Invalid),
+        }
+    }
+    /// The inclusive byte positions this range selects out of a
+    /// representation of `len` bytes, or `None` if it is unsatisfiable.
+    pub fn resolve(&self, len: u64) -> Option<RangeInclusive<u64>> {
+        match *self {
+            Self::FromTo(first, last) if first < len => Some(first..=last.min(len - 1)),
+            Self::From(first) if first < len => Some(first..=len - 1),
+            Self::Suffix(n) if n > 0 && len > 0 => Some(len - n.min(len)..=len - 1),
+            _ => None,
+        }
+    }
+    /// Like [resolve][Self::resolve], but reports an unsatisfiable range
+    /// in a form that converts straight into a
+    /// [416][crate::Response::RangeNotSatisfiable] response.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{header::ByteRange, response::{Incomplete, ResponseBuilder}};
+    /// let answer: ResponseBuilder<Incomplete> = match ByteRange::From(50).try_resolve(10) {
+    ///     Ok(_) => unreachable!(),
+    ///     Err(unsatisfiable) => unsatisfiable.into(),
+    /// };
+    /// assert_eq!(answer.get_header("content-range").unwrap(), "bytes */10");
+    /// ```
+    pub fn try_resolve(&self, len: u64) -> Result<RangeInclusive<u64>, Unsatisfiable> {
+        self.resolve(len).ok_or(Unsatisfiable { complete_length: len })
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+/// A range that selects nothing out of a representation of
+/// `complete_length` bytes.
+pub struct Unsatisfiable {
+    pub complete_length: u64,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+/// The value of a `content-range` response header.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::ContentRange;
+/// assert_eq!(ContentRange::Bytes { first: 0, last: 9, complete_length: Some(100) }.to_string(),
+///     "bytes 0-9/100");
+/// assert_eq!(ContentRange::Unsatisfied { complete_length: 100 }.to_string(), "bytes */100");
+/// ```
+pub enum ContentRange {
+    /// The enclosed bytes, with the full length if it is known
+    Bytes { first: u64, last: u64, complete_length: Option<u64> },
+    /// Sent with [416][crate::Response::RangeNotSatisfiable] to tell the
+    /// client the actual length
+    Unsatisfied { complete_length: u64 },
+}
+impl Display for ContentRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Bytes { first, last, complete_length: Some(len) } => write!(f, "bytes {first}-{last}/{len}"),
+            Self::Bytes { first, last, complete_length: None } => write!(f, "bytes {first}-{last}/*"),
+            Self::Unsatisfied { complete_length } => write!(f, "bytes */{complete_length}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_clamps_to_length() {
+        assert_eq!(ByteRange::FromTo(5, 500).resolve(10), Some(5..=9));
+        assert_eq!(ByteRange::Suffix(500).resolve(10), Some(0..=9));
+        assert_eq!(ByteRange::From(10).resolve(10), None);
+        assert_eq!(ByteRange::Suffix(0).resolve(10), None);
+    }
+    #[test]
+    fn parse_rejects_other_units_and_garbage() {
+        assert_eq!(ByteRange::parse_header("items=0-1"), Err(RangeError::UnsupportedUnit));
+        assert_eq!(ByteRange::parse_header("bytes=5-1"), Err(RangeError::Invalid));
+        assert_eq!(ByteRange::parse_header("bytes=-"), Err(RangeError::Invalid));
+        assert_eq!(ByteRange::parse_header("bytes="), Err(RangeError::Invalid));
+    }
+    #[test]
+    fn parse_limits_count() {
+        let ranges = |n| format!("bytes={}", vec!["0-"; n].join(","));
+        assert_eq!(ByteRange::parse_header(&ranges(ByteRange::MAX_RANGES)).unwrap().len(), ByteRange::MAX_RANGES);
+        assert_eq!(ByteRange::parse_header(&ranges(ByteRange::MAX_RANGES + 1)), Err(RangeError::TooMany));
+    }
+}
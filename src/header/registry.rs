@@ -0,0 +1,101 @@
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+/// What the crate knows about a standard field, see [Key::info][super::Key::info].
+pub struct HeaderInfo {
+    /// The field holds one value instead of a comma-separated list, so a
+    /// second field line is an error rather than more members
+    pub singleton: bool,
+    /// The field only concerns the current connection and is not
+    /// forwarded by proxies (RFC 9110, section 7.6.1)
+    pub hop_by_hop: bool,
+    /// The field may not be sent in a trailer section (RFC 9110, section 6.5.1)
+    pub forbidden_in_trailers: bool,
+}
+
+const LIST: HeaderInfo = HeaderInfo { singleton: false, hop_by_hop: false, forbidden_in_trailers: false };
+const SINGLETON: HeaderInfo = HeaderInfo { singleton: true, ..LIST };
+const HOP_BY_HOP: HeaderInfo = HeaderInfo { hop_by_hop: true, ..LIST };
+const LIST_NOT_TRAILER: HeaderInfo = HeaderInfo { forbidden_in_trailers: true, ..LIST };
+const SINGLETON_NOT_TRAILER: HeaderInfo = HeaderInfo { forbidden_in_trailers: true, ..SINGLETON };
+const HOP_BY_HOP_NOT_TRAILER: HeaderInfo = HeaderInfo { forbidden_in_trailers: true, ..HOP_BY_HOP };
+
+/// Known fields, sorted by name for binary search.
+const REGISTRY: &[(&str, HeaderInfo)] = &[
+    ("accept", LIST),
+    ("accept-charset", LIST),
+    ("accept-encoding", LIST),
+    ("accept-language", LIST),
+    ("accept-ranges", LIST),
+    ("access-control-allow-credentials", SINGLETON),
+    ("access-control-allow-headers", LIST),
+    ("access-control-allow-methods", LIST),
+    ("access-control-allow-origin", SINGLETON),
+    ("access-control-expose-headers", LIST),
+    ("access-control-max-age", SINGLETON),
+    ("access-control-request-headers", LIST),
+    ("access-control-request-method", SINGLETON),
+    ("age", SINGLETON),
+    ("allow", LIST),
+    ("authorization", SINGLETON_NOT_TRAILER),
+    ("cache-control", LIST_NOT_TRAILER),
+    ("connection", HOP_BY_HOP),
+    ("content-disposition", SINGLETON),
+    ("content-encoding", LIST_NOT_TRAILER),
+    ("content-language", LIST),
+    ("content-length", SINGLETON_NOT_TRAILER),
+    ("content-location", SINGLETON),
+    ("content-range", SINGLETON_NOT_TRAILER),
+    ("content-type", SINGLETON_NOT_TRAILER),
+    // Not a comma-separated list, but senders split cookies over lines
+    ("cookie", LIST_NOT_TRAILER),
+    ("date", SINGLETON),
+    ("etag", SINGLETON),
+    ("expect", LIST_NOT_TRAILER),
+    ("expires", SINGLETON),
+    ("forwarded", LIST),
+    ("host", SINGLETON_NOT_TRAILER),
+    ("if-match", LIST),
+    ("if-modified-since", SINGLETON),
+    ("if-none-match", LIST),
+    ("if-range", SINGLETON),
+    ("if-unmodified-since", SINGLETON),
+    ("keep-alive", HOP_BY_HOP),
+    ("last-modified", SINGLETON),
+    ("link", LIST),
+    ("location", SINGLETON),
+    ("max-forwards", SINGLETON_NOT_TRAILER),
+    ("origin", SINGLETON),
+    ("pragma", LIST_NOT_TRAILER),
+    // Hop-by-hop in RFC 2616; proxies consume them rather than forward them
+    ("proxy-authenticate", HOP_BY_HOP_NOT_TRAILER),
+    ("proxy-authorization", HeaderInfo { singleton: true, ..HOP_BY_HOP_NOT_TRAILER }),
+    ("proxy-connection", HOP_BY_HOP),
+    ("range", SINGLETON_NOT_TRAILER),
+    ("referer", SINGLETON),
+    ("retry-after", SINGLETON),
+    ("server", SINGLETON),
+    ("set-cookie", LIST_NOT_TRAILER),
+    ("te", HOP_BY_HOP_NOT_TRAILER),
+    ("trailer", HOP_BY_HOP_NOT_TRAILER),
+    ("transfer-encoding", HOP_BY_HOP_NOT_TRAILER),
+    ("upgrade", HOP_BY_HOP),
+    ("user-agent", SINGLETON),
+    ("vary", LIST),
+    ("via", LIST),
+    ("www-authenticate", LIST_NOT_TRAILER),
+];
+
+/// The entry for a lowercase field name.
+pub(crate) fn lookup(name: &str) -> Option<&'static HeaderInfo> {
+    REGISTRY.binary_search_by_key(&name, |(n, _)| n).ok().map(|i| &REGISTRY[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted_for_binary_search() {
+        assert!(REGISTRY.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(REGISTRY.iter().all(|(name, _)| lookup(name).is_some()));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{is_token, qvalue, QValue};
+
+#[derive(PartialEq, Debug, Clone)]
+/// A transfer coding listed in a `te` header.
+pub struct TransferCoding {
+    /// The coding name in lowercase
+    pub name: String,
+    /// The rank (`q`); zero means the coding is refused
+    pub rank: QValue,
+}
+
+#[derive(PartialEq, Debug, Clone, Default)]
+/// The transfer codings a client accepts in a response, as sent in the
+/// `te` request header, and whether it accepts a trailer section.
+///
+/// Parsing is lenient: elements that are not tokens are skipped and an
+/// invalid rank counts as 0.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::{QValue, Te};
+/// let te = Te::parse("trailers, deflate;q=0.5");
+/// assert!(te.trailers());
+/// assert_eq!(te.rank("deflate"), QValue::from_thousandths(500));
+/// assert_eq!(te.rank("gzip"), None);
+/// ```
+pub struct Te {
+    trailers: bool,
+    codings: Vec<TransferCoding>,
+}
+impl Te {
+    pub fn parse(s: &str) -> Self {
+        let mut te = Self::default();
+        for element in s.split(',') {
+            let (name, rank) = qvalue::split(element);
+            let name = name.split(';').next().unwrap_or_default().trim_end().to_ascii_lowercase();
+            if !is_token(&name) {
+                continue;
+            }
+            if name == "trailers" {
+                te.trailers = true;
+                continue;
+            }
+            te.codings.push(TransferCoding { name, rank: rank.unwrap_or(QValue::ZERO) });
+        }
+        te
+    }
+    /// Whether the client accepts a trailer section in a chunked response.
+    pub fn trailers(&self) -> bool {
+        self.trailers
+    }
+    /// The listed transfer codings, without `trailers`.
+    pub fn codings(&self) -> &[TransferCoding] {
+        &self.codings
+    }
+    /// The rank of `coding`, compared ignoring case, if it is listed.
+    pub fn rank(&self, coding: &str) -> Option<QValue> {
+        self.codings.iter().find(|c| c.name.eq_ignore_ascii_case(coding)).map(|c| c.rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailers_and_ranks() {
+        let te = Te::parse("Trailers, deflate;q=0.5, gzip;q=2");
+        assert!(te.trailers());
+        assert_eq!(te.codings(), [
+            TransferCoding { name: "deflate".to_owned(), rank: QValue::from_thousandths(500).unwrap() },
+            TransferCoding { name: "gzip".to_owned(), rank: QValue::ZERO },
+        ]);
+    }
+    #[test]
+    fn empty() {
+        let te = Te::parse("");
+        assert!(!te.trailers());
+        assert!(te.codings().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use super::{is_token, ValueError};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A protocol listed in an `upgrade` header, such as `websocket` or
+/// `h2c/2`.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::Protocol;
+/// let protocols = Protocol::parse_list("h2c, websocket/13");
+/// assert_eq!(protocols[1], Protocol { name: "websocket".to_owned(), version: Some("13".to_owned()) });
+/// assert_eq!(protocols[1].to_string(), "websocket/13");
+/// ```
+pub struct Protocol {
+    pub name: String,
+    pub version: Option<String>,
+}
+impl Protocol {
+    /// Parses a comma-separated list leniently, skipping elements that are
+    /// no protocol.
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split(',').filter_map(|element| element.trim().parse().ok()).collect()
+    }
+    /// Whether the name is `name`, ignoring case.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+}
+impl FromStr for Protocol {
+    type Err = ValueError;
+    /// Parses `name` or `name/version`, both tokens.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, version) = match s.split_once('/') {
+            Some((name, version)) => (name, Some(version)),
+            None => (s, None),
+        };
+        for token in [Some(name), version].into_iter().flatten() {
+            if !is_token(token) {
+                return Err(ValueError::not_token(token));
+            }
+        }
+        Ok(Self { name: name.to_owned(), version: version.map(str::to_owned) })
+    }
+}
+impl Display for Protocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.name)?;
+        match &self.version {
+            Some(version) => write!(f, "/{version}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenient_list() {
+        let protocols = Protocol::parse_list("h2c, , websocket/13, bad proto, x/");
+        assert_eq!(protocols, [
+            Protocol { name: "h2c".to_owned(), version: None },
+            Protocol { name: "websocket".to_owned(), version: Some("13".to_owned()) },
+        ]);
+        assert!(protocols[1].is("WebSocket"));
+        assert_eq!("x/".parse::<Protocol>(), Err(ValueError::EmptyString));
+    }
+}
@@ -1,72 +1,438 @@
 use std::{
-    borrow::Borrow,
+    borrow::Cow,
     fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+    time::SystemTime,
 };
 
-use super::ValueError;
+use super::{is_token, ValueError, MAX_VALUE_ELEMENTS, MAX_VALUE_LEN};
 
 /// Encodes valid header values that fit the standard requirements:
 /// - No empty string
-/// - No non-ascii characters
+/// - No non-ascii characters, unless built with
+///   [from_bytes_with_obs_text][Value::from_bytes_with_obs_text]
 /// - no \r, \n or \0 characters
 /// - Removing leading and trailing whitespace
+/// - At most [MAX_VALUE_LEN] bytes, unless built with
+///   [new_with_limit][Value::new_with_limit]
+///
+/// The value is kept as the bytes it is sent as.
 #[derive(PartialEq, Clone, Debug, Eq)]
-pub struct Value(String);
+pub struct Value(Vec<u8>);
 impl Value {
     /// Validates the constraints on strings by the standard.
-    pub(crate) fn new<S: AsRef<str>>(s: S) -> Result<Self, ValueError> {
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{Value, ValueError};
+    /// assert_eq!(Value::new("  text/html ").unwrap().to_str(), Some("text/html"));
+    /// assert_eq!(Value::new(" "), Err(ValueError::EmptyString));
+    /// ```
+    pub fn new<S: AsRef<str>>(s: S) -> Result<Self, ValueError> {
+        Self::new_with_limit(s, MAX_VALUE_LEN)
+    }
+    /// Like [new][Value::new], but refusing values longer than `limit`
+    /// bytes after trimming instead.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{Value, ValueError};
+    /// assert!(Value::new_with_limit(" gzip ", 4).is_ok());
+    /// assert_eq!(Value::new_with_limit("gzip", 3), Err(ValueError::TooLong { len: 4, limit: 3 }));
+    /// ```
+    pub fn new_with_limit<S: AsRef<str>>(s: S, limit: usize) -> Result<Self, ValueError> {
         let s = s.as_ref().trim();
-        if !s.is_ascii() {
-            Err(ValueError::NonAsciiChars)
-        } else if s.is_empty() {
+        if s.len() > limit {
+            Err(ValueError::TooLong { len: s.len(), limit })
+        } else if !s.is_ascii() {
+            Err(ValueError::non_ascii(s.as_bytes()))
+        } else {
+            Self::from_bytes(s.as_bytes(), limit)
+        }
+    }
+    /// Like [new][Value::new], but also accepts obs-text, the octets
+    /// 0x80 to 0xFF that older clients send, e.g. in Latin-1 filenames.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Value;
+    /// let value = Value::from_bytes_with_obs_text(b"attachment; filename=caf\xe9.txt").unwrap();
+    /// assert_eq!(value.as_bytes(), b"attachment; filename=caf\xe9.txt");
+    /// assert_eq!(value.to_str(), None);
+    /// assert_eq!(value.as_str_lossy(), "attachment; filename=caf\u{fffd}.txt");
+    /// ```
+    pub fn from_bytes_with_obs_text(bytes: &[u8]) -> Result<Self, ValueError> {
+        Self::from_bytes(bytes, MAX_VALUE_LEN)
+    }
+    fn from_bytes(bytes: &[u8], limit: usize) -> Result<Self, ValueError> {
+        let bytes = bytes.trim_ascii();
+        if bytes.len() > limit {
+            Err(ValueError::TooLong { len: bytes.len(), limit })
+        } else if bytes.is_empty() {
             Err(ValueError::EmptyString)
-        } else if s.contains(['\r', '\n', '\0']) {
-            Err(ValueError::IllegalChars)
+        } else if let Some(at) = bytes.iter().position(|b| matches!(b, b'\r' | b'\n' | b'\0')) {
+            Err(ValueError::illegal_char(bytes, at))
+        } else {
+            Ok(Self(bytes.to_vec()))
+        }
+    }
+    /// The value as sent on the wire.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+    /// The value as text, `None` if it contains obs-text.
+    pub fn to_str(&self) -> Option<&str> {
+        if self.0.is_ascii() {
+            std::str::from_utf8(&self.0).ok()
         } else {
-            Ok(Self (s.to_string()))
+            None
         }
     }
+    /// The value as text, with obs-text that is not valid UTF-8 replaced
+    /// by `U+FFFD`.
+    pub fn as_str_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
     /// Concatenates the current value with a new value with the same key
     /// According to the standard multiple headers like
     /// `head: foo` and `head: bar` are supposed to be parsed like
     /// a single `head: foo,bar`. 
-    pub(crate) fn append<S: AsRef<str>>(&mut self, s: S) -> Result<(), ValueError> {
+    ///
+    /// Fails without changing the value if the result would break the
+    /// [default limits][ValueLimits::default].
+    pub fn append<S: AsRef<str>>(&mut self, s: S) -> Result<(), ValueError> {
+        let cleaned = Self::new(s)?;
+        self.append_value(&cleaned)
+    }
+    /// Like [append][Value::append], but with other limits on the
+    /// combined value.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::{Value, ValueError, ValueLimits};
+    /// let limits = ValueLimits { elements: 2, ..ValueLimits::default() };
+    /// let mut value = Value::new("a").unwrap();
+    /// value.append_with_limits("b", limits).unwrap();
+    /// assert_eq!(value.append_with_limits("c", limits), Err(ValueError::TooManyElements { count: 3, limit: 2 }));
+    /// assert_eq!(value, "a,b");
+    /// ```
+    pub fn append_with_limits<S: AsRef<str>>(&mut self, s: S, limits: ValueLimits) -> Result<(), ValueError> {
+        let cleaned = Self::new(s)?;
+        self.append_value_with_limits(&cleaned, limits)
+    }
+    /// Like [append][Value::append], but only adds the members of `s`
+    /// that the list does not [contain][Value::contains_token] yet.
+    /// Returns whether anything was appended.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Value;
+    /// let mut vary = Value::new("Origin").unwrap();
+    /// assert_eq!(vary.append_unique("origin"), Ok(false));
+    /// assert_eq!(vary.append_unique("accept-encoding, origin"), Ok(true));
+    /// assert_eq!(vary, "Origin,accept-encoding");
+    /// ```
+    pub fn append_unique<S: AsRef<str>>(&mut self, s: S) -> Result<bool, ValueError> {
         let cleaned = Self::new(s)?;
-        self.0.push_str(&format!(",{}", cleaned.0));
+        let missing = cleaned.elements().filter(|e| !self.contains_token(e)).collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(false);
+        }
+        self.append_value(&Self::new(missing.join(", "))?)?;
+        Ok(true)
+    }
+    /// [append][Value::append] for an already validated value.
+    pub(crate) fn append_value(&mut self, other: &Value) -> Result<(), ValueError> {
+        self.append_value_with_limits(other, ValueLimits::default())
+    }
+    pub(crate) fn append_value_with_limits(&mut self, other: &Value, limits: ValueLimits) -> Result<(), ValueError> {
+        let len = self.0.len() + 1 + other.0.len();
+        if len > limits.len {
+            return Err(ValueError::TooLong { len, limit: limits.len });
+        }
+        let count = self.raw_elements().count() + other.raw_elements().count();
+        if count > limits.elements {
+            return Err(ValueError::TooManyElements { count, limit: limits.elements });
+        }
+        self.0.push(b',');
+        self.0.extend_from_slice(&other.0);
         Ok(())
     }
+    /// Parses the value with [FromStr]. Obs-text is seen as `U+FFFD`.
+    pub fn parse<T: FromStr>(&self) -> Result<T, T::Err> {
+        self.as_str_lossy().parse()
+    }
+    /// The value as a decimal number without sign, as in `content-length`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Value;
+    /// assert_eq!(Value::new(" 42 ").unwrap().as_u64(), Some(42));
+    /// assert_eq!(Value::new("+42").unwrap().as_u64(), None);
+    /// ```
+    pub fn as_u64(&self) -> Option<u64> {
+        if !self.0.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.to_str()?.parse().ok()
+    }
+    /// The value as an [HTTP-date][super::date::parse].
+    pub fn as_http_date(&self) -> Option<SystemTime> {
+        super::date::parse(self.to_str()?).ok()
+    }
+    /// The value if it is a single token, such as a coding or method name.
+    pub fn as_token(&self) -> Option<&str> {
+        self.to_str().filter(|s| is_token(s))
+    }
+    /// The members of a comma-separated list value, trimmed of surrounding
+    /// whitespace. Empty members are skipped, and commas inside quoted
+    /// strings do not separate members. So are members with obs-text
+    /// that is not valid UTF-8, but not the others next to them.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Value;
+    /// let value = Value::new(r#"text/html, , foo; bar="a,\"b", */*"#).unwrap();
+    /// assert_eq!(value.elements().collect::<Vec<_>>(), ["text/html", r#"foo; bar="a,\"b""#, "*/*"]);
+    /// ```
+    pub fn elements(&self) -> impl Iterator<Item = &str> {
+        self.raw_elements().filter_map(|element| std::str::from_utf8(element).ok())
+    }
+    /// The [elements][Value::elements] as bytes, obs-text included.
+    fn raw_elements(&self) -> impl Iterator<Item = &[u8]> {
+        let mut rest = &self.0[..];
+        std::iter::from_fn(move || loop {
+            if rest.is_empty() {
+                return None;
+            }
+            let end = top_level_comma(rest).unwrap_or(rest.len());
+            let element = trim_whitespace(&rest[..end]);
+            rest = rest.get(end + 1..).unwrap_or_default();
+            if !element.is_empty() {
+                return Some(element);
+            }
+        })
+    }
+    /// Whether the value equals `other` ignoring ascii case, as tokens
+    /// like `Close` or `WebSocket` compare.
+    pub fn eq_ignore_case(&self, other: &str) -> bool {
+        self.0.eq_ignore_ascii_case(other.as_bytes())
+    }
+    /// Whether one of the [elements][Value::elements] equals `token`
+    /// ignoring ascii case, e.g. `close` in `connection: Upgrade, Close`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::header::Value;
+    /// let connection = Value::new("Keep-Alive, Upgrade").unwrap();
+    /// assert!(connection.contains_token("upgrade"));
+    /// assert!(!connection.contains_token("close"));
+    /// ```
+    pub fn contains_token(&self, token: &str) -> bool {
+        self.raw_elements().any(|element| element.eq_ignore_ascii_case(token.as_bytes()))
+    }
 }
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+/// Bounds on a [Value] grown by appending, so that a client repeating a
+/// field cannot build an arbitrarily large one.
+pub struct ValueLimits {
+    /// Most bytes of the combined value
+    pub len: usize,
+    /// Most [elements][Value::elements] of the combined value
+    pub elements: usize,
+}
+/// [MAX_VALUE_LEN] bytes and [MAX_VALUE_ELEMENTS] members.
+impl Default for ValueLimits {
+    fn default() -> Self {
+        Self { len: MAX_VALUE_LEN, elements: MAX_VALUE_ELEMENTS }
+    }
+}
+
+/// `bytes` without leading and trailing spaces and tabs.
+fn trim_whitespace(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
+
+/// Position of the first comma of `s` outside a quoted string.
+fn top_level_comma(s: &[u8]) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, &b) in s.iter().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b',' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+/// Writes the value [lossily][Value::as_str_lossy].
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.as_str_lossy())
     }
 }
 impl<S: AsRef<str>> PartialEq<S> for Value {
     fn eq(&self, other: &S) -> bool {
-        self.0 == other.as_ref()
+        self.0 == other.as_ref().as_bytes()
     }
 }
 impl PartialEq<str> for Value {
     fn eq(&self, other: &str) -> bool {
-        self.0 == other
+        self.0 == other.as_bytes()
     }
 }
-impl Borrow<str> for Value {
-    fn borrow(&self) -> &str {
+impl AsRef<[u8]> for Value {
+    fn as_ref(&self) -> &[u8] {
         &self.0
     }
 }
 
 impl From<Value> for String {
     fn from(value: Value) -> String {
+        String::from_utf8(value.0).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+    }
+}
+impl From<Value> for Vec<u8> {
+    fn from(value: Value) -> Vec<u8> {
         value.0
     }
 }
 
+/// Values with obs-text are serialized as bytes.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_str() {
+            Some(s) => serializer.serialize_str(s),
+            None => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Value::new(s).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn elements(s: &str) -> Vec<String> {
+        Value::new(s).unwrap().elements().map(str::to_owned).collect()
+    }
+    #[test]
+    fn tokens_ignore_case() {
+        assert!(Value::new("Close").unwrap().eq_ignore_case("close"));
+        assert!(!Value::new("Close").unwrap().eq_ignore_case("clos"));
+        let value = Value::new("gzip, Chunked").unwrap();
+        assert!(value.contains_token("chunked"));
+        assert!(!value.contains_token("gzip, chunked"));
+        assert!(!Value::new("xchunked").unwrap().contains_token("chunked"));
+    }
+    #[test]
+    fn append_unique_tokens() {
+        let mut connection = Value::new("close").unwrap();
+        assert_eq!(connection.append_unique("close"), Ok(false));
+        assert_eq!(connection.append_unique("CLOSE"), Ok(false));
+        assert_eq!(connection, "close");
+        assert_eq!(connection.append_unique("upgrade"), Ok(true));
+        assert_eq!(connection, "close,upgrade");
+        assert_eq!(connection.append_unique(" "), Err(ValueError::EmptyString));
+    }
+    #[test]
+    fn elements_quoted_commas() {
+        assert_eq!(elements(r#"text/html, "a,b""#), ["text/html", r#""a,b""#]);
+        assert_eq!(elements(r#""\",", c"#), [r#""\",""#, "c"]);
+    }
+    #[test]
+    fn elements_skip_empty() {
+        assert_eq!(elements("a,,b"), ["a", "b"]);
+        assert_eq!(elements(", a ,\t,"), ["a"]);
+    }
+    #[test]
+    fn as_u64() {
+        assert_eq!(Value::new("  42 ").unwrap().as_u64(), Some(42));
+        assert_eq!(Value::new("-1").unwrap().as_u64(), None);
+        assert_eq!(Value::new("4 2").unwrap().as_u64(), None);
+        assert_eq!(Value::new("99999999999999999999").unwrap().as_u64(), None);
+        assert_eq!(Value::new("-1").unwrap().parse::<i64>(), Ok(-1));
+    }
+    #[test]
+    fn as_http_date() {
+        let date = Value::new("Sun, 06 Nov 1994 08:49:37 GMT").unwrap().as_http_date().unwrap();
+        assert_eq!(date, std::time::UNIX_EPOCH + std::time::Duration::from_secs(784111777));
+        assert_eq!(Value::new("yesterday").unwrap().as_http_date(), None);
+    }
+    #[test]
+    fn as_token() {
+        assert_eq!(Value::new("gzip").unwrap().as_token(), Some("gzip"));
+        assert_eq!(Value::new("gzip, br").unwrap().as_token(), None);
+    }
+    #[test]
+    fn obs_text() {
+        assert_eq!(Value::new("caf\u{e9}"), Err(ValueError::NonAsciiChars { input: "caf\u{e9}".to_owned(), at: 3 }));
+        let value = Value::from_bytes_with_obs_text(b" caf\xe9 ").unwrap();
+        assert_eq!(value.as_bytes(), b"caf\xe9");
+        assert_eq!(value.to_string(), "caf\u{fffd}");
+        assert_eq!(value.as_u64(), None);
+        assert_eq!(value.elements().count(), 0);
+        assert_eq!(
+            Value::from_bytes_with_obs_text(b"caf\xe9\r\nx"),
+            Err(ValueError::IllegalChars { input: "caf\u{fffd}\r\nx".to_owned(), found: '\r', at: 4 })
+        );
+        assert!(matches!(Value::from_bytes_with_obs_text(b"\xe9\0"), Err(ValueError::IllegalChars { found: '\0', at: 1, .. })));
+    }
+    #[test]
+    fn tokens_next_to_obs_text() {
+        let value = Value::from_bytes_with_obs_text(b"close, caf\xe9, \"a,\xe9\", keep-alive").unwrap();
+        assert_eq!(value.elements().collect::<Vec<_>>(), ["close", "keep-alive"]);
+        assert!(value.contains_token("Close"));
+        assert!(!value.contains_token("caf"));
+    }
+    #[test]
+    fn long_input_cut_in_errors() {
+        let long = format!("{}\0", "a".repeat(100));
+        let error = Value::new(&long).unwrap_err();
+        assert_eq!(error, ValueError::IllegalChars { input: format!("{}...", "a".repeat(64)), found: '\0', at: 100 });
+        assert!(error.to_string().starts_with("illegal character '\\0' at 100 in \"aaaa"));
+    }
+    #[test]
+    fn length_limit() {
+        assert!(Value::new("a".repeat(MAX_VALUE_LEN)).is_ok());
+        assert_eq!(
+            Value::new(format!(" {} ", "a".repeat(MAX_VALUE_LEN + 1))),
+            Err(ValueError::TooLong { len: MAX_VALUE_LEN + 1, limit: MAX_VALUE_LEN })
+        );
+        assert!(Value::from_bytes_with_obs_text(&[0xe9; MAX_VALUE_LEN + 1]).is_err());
+        assert!(Value::new_with_limit("abc", 3).is_ok());
+        assert!(Value::new_with_limit("abcd", 3).is_err());
+    }
+    #[test]
+    fn append_limit() {
+        let mut value = Value::new("a".repeat(MAX_VALUE_LEN - 2)).unwrap();
+        assert_eq!(value.append("bc"), Err(ValueError::TooLong { len: MAX_VALUE_LEN + 1, limit: MAX_VALUE_LEN }));
+        assert_eq!(value.as_bytes().len(), MAX_VALUE_LEN - 2);
+        value.append("b").unwrap();
+        assert_eq!(value.as_bytes().len(), MAX_VALUE_LEN);
+    }
+    #[test]
+    fn elements_single() {
+        assert_eq!(elements("close"), ["close"]);
+    }
+
     #[test]
     fn reject_null() {
         assert!(Value::new("S\0me text").is_err());
@@ -0,0 +1,189 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::Version;
+use super::{is_token, HeaderMap, Key, ValueError};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// One intermediary listed in a `via` header, e.g. `1.1 proxy.example
+/// (Squid)`.
+pub struct ViaEntry {
+    /// The protocol name if it was sent, which is left out for HTTP
+    pub protocol_name: Option<String>,
+    pub protocol_version: String,
+    /// The host, with an optional port, or a pseudonym of the intermediary
+    pub received_by: String,
+    /// The comment without its parentheses
+    pub comment: Option<String>,
+}
+impl ViaEntry {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (protocol, rest) = s.split_once([' ', '\t'])?;
+        let (protocol_name, protocol_version) = match protocol.split_once('/') {
+            Some((name, version)) => (Some(name), version),
+            None => (None, protocol),
+        };
+        let rest = rest.trim_start();
+        let (received_by, comment) = match rest.find('(') {
+            Some(open) => (rest[..open].trim_end(), Some(rest[open..].trim_end())),
+            None => (rest, None),
+        };
+        let tokens = [protocol_name, Some(protocol_version)].into_iter().flatten();
+        if !tokens.chain([received_by]).all(is_received_by) {
+            return None;
+        }
+        let comment = match comment {
+            Some(comment) => Some(comment.strip_prefix('(')?.strip_suffix(')')?.to_owned()),
+            None => None,
+        };
+        Some(Self {
+            protocol_name: protocol_name.map(str::to_owned),
+            protocol_version: protocol_version.to_owned(),
+            received_by: received_by.to_owned(),
+            comment,
+        })
+    }
+}
+impl Display for ViaEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(name) = &self.protocol_name {
+            write!(f, "{name}/")?;
+        }
+        write!(f, "{} {}", self.protocol_version, self.received_by)?;
+        match &self.comment {
+            Some(comment) => write!(f, " ({comment})"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+/// The intermediaries a message passed through, as listed in `via`, the
+/// first one closest to the sender.
+///
+/// Parsing is lenient: malformed entries are skipped.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::header::Via;
+/// let via = Via::parse("1.0 fred, HTTP/1.1 p.example.net:8080 (Apache/2.4)");
+/// assert_eq!(via.entries()[1].received_by, "p.example.net:8080");
+/// assert_eq!(via.entries()[1].comment.as_deref(), Some("Apache/2.4"));
+/// assert!(via.contains_pseudonym("Fred"));
+/// ```
+pub struct Via {
+    entries: Vec<ViaEntry>,
+}
+impl Via {
+    pub fn parse(s: &str) -> Self {
+        Self { entries: split_list(s).filter_map(ViaEntry::parse).collect() }
+    }
+    pub fn entries(&self) -> &[ViaEntry] {
+        &self.entries
+    }
+    /// Whether an entry was received by `name`, ignoring case. A proxy
+    /// finding its own pseudonym has seen the message before and can
+    /// answer with [508][crate::Response::LoopDetected].
+    pub fn contains_pseudonym(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.received_by.eq_ignore_ascii_case(name))
+    }
+}
+impl Display for Via {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds `version pseudonym` to the `via` header of a message being
+/// forwarded, unless that very entry is listed already. Fails if `pseudonym` is
+/// neither a token nor `host:port`.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{header::{append_via, HeaderMap, Key}, Version};
+/// let mut headers = HeaderMap::new();
+/// headers.append(Key::VIA, "1.0 fred").unwrap();
+/// append_via(&mut headers, Version::HTTP_11, "gateway").unwrap();
+/// assert_eq!(headers.get("via").unwrap(), "1.0 fred,1.1 gateway");
+/// ```
+pub fn append_via(headers: &mut HeaderMap, version: Version, pseudonym: &str) -> Result<bool, ValueError> {
+    if !is_received_by(pseudonym) {
+        return Err(ValueError::not_token(pseudonym));
+    }
+    headers.append_unique(Key::VIA, format!("{version} {pseudonym}"))
+}
+
+/// A token, or a `host:port` whose host is one.
+fn is_received_by(s: &str) -> bool {
+    match s.rsplit_once(':') {
+        Some((host, port)) => is_token(host) && !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()),
+        None => is_token(s),
+    }
+}
+
+/// Splits at commas outside of comments, which may nest and escape
+/// characters with a backslash.
+fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    let mut elements = Vec::new();
+    let (mut depth, mut escaped, mut start) = (0usize, false, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if depth > 0 => escaped = true,
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                elements.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(&s[start..]);
+    elements.into_iter().filter(|element| !element.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_hops_with_comment() {
+        let via = Via::parse("HTTP/1.0 fred, 1.1 p.example.net (Apache, with (nested\\) comma)) , garbage");
+        assert_eq!(via.entries(), [
+            ViaEntry {
+                protocol_name: Some("HTTP".to_owned()),
+                protocol_version: "1.0".to_owned(),
+                received_by: "fred".to_owned(),
+                comment: None,
+            },
+            ViaEntry {
+                protocol_name: None,
+                protocol_version: "1.1".to_owned(),
+                received_by: "p.example.net".to_owned(),
+                comment: Some("Apache, with (nested\\) comma)".to_owned()),
+            },
+        ]);
+        assert_eq!(via.to_string(), "HTTP/1.0 fred, 1.1 p.example.net (Apache, with (nested\\) comma))");
+    }
+    #[test]
+    fn loop_detection_ignores_case() {
+        let mut headers = HeaderMap::new();
+        append_via(&mut headers, Version::HTTP_11, "Edge-1").unwrap();
+        let via = Via::parse(&headers.get("via").unwrap().to_string());
+        assert!(via.contains_pseudonym("edge-1"));
+        assert!(via.contains_pseudonym("EDGE-1"));
+        assert!(!via.contains_pseudonym("edge-2"));
+        // The same hop is not listed twice
+        assert_eq!(append_via(&mut headers, Version::HTTP_11, "edge-1"), Ok(false));
+        assert_eq!(append_via(&mut headers, Version::HTTP_11, "proxy:8080"), Ok(true));
+        assert_eq!(headers.get("via").unwrap(), "1.1 Edge-1,1.1 proxy:8080");
+        assert!(append_via(&mut headers, Version::HTTP_11, "two words").is_err());
+    }
+}
@@ -1,22 +1,302 @@
-use std::fmt::{
-    Display, Formatter, Result as FmtResult
+//! HTTP/1.x requests and responses.
+//!
+//! Responses are built from a [Response] status code through a
+//! [ResponseBuilder][response::ResponseBuilder]. Applications with their
+//! own status enums implement [ResponseCode] for them, usually via
+//! [impl_response_code], which also makes them convertible into a builder.
+//!
+//! # Examples
+//! ```
+//! use heggemann_http::{impl_response_code, response::ResponseBuilder, ResponseCode, Version};
+//!
+//! enum AppError {
+//!     NoSuchUser,
+//!     Overloaded,
+//! }
+//! impl_response_code! {
+//!     AppError {
+//!         NoSuchUser => NotFound,
+//!         Overloaded => ServiceUnavailable,
+//!     }
+//! }
+//! assert_eq!(AppError::NoSuchUser.first_line(Version(1, 1)), "HTTP/1.1 404 Not Found");
+//! let response = ResponseBuilder::from(AppError::Overloaded)
+//!     .header("retry-after", "30").unwrap()
+//!     .body("try again later");
+//! assert!(response.to_string().starts_with("HTTP/1.0 503 Service Unavailable\r\n"));
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
 };
 
+mod base64;
+pub mod client;
+pub mod connection;
+pub mod driver;
+pub mod extensions;
+mod framing;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+pub mod handler;
 pub mod header;
+mod line;
+pub mod metrics;
 pub mod request;
 pub mod response;
+pub mod router;
+pub mod server;
+mod sha1;
 
 pub use self::{
+    extensions::Extensions,
     request::{Request, RequestMethod},
     // Traits have to be reexported due to compatibility
     response::{Response, Byteable, ResponseCode},
 };
+/// The TLS library behind the `tls` feature, for building configurations.
+#[cfg(feature = "tls")]
+pub use rustls;
 
-#[derive(PartialEq, Debug)]
+/// An HTTP version as major and minor number, ordered by major version
+/// first.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::Version;
+/// assert!(Version(1, 10) < Version::HTTP_2);
+/// assert_eq!(Version::HTTP_11.to_string(), "1.1");
+/// assert_eq!(Version::HTTP_11.http_string(), "HTTP/1.1");
+/// assert_eq!("1.1".parse(), Ok(Version::HTTP_11));
+/// ```
+///
+/// With the `serde` feature, versions are (de)serialized in the `"1.1"`
+/// form of [Display] and [FromStr].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
 pub struct Version(pub u64, pub u64);
 
+impl Version {
+    pub const HTTP_09: Version = Version(0, 9);
+    pub const HTTP_10: Version = Version(1, 0);
+    pub const HTTP_11: Version = Version(1, 1);
+    pub const HTTP_2: Version = Version(2, 0);
+    pub const HTTP_3: Version = Version(3, 0);
+
+    /// Whether a server implementing up to `max` can answer this version,
+    /// which is the case if the major versions match, see
+    /// [negotiate][Version::negotiate].
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Version;
+    /// assert!(Version::HTTP_10.is_supported(&Version::HTTP_11));
+    /// assert!(Version(1, 5).is_supported(&Version::HTTP_11));
+    /// assert!(!Version::HTTP_2.is_supported(&Version::HTTP_11));
+    /// ```
+    pub fn is_supported(&self, max: &Version) -> bool {
+        self.0 == max.0
+    }
+    /// Whether connections stay open after a response unless either side
+    /// sends `connection: close`, which is the case from HTTP/1.1 on.
+    pub fn supports_persistent_by_default(&self) -> bool {
+        *self >= Self::HTTP_11
+    }
+    /// Whether the chunked transfer coding exists, which is HTTP/1.1 only;
+    /// HTTP/2 and later frame the body themselves.
+    pub fn supports_chunked(&self) -> bool {
+        self.0 == 1 && self.1 >= 1
+    }
+    /// Whether 1xx responses may precede the final response, which is the
+    /// case from HTTP/1.1 on.
+    pub fn supports_interim_responses(&self) -> bool {
+        *self >= Self::HTTP_11
+    }
+    /// Whether a request has to carry a `host` header (RFC 9112, section
+    /// 3.2). HTTP/2 and later send the `:authority` pseudo-header instead.
+    pub fn requires_host_header(&self) -> bool {
+        self.0 == 1 && self.1 >= 1
+    }
+    /// The version as written in request and status lines, e.g. `HTTP/1.1`.
+    pub fn http_string(&self) -> String {
+        self.http().to_string()
+    }
+    /// Writes like [http_string][Version::http_string] without allocating.
+    pub(crate) fn http(self) -> impl Display {
+        struct Http(Version);
+        impl Display for Http {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                write!(f, "HTTP/{}", self.0)
+            }
+        }
+        Http(self)
+    }
+    /// The version to answer a `request_version` request with on a server
+    /// implementing up to `max_supported`.
+    ///
+    /// The major versions have to match; the minor version is the lower of
+    /// the two, so a 1.1 server answers 1.0 clients in 1.0.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Response, Version, response::ResponseBuilder};
+    /// assert_eq!(Version::negotiate(&Version(1, 0), &Version(1, 1)), Ok(Version(1, 0)));
+    /// let refused = Version::negotiate(&Version(2, 0), &Version(1, 1)).unwrap_err();
+    /// assert_eq!(ResponseBuilder::from(refused).get_status(), &Response::HttpVersionNotSupported);
+    /// ```
+    pub fn negotiate(request_version: &Version, max_supported: &Version) -> Result<Version, UnsupportedVersion> {
+        if request_version.0 != max_supported.0 {
+            return Err(UnsupportedVersion(*request_version));
+        }
+        Ok(*request_version.min(max_supported))
+    }
+}
+
 impl Display for Version {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}.{}", self.0, self.1)
     }
 }
+
+impl FromStr for Version {
+    type Err = VersionError;
+    /// Parses `major.minor`, both single digits as in the protocol.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || VersionError::Malformed(s.to_owned());
+        let (major, minor) = s.split_once('.').ok_or_else(malformed)?;
+        let number = |n: &str| if n.bytes().all(|b| b.is_ascii_digit()) {
+            n.parse::<u64>().map_err(|_| malformed())
+        } else {
+            Err(malformed())
+        };
+        Version::try_from((number(major)?, number(minor)?))
+    }
+}
+
+impl TryFrom<(u64, u64)> for Version {
+    type Error = VersionError;
+    /// Refuses numbers above 9, which the protocol cannot express.
+    fn try_from((major, minor): (u64, u64)) -> Result<Self, Self::Error> {
+        if major > 9 || minor > 9 {
+            return Err(VersionError::OutOfRange(major, minor));
+        }
+        Ok(Version(major, minor))
+    }
+}
+
+impl From<Version> for (u64, u64) {
+    fn from(Version(major, minor): Version) -> (u64, u64) {
+        (major, minor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Version {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// A version that could not be read from a string or number pair.
+pub enum VersionError {
+    /// Not of the form `major.minor`
+    Malformed(String),
+    /// A major or minor version above 9
+    OutOfRange(u64, u64),
+}
+impl Error for VersionError {}
+impl Display for VersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Malformed(s) => write!(f, "version {s:?} is not of the form major.minor"),
+            Self::OutOfRange(major, minor) => write!(f, "version {major}.{minor} is out of range"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+/// A request version with a major version the server does not implement,
+/// answered with [Response::HttpVersionNotSupported].
+pub struct UnsupportedVersion(pub Version);
+impl Error for UnsupportedVersion {}
+impl Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} is not supported", self.0.http_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: Version = Version(1, 1);
+
+    #[test]
+    fn negotiate_one_x() {
+        assert_eq!(Version::negotiate(&Version(1, 0), &MAX), Ok(Version(1, 0)));
+        assert_eq!(Version::negotiate(&Version(1, 1), &MAX), Ok(Version(1, 1)));
+        assert_eq!(Version::negotiate(&Version(1, 5), &MAX), Ok(Version(1, 1)));
+    }
+    #[test]
+    fn ordering_across_majors() {
+        let mut versions = [Version::HTTP_3, Version(1, 10), Version::HTTP_09, Version::HTTP_2, Version::HTTP_11, Version::HTTP_10];
+        versions.sort();
+        assert_eq!(versions, [Version::HTTP_09, Version::HTTP_10, Version::HTTP_11, Version(1, 10), Version::HTTP_2, Version::HTTP_3]);
+        assert!(Version(2, 0) > Version(1, 99));
+    }
+    #[test]
+    fn display_and_http_string() {
+        assert_eq!(Version::HTTP_09.to_string(), "0.9");
+        assert_eq!(Version::HTTP_2.http_string(), "HTTP/2.0");
+        assert_eq!(UnsupportedVersion(Version::HTTP_3).to_string(), "HTTP/3.0 is not supported");
+    }
+    #[test]
+    fn capabilities() {
+        let queries = |v: Version| [
+            v.supports_persistent_by_default(),
+            v.supports_chunked(),
+            v.supports_interim_responses(),
+            v.requires_host_header(),
+        ];
+        assert_eq!(queries(Version::HTTP_09), [false, false, false, false]);
+        assert_eq!(queries(Version::HTTP_10), [false, false, false, false]);
+        assert_eq!(queries(Version::HTTP_11), [true, true, true, true]);
+        assert_eq!(queries(Version::HTTP_2), [true, false, true, false]);
+    }
+    #[test]
+    fn parse_and_convert() {
+        assert_eq!("1.0".parse(), Ok(Version::HTTP_10));
+        assert_eq!(<(u64, u64)>::from(Version::HTTP_11), (1, 1));
+        assert_eq!(Version::try_from((2, 0)), Ok(Version::HTTP_2));
+        assert_eq!(Version::try_from((10, 0)), Err(VersionError::OutOfRange(10, 0)));
+        for s in ["1", "1.1.1", "", "1.", ".1", "+1.1", " 1.1", "HTTP/1.1"] {
+            assert_eq!(s.parse::<Version>(), Err(VersionError::Malformed(s.to_owned())));
+        }
+        assert_eq!("12.0".parse::<Version>(), Err(VersionError::OutOfRange(12, 0)));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_as_string() {
+        assert_eq!(serde_json::to_string(&Version::HTTP_11).unwrap(), r#""1.1""#);
+        assert_eq!(serde_json::from_str::<Version>(r#""1.0""#).unwrap(), Version::HTTP_10);
+        for json in [r#""1""#, r#""1.1.1""#, "[1,1]"] {
+            assert!(serde_json::from_str::<Version>(json).is_err());
+        }
+        let error = serde_json::from_str::<Version>(r#""1.1.1""#).unwrap_err();
+        assert!(error.to_string().contains("not of the form major.minor"));
+    }
+    #[test]
+    fn negotiate_other_major() {
+        assert_eq!(Version::negotiate(&Version(2, 0), &MAX), Err(UnsupportedVersion(Version(2, 0))));
+        assert_eq!(Version::negotiate(&Version(0, 9), &MAX), Err(UnsupportedVersion(Version(0, 9))));
+    }
+}
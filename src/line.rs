@@ -0,0 +1,122 @@
+//! The request and status lines starting every message, formatted and
+//! parsed in one place so both directions agree on the grammar.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::{
+    request::RequestParseError,
+    response::StatusLineError,
+    RequestMethod,
+    Version,
+};
+
+/// Writes `HTTP/1.1 404 Not Found` without allocating. An empty phrase
+/// keeps the space before it.
+pub(crate) fn format_status_line(version: Version, code: u16, phrase: &str) -> impl Display + '_ {
+    struct Line<'a>(Version, u16, &'a str);
+    impl Display for Line<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+            write!(f, "{} {} {}", self.0.http(), self.1, self.2)
+        }
+    }
+    Line(version, code, phrase)
+}
+
+/// Writes `GET /index.html HTTP/1.1` without allocating.
+pub(crate) fn format_request_line(method: RequestMethod, target: &str, version: Version) -> impl Display + '_ {
+    struct Line<'a>(RequestMethod, &'a str, Version);
+    impl Display for Line<'_> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+            write!(f, "{} {} {}", self.0.as_str(), self.1, self.2.http())
+        }
+    }
+    Line(method, target, version)
+}
+
+/// Reads the version word, e.g. `HTTP/1.1`. HTTP/2 and later may omit
+/// the minor version, as in `HTTP/2`.
+pub(crate) fn parse_http_version(word: &str) -> Option<Version> {
+    let version = word.strip_prefix("HTTP/")?;
+    match (version.parse(), version.as_bytes()) {
+        (Ok(version), _) => Some(version),
+        (Err(_), &[major @ b'2'..=b'9']) => Some(Version(u64::from(major - b'0'), 0)),
+        _ => None,
+    }
+}
+
+/// Splits a status line, with or without its line ending, into version,
+/// code and reason phrase. The phrase may be empty, in which case the
+/// space before it is optional.
+pub(crate) fn parse_status_line(s: &str) -> Result<(Version, u16, &str), StatusLineError> {
+    let s = s.strip_suffix("\r\n").or_else(|| s.strip_suffix('\n')).unwrap_or(s);
+    let (http_word, rest) = s.split_once(' ').unwrap_or((s, ""));
+    if !http_word.starts_with("HTTP/") {
+        return Err(StatusLineError::NoHttpWord);
+    }
+    let version = parse_http_version(http_word).ok_or(StatusLineError::InvalidVersion)?;
+    let (code, phrase) = rest.split_once(' ').unwrap_or((rest, ""));
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(StatusLineError::InvalidCode);
+    }
+    if phrase.bytes().any(|b| b.is_ascii_control() && b != b'\t') {
+        return Err(StatusLineError::InvalidPhrase);
+    }
+    Ok((version, code.parse().map_err(|_| StatusLineError::InvalidCode)?, phrase))
+}
+
+/// Splits a request line without its line ending into the method word,
+/// the request target and the version. The words may be separated by any
+/// whitespace.
+pub(crate) fn parse_request_line(s: &str) -> Result<(&str, &str, Version), RequestParseError> {
+    let mut words = s.split_whitespace();
+    let method = words.next().ok_or(RequestParseError::NoMethod)?;
+    let target = words.next().ok_or(RequestParseError::NoPath)?;
+    let http_word = words.next().ok_or(RequestParseError::NoHttpWord)?;
+    let version = parse_http_version(http_word).ok_or(RequestParseError::InvalidVersion)?;
+    Ok((method, target, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [RequestMethod; 8] = {
+        use RequestMethod::*;
+        [Get, Head, Post, Put, Delete, Connect, Options, Trace]
+    };
+
+    fn versions() -> impl Iterator<Item = Version> {
+        (0..=9).flat_map(|major| (0..=9).map(move |minor| Version(major, minor)))
+    }
+
+    #[test]
+    fn request_lines_round_trip() {
+        for version in versions() {
+            for method in METHODS {
+                for target in ["/", "*", "/a/b?c=d", "example.com:443", "http://example.com/"] {
+                    let line = format_request_line(method, target, version).to_string();
+                    assert_eq!(parse_request_line(&line), Ok((method.as_str(), target, version)));
+                }
+            }
+        }
+    }
+    #[test]
+    fn status_lines_round_trip() {
+        for version in versions() {
+            for code in [100, 200, 299, 404, 505, 999] {
+                for phrase in ["", "OK", "HTTP Version Not Supported", "tab\tinside"] {
+                    let line = format_status_line(version, code, phrase).to_string();
+                    assert_eq!(parse_status_line(&line), Ok((version, code, phrase)));
+                }
+            }
+        }
+    }
+    #[test]
+    fn versions_without_minor() {
+        assert_eq!(parse_http_version("HTTP/2"), Some(Version::HTTP_2));
+        assert_eq!(parse_http_version("HTTP/3"), Some(Version::HTTP_3));
+        assert_eq!(parse_http_version("HTTP/1"), None);
+        assert_eq!(parse_http_version("HTTP/1.10"), None);
+        assert_eq!(parse_http_version("HTTPS/1.1"), None);
+    }
+}
@@ -0,0 +1,178 @@
+//! Hooks observing the requests a [Connection][crate::connection::Connection]
+//! reads and answers, for exporting counts and timings.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::{net::TcpListener, sync::Arc};
+//! # use heggemann_http::{metrics::AtomicMetrics, server::Server, Response};
+//! let metrics = Arc::new(AtomicMetrics::new());
+//! let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+//! let server = Server::new(listener, |_| Response::Ok.body("hi")).metrics(metrics.clone());
+//! std::thread::spawn(move || server.run());
+//! // Later, from an exporter
+//! println!("{} requests, {} bytes sent", metrics.requests(), metrics.bytes_out());
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use crate::{connection::ReadTimeout, request::RequestParseError, RequestMethod};
+
+/// Receives events of the requests on a connection. Every method does
+/// nothing by default.
+pub trait Metrics: Send + Sync {
+    /// The head of a request arrived and is valid.
+    fn on_request_start(&self) {}
+    /// The response to a request was written. The byte counts are those
+    /// of the request and the response as sent over the stream, heads
+    /// included, and `elapsed` runs from the arrival of the head.
+    fn on_request_end(&self, method: &RequestMethod, status: u16, bytes_in: u64, bytes_out: u64, elapsed: Duration) {
+        let _ = (method, status, bytes_in, bytes_out, elapsed);
+    }
+    /// A request could not be read, which closes the connection.
+    fn on_parse_error(&self, err: &RequestParseError) {
+        let _ = err;
+    }
+}
+
+#[derive(Debug, Default)]
+/// [Metrics] summed up in atomic counters.
+pub struct AtomicMetrics {
+    started: AtomicU64,
+    requests: AtomicU64,
+    /// Responses by status class, `1xx` first
+    classes: [AtomicU64; 5],
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    nanos: AtomicU64,
+    parse_errors: AtomicU64,
+}
+impl AtomicMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Requests whose head arrived
+    pub fn requests_started(&self) -> u64 {
+        self.started.load(Ordering::Relaxed)
+    }
+    /// Requests answered
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+    /// Responses with a status of `class`xx, such as 2 for `2xx`.
+    pub fn status_class(&self, class: u16) -> u64 {
+        match class {
+            1..=5 => self.classes[class as usize - 1].load(Ordering::Relaxed),
+            _ => 0,
+        }
+    }
+    pub fn bytes_in(&self) -> u64 {
+        self.bytes_in.load(Ordering::Relaxed)
+    }
+    pub fn bytes_out(&self) -> u64 {
+        self.bytes_out.load(Ordering::Relaxed)
+    }
+    /// The time of all requests answered, added up
+    pub fn total_duration(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+    pub fn parse_errors(&self) -> u64 {
+        self.parse_errors.load(Ordering::Relaxed)
+    }
+}
+impl Metrics for AtomicMetrics {
+    fn on_request_start(&self) {
+        self.started.fetch_add(1, Ordering::Relaxed);
+    }
+    fn on_request_end(&self, _: &RequestMethod, status: u16, bytes_in: u64, bytes_out: u64, elapsed: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(class) = self.classes.get((status / 100).wrapping_sub(1) as usize) {
+            class.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes_in.fetch_add(bytes_in, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes_out, Ordering::Relaxed);
+        self.nanos.fetch_add(elapsed.as_nanos().try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+    fn on_parse_error(&self, _: &RequestParseError) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+/// A stream counting the bytes read from and written to it.
+pub struct Counted<S> {
+    inner: S,
+    read: u64,
+    written: u64,
+}
+impl<S> Counted<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, read: 0, written: 0 }
+    }
+    pub fn bytes_read(&self) -> u64 {
+        self.read
+    }
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+impl<S: Read> Read for Counted<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.read += len as u64;
+        Ok(len)
+    }
+}
+impl<S: Write> Write for Counted<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.written += len as u64;
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl<S: ReadTimeout> ReadTimeout for Counted<S> {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_read_timeout(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_both_directions() {
+        let mut stream = Counted::new(io::Cursor::new(b"hello".to_vec()));
+        let mut buf = [0; 3];
+        stream.read_exact(&mut buf).unwrap();
+        stream.write_all(b"ab").unwrap();
+        assert_eq!((stream.bytes_read(), stream.bytes_written()), (3, 2));
+        assert_eq!(stream.into_inner().into_inner(), b"helab");
+    }
+    #[test]
+    fn status_classes() {
+        let metrics = AtomicMetrics::new();
+        for status in [200, 204, 404, 503, 99, 600] {
+            metrics.on_request_end(&RequestMethod::Get, status, 1, 2, Duration::from_millis(1));
+        }
+        assert_eq!(metrics.requests(), 6);
+        assert_eq!([1, 2, 3, 4, 5].map(|class| metrics.status_class(class)), [0, 2, 0, 1, 1]);
+        assert_eq!((metrics.bytes_in(), metrics.bytes_out()), (6, 12));
+        assert_eq!(metrics.total_duration(), Duration::from_millis(6));
+    }
+}
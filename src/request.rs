@@ -1,16 +1,32 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
     error::Error,
     fmt::{Display, Formatter, Result as FMTResult},
+    io::{self, BufRead, Write},
     str::FromStr,
+    time::SystemTime,
 };
 
 use crate::{
-    header::{key::Key, value::Value, HeaderError},
+    extensions::Extensions,
+    header::{Authorization, AuthorizationError, CacheControl, CacheControlError, EntityTag, HeaderError, HeaderMap, Key, KeyError, MediaRange, MediaTypeError, Protocol, QValue, Te, ToHeader, ValueError, Via},
+    line,
+    response::{Incomplete, ResponseBuilder},
+    Response,
     Version,
 };
 
+mod cache_key;
+mod parser;
+pub mod websocket;
+
+pub use cache_key::CacheKey;
+pub use parser::RequestParser;
+
+/// Longest request line and header section [Request::from_reader] accepts.
+pub const MAX_HEAD_LEN: usize = 64 * 1024;
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The overall HTTP request struct.
 /// 
 /// # Examples
@@ -39,15 +55,187 @@ use crate::{
 /// assert_eq!(request.headers.get("authorization").unwrap(), "I have none");
 /// ```
 /// 
-/// Header keys have to be compared in lowercase. (Work in progress)
+/// Header lookups ignore the case of the key, see [HeaderMap].
 pub struct Request {
     pub method: RequestMethod,
     pub path: String,
-    pub headers: HashMap<Key, Value>,
+    pub headers: HeaderMap,
     pub version: Version,
+    /// Data attached by the application, see [Extensions].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, PartialEq)]
+impl Request {
+    /// Whether the client wants the connection to stay open after the response.
+    ///
+    /// An explicit `connection: close` or `connection: keep-alive` token wins,
+    /// otherwise the protocol default applies: HTTP/1.1 and later keep the
+    /// connection alive, HTTP/1.0 closes it.
+    pub fn keep_alive(&self) -> bool {
+        let has = |token| self.headers.get("connection").is_some_and(|v| v.contains_token(token));
+        if has("close") {
+            false
+        } else if has("keep-alive") {
+            true
+        } else {
+            self.version.supports_persistent_by_default()
+        }
+    }
+    /// Refuses a request without `host` in a version that
+    /// [requires][Version::requires_host_header] one, which a server has to
+    /// answer with [400][crate::Response::BadRequest].
+    pub fn check_host(&self) -> Result<(), RequestParseError> {
+        if self.version.requires_host_header() && !self.headers.contains_key("host") {
+            Err(RequestParseError::MissingHost)
+        } else {
+            Ok(())
+        }
+    }
+    /// Refuses a request whose version a server implementing up to `max`
+    /// cannot [negotiate][Version::negotiate], such as one parsed
+    /// [leniently][Request::parse_lenient] as HTTP/0.9.
+    pub fn check_version(&self, max: &Version) -> Result<(), RequestParseError> {
+        if self.version.is_supported(max) {
+            Ok(())
+        } else {
+            Err(RequestParseError::UnsupportedVersion(self.version))
+        }
+    }
+    /// Whether the client sent `expect: 100-continue` and waits for a
+    /// [100][crate::Response::Continue] before sending the body.
+    pub fn expects_continue(&self) -> bool {
+        self.headers.get("expect").is_some_and(|v| v.contains_token("100-continue"))
+    }
+    /// Whether the client asks to switch to `protocol`, such as `websocket`,
+    /// by listing it in `upgrade` and announcing `connection: upgrade`.
+    /// A version of the protocol, as in `h2c/2`, is not compared.
+    pub fn wants_upgrade(&self, protocol: &str) -> bool {
+        self.upgrade_tokens().iter().any(|p| p.is(protocol))
+    }
+    /// The protocols listed in `upgrade`, in the client's order of
+    /// preference. Empty unless `connection` lists `upgrade`, without
+    /// which an intermediary may have forwarded the header by mistake.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Request;
+    /// let request: Request = "GET / HTTP/1.1\r\nUpgrade: h2c, websocket/13\r\nConnection: Upgrade\r\n\r\n".parse().unwrap();
+    /// let names: Vec<_> = request.upgrade_tokens().iter().map(ToString::to_string).collect();
+    /// assert_eq!(names, ["h2c", "websocket/13"]);
+    /// ```
+    pub fn upgrade_tokens(&self) -> Vec<Protocol> {
+        match (self.headers.get("connection"), self.headers.get("upgrade")) {
+            (Some(connection), Some(upgrade)) if connection.contains_token("upgrade") => {
+                Protocol::parse_list(&upgrade.as_str_lossy())
+            }
+            _ => vec![],
+        }
+    }
+    /// The parsed `te` header, if the client sent one.
+    pub fn te(&self) -> Option<Te> {
+        self.headers.get("te").map(|v| Te::parse(&v.as_str_lossy()))
+    }
+    /// Whether the client accepts a trailer section after a chunked body,
+    /// announced by `te: trailers`.
+    pub fn accepts_trailers(&self) -> bool {
+        self.te().is_some_and(|te| te.trailers())
+    }
+    /// The intermediaries listed in `via`, if any; see
+    /// [contains_pseudonym][Via::contains_pseudonym] for loop detection.
+    pub fn via(&self) -> Option<Via> {
+        self.headers.get("via").map(|v| Via::parse(&v.as_str_lossy()))
+    }
+    /// The time in `if-modified-since`, if present and a valid HTTP-date.
+    ///
+    /// A [GET][RequestMethod::Get] or [HEAD][RequestMethod::Head] for a
+    /// resource not modified since should be answered with
+    /// [304][crate::Response::NotModified]. The header is to be ignored
+    /// when the request also has `if-none-match`.
+    pub fn if_modified_since(&self) -> Option<SystemTime> {
+        self.headers.get("if-modified-since")?.as_http_date()
+    }
+    /// The parsed `authorization` header, if the client sent one.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Request, header::Authorization};
+    /// let request: Request = "GET / HTTP/1.1\r\nAuthorization: Bearer abc\r\n\r\n".parse().unwrap();
+    /// assert_eq!(request.authorization(), Some(Ok(Authorization::Bearer("abc".to_owned()))));
+    /// ```
+    pub fn authorization(&self) -> Option<Result<Authorization, AuthorizationError>> {
+        self.headers.get("authorization").map(|v| v.as_str_lossy().parse())
+    }
+    /// The media ranges of the `accept` header with their weights, if the
+    /// client sent one; see [negotiate][crate::header::media_type::negotiate].
+    pub fn accept(&self) -> Option<Result<Vec<(MediaRange, QValue)>, MediaTypeError>> {
+        self.headers.get("accept").map(MediaRange::parse_accept)
+    }
+    /// The parsed `cache-control` header, if the client sent one.
+    pub fn cache_control(&self) -> Option<Result<CacheControl, CacheControlError>> {
+        self.headers.get("cache-control").map(CacheControl::parse)
+    }
+    /// Sets a structured header, replacing any previous value of that field.
+    ///
+    /// Fails if the serialized value is longer than
+    /// [MAX_VALUE_LEN][crate::header::MAX_VALUE_LEN].
+    pub fn set_typed_header<H: ToHeader>(&mut self, h: H) -> Result<(), HeaderError> {
+        let k = h.name();
+        let v = h.value().map_err(|e| HeaderError::from(e).for_key(&k))?;
+        self.headers.insert(k, v);
+        Ok(())
+    }
+    /// Removes the hop-by-hop fields before the request is forwarded, see
+    /// [HeaderMap::strip_hop_by_hop].
+    pub fn strip_hop_by_hop(&mut self) -> Vec<Key> {
+        self.headers.strip_hop_by_hop()
+    }
+    /// Evaluates `if-none-match` against the current entity tag of the resource.
+    ///
+    /// Returns `true` when the header is `*` or lists a tag weakly matching
+    /// `current`, in which case a [GET][RequestMethod::Get] or
+    /// [HEAD][RequestMethod::Head] should be answered with
+    /// [304][crate::Response::NotModified]. Unparseable headers never match.
+    pub fn if_none_match(&self, current: &EntityTag) -> bool {
+        let Some(header) = self.headers.get("if-none-match") else {
+            return false
+        };
+        let header = header.to_string();
+        header.trim() == "*" || EntityTag::parse_list(&header)
+            .is_ok_and(|tags| tags.iter().any(|t| t.weak_eq(current)))
+    }
+    /// The request line and header section as sent on the wire, like
+    /// [Display] but keeping header values byte for byte.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_head(&mut bytes, None, None);
+        bytes
+    }
+    /// Appends [to_bytes][Self::to_bytes] to `out`, with a `host` field
+    /// before and a `content-length` field after those of the request if
+    /// given, as a client adds them.
+    pub(crate) fn write_head(&self, out: &mut Vec<u8>, host: Option<&str>, content_length: Option<usize>) {
+        // Writing into a `Vec` cannot fail
+        write!(out, "{}\r\n", line::format_request_line(self.method, &self.path, self.version)).unwrap();
+        if let Some(host) = host {
+            write!(out, "host: {host}\r\n").unwrap();
+        }
+        for (k, v) in &self.headers {
+            out.extend_from_slice(k.as_str().as_bytes());
+            out.extend_from_slice(b": ");
+            out.extend_from_slice(v.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+        if let Some(len) = content_length {
+            write!(out, "content-length: {len}\r\n").unwrap();
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
 /// Enumeration of the standardized Request methods.
 /// 
 /// Safety and Idempotency defined by the HTTP/1.1 standard.
@@ -63,6 +251,19 @@ pub enum RequestMethod {
 }
 
 impl RequestMethod {
+    /// The method name as written on the request line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Delete => "DELETE",
+            Self::Connect => "CONNECT",
+            Self::Options => "OPTIONS",
+            Self::Trace => "TRACE",
+        }
+    }
     /// Safe methods are not supposed to mutate state on the server.
     /// This may be used to force a library or binary to take an
     /// immutable reference to some struct when sent a safe method.
@@ -79,6 +280,33 @@ impl RequestMethod {
     }
 }
 
+/// The request line and header section as sent on the wire, ending in
+/// the blank line. Headers are written in the order they were received.
+/// Obs-text in header values is written [lossily][crate::header::Value::as_str_lossy];
+/// [to_bytes][Request::to_bytes] keeps it.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::Request;
+/// let request: Request = "GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".parse().unwrap();
+/// assert_eq!(request.to_string(), "GET /index.html HTTP/1.1\r\nhost: example.com\r\n\r\n");
+/// ```
+impl Display for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FMTResult {
+        write!(f, "{}\r\n", line::format_request_line(self.method, &self.path, self.version))?;
+        for (k, v) in &self.headers {
+            write!(f, "{}: {}\r\n", k.as_str(), v)?;
+        }
+        write!(f, "\r\n")
+    }
+}
+
+impl Display for RequestMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FMTResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 /// Ascii-uppercase is not technically a must for new HTTP methods,
 /// but all the standardized methods are by said standard all
@@ -139,6 +367,73 @@ pub enum RequestParseError {
     /// The version word in the (`HTTP/[major].[minor]`)-term is
     /// not parseable as such
     InvalidVersion,
+    /// The request bytes are not valid UTF-8
+    InvalidUtf8,
+    /// The request is in a version the server does not speak
+    UnsupportedVersion(Version),
+    /// The connection starts with the HTTP/2 preface `PRI * HTTP/2.0`, sent
+    /// by clients assuming h2c prior knowledge
+    Http2Preface,
+    /// An HTTP/1.1 request without a `host` header, see [Request::check_host]
+    MissingHost,
+    /// The bytes start like a TLS handshake, so the client speaks HTTPS to
+    /// a plaintext port
+    LooksLikeTls,
+    /// The request line and header section are longer than
+    /// [MAX_HEAD_LEN], see [Request::from_reader]
+    HeadTooLarge,
+    /// The body is delimited ambiguously: `transfer-encoding` and
+    /// `content-length` together, a transfer coding not ending in
+    /// `chunked`, a `content-length` that is no number, or malformed chunks
+    InvalidFraming,
+    /// The body is longer than the server accepts, see
+    /// [Connection::set_max_body_size][crate::connection::Connection::set_max_body_size]
+    BodyTooLarge,
+    /// The request did not arrive in time, see
+    /// [Connection::set_header_timeout][crate::connection::Connection::set_header_timeout]
+    Timeout,
+    /// The connection closed before the request was complete
+    UnexpectedEof { stage: EofStage },
+    /// Reading from the connection failed, see
+    /// [Connection::next_request][crate::connection::Connection::next_request]
+    Io(io::ErrorKind),
+}
+impl RequestParseError {
+    /// The status to answer with: [505][Response::HttpVersionNotSupported]
+    /// for unsupported versions and the HTTP/2 preface,
+    /// [501][Response::NotImplemented] for unknown methods,
+    /// [408][Response::RequestTimeout] for requests arriving too slowly,
+    /// [413][Response::PayloadTooLarge] for bodies over the limit,
+    /// [431][Response::RequestHeaderFieldsTooLarge] for header fields over
+    /// their limits and [400][Response::BadRequest] otherwise.
+    ///
+    /// `None` for an empty request, which is no request to answer, for a
+    /// TLS handshake, which would not understand a plaintext answer, and
+    /// for a connection closed or failing mid-request; the connection
+    /// should just be closed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{request::RequestParseError, Request, Response};
+    /// let error = "PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n".parse::<Request>().unwrap_err();
+    /// assert_eq!(error, RequestParseError::Http2Preface);
+    /// assert_eq!(error.appropriate_response(), Some(Response::HttpVersionNotSupported));
+    /// ```
+    pub fn appropriate_response(&self) -> Option<Response> {
+        Some(match self {
+            Self::EmptyRequest | Self::LooksLikeTls | Self::UnexpectedEof { .. } | Self::Io(_) => return None,
+            Self::UnsupportedVersion(_) | Self::Http2Preface => Response::HttpVersionNotSupported,
+            Self::MethodNotRecognized(_) => Response::NotImplemented,
+            Self::Timeout => Response::RequestTimeout,
+            Self::BodyTooLarge => Response::PayloadTooLarge,
+            Self::HeadTooLarge
+            | Self::BadHeader(
+                HeaderError::Key(KeyError::TooLong { .. })
+                | HeaderError::Value { error: ValueError::TooLong { .. } | ValueError::TooManyElements { .. }, .. }
+            ) => Response::RequestHeaderFieldsTooLarge,
+            _ => Response::BadRequest,
+        })
+    }
 }
 impl Error for RequestParseError {}
 impl Display for RequestParseError {
@@ -152,12 +447,40 @@ impl Display for RequestParseError {
                 Self::NoPath => "no path".to_owned(),
                 Self::NoHttpWord => "no version".to_owned(),
                 Self::MethodNotRecognized(e) => format!("method not recognized: {}", e),
-                Self::BadHeader(_) => "header invalid".to_owned(),
+                Self::BadHeader(e) => format!("header invalid: {}", e),
                 Self::InvalidVersion => "version invalid".to_owned(),
+                Self::InvalidUtf8 => "not UTF-8".to_owned(),
+                Self::UnsupportedVersion(v) => format!("{} is not supported", v.http_string()),
+                Self::Http2Preface => "HTTP/2 connection preface".to_owned(),
+                Self::MissingHost => "Missing host header".to_owned(),
+                Self::LooksLikeTls => "TLS handshake on a plaintext connection".to_owned(),
+                Self::HeadTooLarge => "request head too large".to_owned(),
+                Self::InvalidFraming => "ambiguous message framing".to_owned(),
+                Self::BodyTooLarge => "request body too large".to_owned(),
+                Self::Timeout => "request not received in time".to_owned(),
+                Self::UnexpectedEof { stage } => format!("connection closed within the {stage}"),
+                Self::Io(kind) => format!("reading failed: {kind}"),
             }
         )
     }
 }
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Where in a request the connection closed, see
+/// [RequestParseError::UnexpectedEof].
+pub enum EofStage {
+    RequestLine,
+    Headers,
+    Body,
+}
+impl Display for EofStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FMTResult {
+        f.write_str(match self {
+            Self::RequestLine => "request line",
+            Self::Headers => "header section",
+            Self::Body => "body",
+        })
+    }
+}
 impl From<MethodParseError> for RequestParseError {
     fn from(value: MethodParseError) -> Self {
         RequestParseError::MethodNotRecognized(value)
@@ -172,56 +495,122 @@ impl From<HeaderError> for RequestParseError {
 impl FromStr for Request {
     type Err = RequestParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let mut firstline = lines
-            .next()
-            .ok_or(RequestParseError::EmptyRequest)?
-            .split_whitespace();
-        let method_word = firstline.next().ok_or(RequestParseError::NoMethod)?;
-        let path = firstline
-            .next()
-            .ok_or(RequestParseError::NoPath)?
-            .to_string();
-        let http_word = firstline.next().ok_or(RequestParseError::NoHttpWord)?;
-        let version = match http_word
-            .strip_prefix("HTTP/")
-            .map(|x| x.split('.').map(|x| x.parse::<u64>()).collect::<Vec<_>>())
-            .as_deref()
-        {
-            Some([Ok(major), Ok(minor)]) => Version(*major, *minor),
-            _ => return Err(RequestParseError::InvalidVersion),
-        };
-        let headers = lines.take_while(|&l| !l.is_empty()).fold(
-            Ok(HashMap::new()),
-            |h: Result<HashMap<Key, Value>, HeaderError>, new| {
-                let Ok(mut h) = h else {
-                    return h
-                };
-                let mut parts = new.split(':');
-                let key = Key::new(parts.next().ok_or(HeaderError::MissingKey)?)?;
-                let value = parts.next().ok_or(HeaderError::MissingValue)?;
+        Self::parse_bytes(s.as_bytes(), false)
+    }
+}
 
-                match h.entry(key) {
-                    Entry::Occupied(mut x) => x.get_mut().append(value)?,
-                    Entry::Vacant(x) => {
-                        x.insert(Value::new(value)?);
-                    }
-                };
-                Ok(h)
-            },
-        )?;
+impl Request {
+    /// Like [TryFrom<&\[u8\]>][Request::try_from], but accepts obs-text
+    /// (octets 0x80 to 0xFF) in header values, as
+    /// [Value::from_bytes_with_obs_text][crate::header::Value::from_bytes_with_obs_text]
+    /// does. The request line and header names still have to be ASCII.
+    /// A [singleton][crate::header::Key::is_singleton] field repeated with the same value
+    /// is accepted once instead of refused, and HTTP/0.x versions are kept
+    /// for the caller to decide on.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Request;
+    /// let bytes = b"GET / HTTP/1.1\r\nX-Name: caf\xe9\r\n\r\n";
+    /// assert!(Request::try_from(&bytes[..]).is_err());
+    /// let request = Request::parse_lenient(bytes).unwrap();
+    /// assert_eq!(request.headers.get("x-name").unwrap().as_bytes(), b"caf\xe9");
+    /// ```
+    pub fn parse_lenient(bytes: &[u8]) -> Result<Self, RequestParseError> {
+        Self::parse_bytes(bytes, true)
+    }
+    /// Reads one request head from `reader`, up to and including the empty
+    /// line ending the header section, and parses it strictly. A body is
+    /// left in the reader. Empty lines before the request line are skipped.
+    ///
+    /// Parse errors are reported as [InvalidData][io::ErrorKind::InvalidData]
+    /// wrapping a [RequestParseError], a connection closed before the head
+    /// is complete as [UnexpectedEof][io::ErrorKind::UnexpectedEof], which
+    /// wraps [RequestParseError::UnexpectedEof] unless no byte of the head
+    /// was read.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Request;
+    /// let mut stream = &b"\r\nGET / HTTP/1.1\r\nHost: a\r\n\r\nbody"[..];
+    /// let request = Request::from_reader(&mut stream).unwrap();
+    /// assert_eq!(request.headers.get("host").unwrap(), "a");
+    /// assert_eq!(stream, b"body");
+    /// ```
+    pub fn from_reader<R: BufRead>(reader: &mut R) -> io::Result<Self> {
+        RequestParser::new().read(reader)
+    }
+    /// Async variant of [from_reader][Request::from_reader]. It is not
+    /// cancellation safe, as the bytes read so far are lost with a dropped
+    /// future; [RequestParser::read_async] keeps them for another try.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Self> {
+        RequestParser::new().read_async(reader).await
+    }
+    fn parse_bytes(bytes: &[u8], lenient: bool) -> Result<Self, RequestParseError> {
+        if bytes.is_empty() {
+            return Err(RequestParseError::EmptyRequest);
+        }
+        if looks_like_tls(bytes) {
+            return Err(RequestParseError::LooksLikeTls);
+        }
+        let mut lines = bytes
+            .split(|&b| b == b'\n')
+            .map(|l| l.strip_suffix(b"\r").unwrap_or(l));
+        let firstline = lines.next().ok_or(RequestParseError::EmptyRequest)?;
+        if firstline == b"PRI * HTTP/2.0" {
+            return Err(RequestParseError::Http2Preface);
+        }
+        let firstline = std::str::from_utf8(firstline).map_err(|_| RequestParseError::InvalidUtf8)?;
+        let (method_word, path, version) = line::parse_request_line(firstline)?;
+        if version.0 > 1 || (version.0 == 0 && !lenient) {
+            return Err(RequestParseError::UnsupportedVersion(version));
+        }
+        let headers = HeaderMap::from_lines(lines.take_while(|l| !l.is_empty()), lenient)?;
         let method = method_word.parse()?;
         Ok(Request {
             method,
-            path,
+            path: path.to_owned(),
             headers,
             version,
+            extensions: Extensions::new(),
         })
     }
 }
 
+/// Whether `bytes` start like a TLS record of type handshake (22),
+/// followed by the major version 3 of SSL 3.0 and every TLS version.
+pub(crate) fn looks_like_tls(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x16, 0x03])
+}
+
+/// The answer to a request that could not be parsed, see
+/// [appropriate_response][RequestParseError::appropriate_response];
+/// [400][Response::BadRequest] for an empty request.
+impl From<RequestParseError> for ResponseBuilder<Incomplete> {
+    fn from(value: RequestParseError) -> Self {
+        value.appropriate_response().unwrap_or(Response::BadRequest).into()
+    }
+}
+
+impl TryFrom<&[u8]> for Request {
+    type Error = RequestParseError;
+    /// Parses a request as read from the connection.
+    ///
+    /// Only HTTP/1.x is accepted; other versions are an
+    /// [UnsupportedVersion][RequestParseError::UnsupportedVersion].
+    ///
+    /// Repeated list fields are combined with commas, while a repeated
+    /// [singleton][crate::header::Key::is_singleton] field like `content-length` or `host`
+    /// is a [Duplicate][HeaderError::Duplicate].
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse_bytes(bytes, false)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::header::QValue;
     use super::*;
 
     #[test]
@@ -237,21 +626,311 @@ mod tests {
         ))
     }
     #[test]
+    fn strip_hop_by_hop() {
+        let mut request: Request = "GET / HTTP/1.1\r\nHost: x\r\nConnection: close, X-Custom-Hop\r\n\
+            X-Custom-Hop: 1\r\nX-Custom-Keep: 2\r\nTE: trailers\r\n\r\n".parse().unwrap();
+        let removed = request.strip_hop_by_hop();
+        assert_eq!(removed, [Key::CONNECTION, Key::new("x-custom-hop").unwrap(), Key::TE]);
+        assert!(!request.headers.contains_key("x-custom-hop"));
+        assert_eq!(request.headers.get("x-custom-keep").unwrap(), "2");
+        assert_eq!(request.headers.get("host").unwrap(), "x");
+    }
+    #[test]
     fn version_three() {
-        let request = "POST /stuff HTTP/3.0\r\n\r\n".parse().unwrap();
-        assert!(matches!(
-            request,
-            Request {
-                version: Version (3, 0),
-                ..
-            }
-        ))
+        let request = "POST /stuff HTTP/3.0\r\n\r\n".parse::<Request>();
+        assert_eq!(request, Err(RequestParseError::UnsupportedVersion(Version::HTTP_3)));
+    }
+    #[test]
+    fn version_two_refused_with_505() {
+        for line in ["GET / HTTP/2\r\n", "GET / HTTP/2.0\r\n"] {
+            let error = line.parse::<Request>().unwrap_err();
+            assert_eq!(error, RequestParseError::UnsupportedVersion(Version::HTTP_2), "{line}");
+            assert_eq!(ResponseBuilder::from(error).get_status(), &Response::HttpVersionNotSupported);
+        }
+        assert_eq!("GET / HTTP/1\r\n".parse::<Request>(), Err(RequestParseError::InvalidVersion));
+    }
+    #[test]
+    fn foreign_protocols() {
+        let preface = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+        assert_eq!(Request::try_from(&preface[..]), Err(RequestParseError::Http2Preface));
+        assert_eq!(Request::try_from(&preface[..18]), Err(RequestParseError::Http2Preface));
+        assert_eq!(Request::parse_lenient(preface), Err(RequestParseError::Http2Preface));
+        let client_hello = [0x16, 0x03, 0x01, 0x02, 0x00, 0x01, 0x00, 0x01, 0xfc, 0x03, 0x03];
+        let error = Request::try_from(&client_hello[..]).unwrap_err();
+        assert_eq!(error, RequestParseError::LooksLikeTls);
+        assert_eq!(error.appropriate_response(), None);
+        assert_eq!(ResponseBuilder::from(RequestParseError::Http2Preface).get_status(), &Response::HttpVersionNotSupported);
+    }
+    #[test]
+    fn host_required_from_one_one() {
+        let request: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(request.check_host(), Err(RequestParseError::MissingHost));
+        let request: Request = "GET / HTTP/1.1\r\nHost: a\r\n\r\n".parse().unwrap();
+        assert_eq!(request.check_host(), Ok(()));
+        let request: Request = "GET / HTTP/1.0\r\n\r\n".parse().unwrap();
+        assert_eq!(request.check_host(), Ok(()));
+    }
+    #[test]
+    fn from_reader_limits() {
+        let parse_error = |e: io::Error| e.into_inner().and_then(|e| e.downcast::<RequestParseError>().ok()).map(|e| *e);
+        let mut tls = &[0x16, 0x03, 0x01, 0x02, 0x00][..];
+        assert_eq!(parse_error(Request::from_reader(&mut tls).unwrap_err()), Some(RequestParseError::LooksLikeTls));
+        let long = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_HEAD_LEN));
+        let error = Request::from_reader(&mut long.as_bytes()).unwrap_err();
+        assert_eq!(parse_error(error), Some(RequestParseError::HeadTooLarge));
+        let error = Request::from_reader(&mut &b"GET / HTTP/1.1\r\nHost: a\r\n"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(parse_error(error), Some(RequestParseError::UnexpectedEof { stage: EofStage::Headers }));
+        let mut pipelined = &b"GET /a HTTP/1.1\n\nGET /b HTTP/1.1\r\n\r\n"[..];
+        assert_eq!(Request::from_reader(&mut pipelined).unwrap().path, "/a");
+        assert_eq!(Request::from_reader(&mut pipelined).unwrap().path, "/b");
+        let error = Request::from_reader(&mut pipelined).unwrap_err();
+        assert_eq!((error.kind(), parse_error(error)), (io::ErrorKind::UnexpectedEof, None));
+    }
+    #[test]
+    fn from_reader_across_buffer_boundaries() {
+        let input = b"GET /a HTTP/1.1\r\nHost: a\r\n\r\nGET /b HTTP/1.1\r\nHost: a\r\n\r\n";
+        for capacity in 1..input.len() {
+            let mut reader = io::BufReader::with_capacity(capacity, &input[..]);
+            assert_eq!(Request::from_reader(&mut reader).unwrap().path, "/a");
+            assert_eq!(Request::from_reader(&mut reader).unwrap().path, "/b");
+        }
+    }
+    #[test]
+    fn lenient_close_next_to_obs_text() {
+        let request = Request::parse_lenient(b"GET / HTTP/1.1\r\nHost: x\r\nConnection: close, caf\xe9\r\n\r\n").unwrap();
+        assert!(!request.keep_alive());
+    }
+    #[test]
+    fn version_zero_nine_only_lenient() {
+        let line = b"GET / HTTP/0.9\r\n";
+        assert_eq!(Request::try_from(&line[..]), Err(RequestParseError::UnsupportedVersion(Version::HTTP_09)));
+        let request = Request::parse_lenient(line).unwrap();
+        assert_eq!(request.version, Version::HTTP_09);
+        assert_eq!(request.check_version(&Version::HTTP_11), Err(RequestParseError::UnsupportedVersion(Version::HTTP_09)));
+        assert_eq!("GET / HTTP/1.1\r\n".parse::<Request>().unwrap().check_version(&Version::HTTP_11), Ok(()));
+        assert_eq!("GET / HTTP/1.1\r\n".parse::<Request>().unwrap().check_version(&Version::HTTP_10), Ok(()));
     }
     #[test]
     fn version_invalid_three_items() {
         let request = "DELETE /other/stuff HTTP/2.0.1\r\n".parse::<Request>();
         assert_eq!(request, Err(RequestParseError::InvalidVersion))
     }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let request = "POST /stuff HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Accept: text/html\r\n\
+            Accept: text/plain\r\n"
+            .parse::<Request>()
+            .unwrap();
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_validates_headers() {
+        let json = r#"{"method":"GET","path":"/","headers":{"bad key ":"x"},"version":"1.1"}"#;
+        assert!(serde_json::from_str::<Request>(json).is_err());
+        let json = r#"{"method":"GET","path":"/","headers":{"key":"line\nbreak"},"version":"1.1"}"#;
+        assert!(serde_json::from_str::<Request>(json).is_err());
+    }
+    #[test]
+    fn if_none_match_weak_comparison() {
+        let request = "GET / HTTP/1.1\r\nIf-None-Match: \"x\", W/\"abc\"\r\n"
+            .parse::<Request>()
+            .unwrap();
+        assert!(request.if_none_match(&EntityTag::strong("abc").unwrap()));
+        assert!(!request.if_none_match(&EntityTag::strong("abd").unwrap()));
+    }
+    #[test]
+    fn extensions_excluded_from_eq() {
+        let mut request = "GET / HTTP/1.1\r\n".parse::<Request>().unwrap();
+        request.extensions.insert("user");
+        assert_eq!(request.extensions.get::<&str>(), Some(&"user"));
+        assert_eq!(request, "GET / HTTP/1.1\r\n".parse::<Request>().unwrap());
+    }
+    #[test]
+    fn from_bytes() {
+        let request = Request::try_from(&b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"[..]).unwrap();
+        assert_eq!(request.headers.get("host").unwrap(), "a");
+        assert_eq!(Request::try_from(&b"GET /\xff HTTP/1.1\r\n"[..]), Err(RequestParseError::InvalidUtf8));
+    }
+    #[test]
+    fn display_round_trip() {
+        let request = "PUT /a?b=c HTTP/1.0\r\nX-One: 1\r\nX-Two: a:b\r\n\r\n".parse::<Request>().unwrap();
+        assert_eq!(request.to_string().parse::<Request>().unwrap(), request);
+    }
+    #[test]
+    fn lenient_obs_text() {
+        let bytes = b"GET / HTTP/1.1\r\nContent-Disposition: attachment; filename=caf\xe9.txt\r\n\r\n";
+        assert_eq!(
+            Request::try_from(&bytes[..]),
+            Err(RequestParseError::BadHeader(HeaderError::Value {
+                key: Some(Key::new("content-disposition").unwrap()),
+                error: ValueError::NonAsciiChars { input: "attachment; filename=caf\u{fffd}.txt".to_owned(), at: 24 },
+            }))
+        );
+        let request = Request::parse_lenient(bytes).unwrap();
+        let value = request.headers.get("content-disposition").unwrap();
+        assert_eq!(value.as_bytes(), b"attachment; filename=caf\xe9.txt");
+        assert_eq!(request.to_bytes(), b"GET / HTTP/1.1\r\ncontent-disposition: attachment; filename=caf\xe9.txt\r\n\r\n");
+        let error = Request::parse_lenient(b"GET / HTTP/1.1\r\nX: a\0b\r\n").unwrap_err();
+        assert_eq!(error.to_string(), r#"header invalid: Value of x: illegal character '\0' at 1 in "a\0b""#);
+        let mut headers = HeaderMap::new();
+        headers.append_value(Key::CONTENT_DISPOSITION, value.clone()).unwrap();
+        let response = crate::response::ResponseBuilder::from_parts(Response::Ok, headers, b"x".to_vec());
+        let written = crate::Byteable::into_bytes(response);
+        let line = b"content-disposition: attachment; filename=caf\xe9.txt\r\n";
+        assert!(written.windows(line.len()).any(|w| w == line));
+    }
+    #[test]
+    fn combined_header_too_long() {
+        let line = format!("X-Big: {}\r\n", "a".repeat(6000));
+        let request = format!("GET / HTTP/1.1\r\n{line}{line}\r\n");
+        assert!(request.parse::<Request>().is_ok());
+        let request = format!("GET / HTTP/1.1\r\n{line}{line}{line}\r\n");
+        assert_eq!(request.parse::<Request>(), Err(RequestParseError::BadHeader(HeaderError::Value {
+            key: Some(Key::new("x-big").unwrap()),
+            error: ValueError::TooLong { len: 18002, limit: crate::header::MAX_VALUE_LEN },
+        })));
+    }
+    #[test]
+    fn repeated_header_hits_element_limit() {
+        let mut request = String::from("GET / HTTP/1.1\r\n");
+        for _ in 0..10_000 {
+            request.push_str("X-Foo: a\r\n");
+        }
+        let error = request.parse::<Request>().unwrap_err();
+        assert_eq!(error, RequestParseError::BadHeader(HeaderError::Value {
+            key: Some(Key::new("x-foo").unwrap()),
+            error: ValueError::TooManyElements { count: crate::header::MAX_VALUE_ELEMENTS + 1, limit: crate::header::MAX_VALUE_ELEMENTS },
+        }));
+        assert_eq!(ResponseBuilder::from(error).get_status(), &Response::RequestHeaderFieldsTooLarge);
+        let cookies = "GET / HTTP/1.1\r\n".to_owned() + &"Set-Cookie: a=1\r\n".repeat(10_000);
+        assert!(matches!(
+            cookies.parse::<Request>(),
+            Err(RequestParseError::BadHeader(HeaderError::Value { error: ValueError::TooManyElements { .. }, .. }))
+        ));
+    }
+    #[test]
+    fn obs_text_counts_toward_element_limit() {
+        let mut request = b"GET / HTTP/1.1\r\nHost: x\r\n".to_vec();
+        for _ in 0..3000 {
+            request.extend_from_slice(b"X: \xe9\r\n");
+        }
+        request.extend_from_slice(b"\r\n");
+        assert!(matches!(
+            Request::parse_lenient(&request),
+            Err(RequestParseError::BadHeader(HeaderError::Value { error: ValueError::TooManyElements { .. }, .. }))
+        ));
+    }
+    #[test]
+    fn te_trailers() {
+        let request = "GET / HTTP/1.1\r\nTE: trailers, deflate;q=0.5\r\n\r\n".parse::<Request>().unwrap();
+        assert!(request.accepts_trailers());
+        assert_eq!(request.te().unwrap().rank("deflate").map(QValue::as_f32), Some(0.5));
+        let request = "GET / HTTP/1.1\r\n\r\n".parse::<Request>().unwrap();
+        assert!(!request.accepts_trailers());
+        assert_eq!(request.te(), None);
+    }
+    #[test]
+    fn if_modified_since() {
+        let request = "GET / HTTP/1.1\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n".parse::<Request>().unwrap();
+        let date = std::time::UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+        assert_eq!(request.if_modified_since(), Some(date));
+        let request = "GET / HTTP/1.1\r\nIf-Modified-Since: soon\r\n".parse::<Request>().unwrap();
+        assert_eq!(request.if_modified_since(), None);
+    }
+    #[test]
+    fn non_token_key_is_bad_request() {
+        let error = "GET / HTTP/1.1\r\nBad Key: x\r\n".parse::<Request>().unwrap_err();
+        assert_eq!(error, RequestParseError::BadHeader(HeaderError::Key(KeyError::IllegalChar {
+            input: "Bad Key".to_owned(),
+            found: ' ',
+            at: 3,
+        })));
+        assert_eq!(ResponseBuilder::from(error).get_status(), &Response::BadRequest);
+        let error = "GETS / HTTP/1.1\r\n".parse::<Request>().unwrap_err();
+        assert_eq!(ResponseBuilder::from(error).get_status(), &Response::NotImplemented);
+    }
+    #[test]
+    fn cache_control() {
+        let request = "GET / HTTP/1.1\r\nCache-Control: no-cache\r\nCache-Control: max-age=0, min-fresh=30\r\n\r\n"
+            .parse::<Request>()
+            .unwrap();
+        let cache = request.cache_control().unwrap().unwrap();
+        assert!(cache.no_cache());
+        assert_eq!((cache.max_age(), cache.min_fresh()), (Some(0), Some(30)));
+    }
+    #[test]
+    fn mixed_case_tokens() {
+        let request = "GET /chat HTTP/1.1\r\nConnection: keep-alive, Upgrade\r\nUpgrade: WebSocket\r\nExpect: 100-Continue\r\n\r\n"
+            .parse::<Request>()
+            .unwrap();
+        assert!(request.wants_upgrade("websocket"));
+        assert!(!request.wants_upgrade("h2c"));
+        assert!(request.expects_continue());
+        let close = "GET / HTTP/1.1\r\nConnection: Close\r\n\r\n".parse::<Request>().unwrap();
+        assert!(!close.keep_alive());
+        assert!(!close.wants_upgrade("websocket"));
+        assert!(!close.expects_continue());
+    }
+    #[test]
+    fn upgrade_tokens() {
+        let request: Request = "GET / HTTP/1.1\r\nUpgrade: h2c, websocket/13\r\nConnection: keep-alive, Upgrade\r\n\r\n".parse().unwrap();
+        assert_eq!(request.upgrade_tokens(), [
+            Protocol { name: "h2c".to_owned(), version: None },
+            Protocol { name: "websocket".to_owned(), version: Some("13".to_owned()) },
+        ]);
+        assert!(request.wants_upgrade("WebSocket"));
+        let unannounced: Request = "GET / HTTP/1.1\r\nUpgrade: h2c\r\n\r\n".parse().unwrap();
+        assert!(unannounced.upgrade_tokens().is_empty());
+    }
+    #[test]
+    fn duplicate_singletons() {
+        let twice = "POST / HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Type: text/html\r\n\r\n";
+        assert_eq!(twice.parse::<Request>(), Err(RequestParseError::BadHeader(HeaderError::Duplicate(Key::CONTENT_TYPE))));
+        let same = b"POST / HTTP/1.1\r\nContent-Length: 3\r\ncontent-length: 3\r\n\r\n";
+        assert!(Request::try_from(&same[..]).is_err());
+        assert_eq!(Request::parse_lenient(same).unwrap().headers.get("content-length").unwrap(), "3");
+        let request = "GET / HTTP/1.1\r\nAccept: text/html\r\nAccept: */*\r\n\r\n".parse::<Request>().unwrap();
+        assert_eq!(request.headers.get("accept").unwrap(), "text/html,*/*");
+    }
+    #[test]
+    fn accept() {
+        let request = "GET / HTTP/1.1\r\nAccept: text/html\r\nAccept: */*;q=0.1\r\n\r\n".parse::<Request>().unwrap();
+        let accept = request.accept().unwrap().unwrap();
+        let offered = ["application/json".parse().unwrap()];
+        assert_eq!(crate::header::media_type::negotiate(&accept, &offered), Some(&offered[0]));
+    }
+    #[test]
+    fn authorization_roundtrip() {
+        let mut request = "GET / HTTP/1.1\r\n".parse::<Request>().unwrap();
+        assert_eq!(request.authorization(), None);
+        let basic = Authorization::Basic { user: "me".to_owned(), password: "secret".to_owned() };
+        request.set_typed_header(basic.clone()).unwrap();
+        assert_eq!(request.headers.get("authorization").unwrap(), "Basic bWU6c2VjcmV0");
+        assert_eq!(request.authorization(), Some(Ok(basic)));
+    }
+    #[test]
+    fn set_typed_header_replaces() {
+        let mut request = "GET / HTTP/1.1\r\nEtag: \"old\"\r\n".parse::<Request>().unwrap();
+        request.set_typed_header(EntityTag::strong("new").unwrap()).unwrap();
+        assert_eq!(request.headers.get("etag").unwrap(), "\"new\"");
+    }
+    #[test]
+    fn set_typed_header_too_long() {
+        use crate::header::{Via, MAX_VALUE_LEN};
+        let mut request = "GET / HTTP/1.1\r\n".parse::<Request>().unwrap();
+        let too_long = |key: Key, len| Err(HeaderError::Value { key: Some(key), error: ValueError::TooLong { len, limit: MAX_VALUE_LEN } });
+        let bearer = Authorization::Bearer("a".repeat(17000));
+        assert_eq!(request.set_typed_header(bearer), too_long(Key::AUTHORIZATION, 17007));
+        let via = Via::parse(&format!("1.1 {}", "a".repeat(17000)));
+        assert_eq!(request.set_typed_header(via), too_long(Key::VIA, 17004));
+        assert!(request.headers.get("authorization").is_none());
+    }
     #[test]
     fn headers_combine() {
         let request = "POST /stuff HTTP/1.1\r\n\
@@ -262,4 +941,11 @@ mod tests {
             .unwrap();
         assert_eq!(request.headers.get("some_header").unwrap(), "A,B,C");
     }
+    #[test]
+    fn header_value_with_colons() {
+        let request = "GET / HTTP/1.1\r\nOrigin: https://a.example:8443\r\n\r\n"
+            .parse::<Request>()
+            .unwrap();
+        assert_eq!(request.headers.get("origin").unwrap(), "https://a.example:8443");
+    }
 }
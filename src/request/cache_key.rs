@@ -0,0 +1,100 @@
+use crate::{header::key::Key, RequestMethod};
+
+use super::Request;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+/// Identifies the stored response a cache may use for a request, built
+/// with [Request::cache_key].
+pub struct CacheKey {
+    method: RequestMethod,
+    target: String,
+    varied: Vec<(Key, Option<Vec<u8>>)>,
+}
+
+impl Request {
+    /// The cache key of this request for a stored response that varies on
+    /// the headers in `vary`.
+    ///
+    /// The target is normalized by decoding percent-encoded unreserved
+    /// characters and uppercasing the remaining escapes. Varied headers are
+    /// compared by their exact values, in the order of `vary`; an absent
+    /// header is kept distinct from every value.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Request, header::Key};
+    /// let a: Request = "GET /%7Euser HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n".parse().unwrap();
+    /// let b: Request = "GET /~user HTTP/1.1\r\nAccept-Encoding: br\r\n\r\n".parse().unwrap();
+    /// assert_eq!(a.cache_key(&[]), b.cache_key(&[]));
+    /// let vary = [Key::ACCEPT_ENCODING];
+    /// assert_ne!(a.cache_key(&vary), b.cache_key(&vary));
+    /// ```
+    pub fn cache_key(&self, vary: &[Key]) -> CacheKey {
+        CacheKey {
+            method: self.method,
+            target: normalize_target(&self.path),
+            varied: vary.iter()
+                .map(|k| (k.clone(), self.headers.get(k.as_str()).map(|v| v.as_bytes().to_vec())))
+                .collect(),
+        }
+    }
+}
+
+fn normalize_target(target: &str) -> String {
+    let bytes = target.as_bytes();
+    let mut normalized = String::with_capacity(target.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escape {
+            Some(b) if b.is_ascii_alphanumeric() || b"-._~".contains(&b) => {
+                normalized.push(b as char);
+                i += 3;
+            }
+            Some(_) => {
+                normalized.push_str(&target[i..i + 3].to_ascii_uppercase());
+                i += 3;
+            }
+            None => {
+                let c = target[i..].chars().next().unwrap();
+                normalized.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(s: &str) -> Request {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn vary_on_accept_encoding() {
+        let gzip = request("GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n");
+        let br = request("GET / HTTP/1.1\r\nAccept-Encoding: br\r\n\r\n");
+        let vary = [Key::new("Accept-Encoding").unwrap()];
+        assert_ne!(gzip.cache_key(&vary), br.cache_key(&vary));
+        assert_eq!(gzip.cache_key(&[]), br.cache_key(&[]));
+    }
+    #[test]
+    fn absent_header_distinct() {
+        let absent = request("GET / HTTP/1.1\r\n\r\n");
+        let present = request("GET / HTTP/1.1\r\nOrigin: null\r\n\r\n");
+        let vary = [Key::ORIGIN];
+        assert_ne!(absent.cache_key(&vary), present.cache_key(&vary));
+        assert_eq!(absent.cache_key(&vary), request("GET / HTTP/1.1\r\n\r\n").cache_key(&vary));
+    }
+    #[test]
+    fn method_and_target() {
+        assert_ne!(request("GET / HTTP/1.1\r\n").cache_key(&[]), request("HEAD / HTTP/1.1\r\n").cache_key(&[]));
+        assert_eq!(normalize_target("/a%2fb%41%7e?q=%e2%82%ac"), "/a%2FbA~?q=%E2%82%AC");
+        assert_eq!(normalize_target("/%zz%4"), "/%zz%4");
+    }
+}
@@ -0,0 +1,172 @@
+use std::io::{self, BufRead};
+
+use super::{looks_like_tls, EofStage, Request, RequestParseError, MAX_HEAD_LEN};
+
+#[derive(Debug, Default)]
+/// Incremental parser for request heads arriving in pieces, as they are
+/// read from a connection.
+///
+/// The bytes fed so far are kept in the parser, so reading can stop at
+/// any point and continue later: the async
+/// [read_async][RequestParser::read_async] is cancellation safe as long as
+/// the parser outlives the dropped future. Empty lines before the request
+/// line are skipped.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::request::RequestParser;
+/// let mut parser = RequestParser::new();
+/// assert_eq!(parser.feed(b"GET / HT"), Ok((8, None)));
+/// let (used, request) = parser.feed(b"TP/1.1\r\n\r\nnext").unwrap();
+/// assert_eq!((used, request.unwrap().path.as_str()), (10, "/"));
+/// ```
+pub struct RequestParser {
+    head: Vec<u8>,
+}
+
+impl RequestParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Takes bytes up to the end of the head from `bytes`, returning how
+    /// many were used and the request once its head is complete. Bytes
+    /// after the head, such as a body, are left to the caller.
+    ///
+    /// After an error the head read so far is dropped, and the connection
+    /// should be closed.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(usize, Option<Request>), RequestParseError> {
+        let mut used = 0;
+        while used < bytes.len() {
+            let rest = &bytes[used..];
+            let line_len = rest.iter().position(|&b| b == b'\n').map_or(rest.len(), |i| i + 1);
+            let take = line_len.min(MAX_HEAD_LEN - self.head.len());
+            self.head.extend_from_slice(&rest[..take]);
+            used += take;
+            // Refuse a TLS handshake before waiting for a line ending it will not send
+            if looks_like_tls(&self.head) {
+                self.head.clear();
+                return Err(RequestParseError::LooksLikeTls);
+            }
+            if matches!(&self.head[..], b"\n" | b"\r\n") {
+                self.head.clear();
+            } else if self.head.ends_with(b"\n\n") || self.head.ends_with(b"\n\r\n") {
+                let head = std::mem::take(&mut self.head);
+                return Request::parse_bytes(&head, false).map(|request| (used, Some(request)));
+            } else if self.head.len() == MAX_HEAD_LEN {
+                self.head.clear();
+                return Err(RequestParseError::HeadTooLarge);
+            }
+        }
+        Ok((used, None))
+    }
+    /// Whether no part of a head has been fed since the last request.
+    pub fn is_idle(&self) -> bool {
+        self.head.is_empty()
+    }
+    /// Where the head fed so far ends, `None` if the parser is
+    /// [idle][Self::is_idle].
+    pub fn stage(&self) -> Option<EofStage> {
+        match self.head.contains(&b'\n') {
+            _ if self.is_idle() => None,
+            true => Some(EofStage::Headers),
+            false => Some(EofStage::RequestLine),
+        }
+    }
+    /// The error for input ending now: [UnexpectedEof][io::ErrorKind::UnexpectedEof],
+    /// wrapping the stage if a head was started.
+    fn eof(&self) -> io::Error {
+        match self.stage() {
+            Some(stage) => io::Error::new(io::ErrorKind::UnexpectedEof, RequestParseError::UnexpectedEof { stage }),
+            None => io::ErrorKind::UnexpectedEof.into(),
+        }
+    }
+    /// Reads the next request head from `reader`, see [Request::from_reader].
+    pub fn read<R: BufRead>(&mut self, reader: &mut R) -> io::Result<Request> {
+        loop {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Err(self.eof());
+            }
+            let (used, request) = self.feed(buf).map_err(invalid)?;
+            reader.consume(used);
+            if let Some(request) = request {
+                return Ok(request);
+            }
+        }
+    }
+    /// Async variant of [read][RequestParser::read]. Cancellation safe:
+    /// a future dropped while waiting for input, e.g. by a timeout, leaves
+    /// the bytes read so far in the parser for the next call.
+    #[cfg(feature = "tokio")]
+    pub async fn read_async<R: tokio::io::AsyncBufRead + Unpin>(&mut self, reader: &mut R) -> io::Result<Request> {
+        use tokio::io::AsyncBufReadExt;
+        loop {
+            let buf = reader.fill_buf().await?;
+            if buf.is_empty() {
+                return Err(self.eof());
+            }
+            let (used, request) = self.feed(buf).map_err(invalid)?;
+            reader.consume(used);
+            if let Some(request) = request {
+                return Ok(request);
+            }
+        }
+    }
+}
+
+fn invalid(e: RequestParseError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_at_every_byte() {
+        let input = b"\r\nGET /a HTTP/1.1\r\nHost: a\r\n\r\n";
+        for split in 0..input.len() {
+            let mut parser = RequestParser::new();
+            let (used, request) = parser.feed(&input[..split]).unwrap();
+            assert_eq!((used, request), (split, None));
+            let (used, request) = parser.feed(&input[split..]).unwrap();
+            assert_eq!(used, input.len() - split);
+            assert_eq!(request.unwrap().headers.get("host").unwrap(), "a");
+            assert!(parser.is_idle());
+        }
+    }
+    #[test]
+    fn stops_after_head() {
+        let mut parser = RequestParser::new();
+        let input = b"GET /a HTTP/1.1\n\nGET /b HTTP/1.1\r\n\r\n";
+        let (used, request) = parser.feed(input).unwrap();
+        assert_eq!(request.unwrap().path, "/a");
+        let (_, request) = parser.feed(&input[used..]).unwrap();
+        assert_eq!(request.unwrap().path, "/b");
+    }
+    #[test]
+    fn tls_needs_two_bytes() {
+        let mut parser = RequestParser::new();
+        assert_eq!(parser.feed(&[0x16]), Ok((1, None)));
+        assert_eq!(parser.feed(&[0x03, 0x01]), Err(RequestParseError::LooksLikeTls));
+        // A lone 0x16 is just a bad request line
+        let mut parser = RequestParser::new();
+        let error = parser.feed(b"\x16 / HTTP/1.1\r\n\r\n").unwrap_err();
+        assert!(matches!(error, RequestParseError::MethodNotRecognized(_)), "{error:?}");
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn read_async_survives_timeout() {
+        use std::time::Duration;
+        use tokio::io::{AsyncWriteExt, BufReader};
+        let (mut client, server) = tokio::io::duplex(64);
+        let (mut reader, mut parser) = (BufReader::new(server), RequestParser::new());
+        client.write_all(b"GET /a HTTP/1.1\r\nHo").await.unwrap();
+        let read = tokio::time::timeout(Duration::from_millis(20), parser.read_async(&mut reader)).await;
+        assert!(read.is_err());
+        assert!(!parser.is_idle());
+        client.write_all(b"st: a\r\n\r\n").await.unwrap();
+        let request = parser.read_async(&mut reader).await.unwrap();
+        assert_eq!(request.headers.get("host").unwrap(), "a");
+    }
+}
@@ -0,0 +1,158 @@
+//! The opening handshake of WebSocket (RFC 6455, section 4.2). Frames are
+//! out of scope: after answering with the handshake response, the stream
+//! is handed to a WebSocket implementation, e.g. through
+//! [Connection::into_parts][crate::connection::Connection::into_parts].
+//!
+//! # Examples
+//! ```
+//! # use heggemann_http::{request::websocket, Request};
+//! let request: Request = "GET /chat HTTP/1.1\r\n\
+//!     Host: server.example.com\r\n\
+//!     Upgrade: websocket\r\n\
+//!     Connection: Upgrade\r\n\
+//!     Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+//!     Sec-WebSocket-Version: 13\r\n\r\n".parse().unwrap();
+//! assert!(websocket::is_upgrade(&request));
+//! let response = websocket::handshake(&request).unwrap().body("");
+//! assert_eq!(response.get_header("sec-websocket-accept").unwrap(), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+//! ```
+
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use crate::{
+    base64,
+    response::{Incomplete, ResponseBuilder},
+    sha1,
+    Request,
+    RequestMethod,
+    Response,
+    Version,
+};
+
+/// Appended to the key before hashing, fixed by RFC 6455.
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The only version of the protocol, as sent in `sec-websocket-version`.
+pub const VERSION: &str = "13";
+
+/// Whether `request` asks to switch to WebSocket: a `GET` with
+/// `upgrade: websocket` and `connection: upgrade`.
+pub fn is_upgrade(request: &Request) -> bool {
+    request.method == RequestMethod::Get && request.wants_upgrade("websocket")
+}
+
+/// Checks a WebSocket upgrade request and builds the
+/// [101][Response::SwitchingProtocols] accepting it, to which
+/// `sec-websocket-protocol` or `sec-websocket-extensions` can be added.
+pub fn handshake(request: &Request) -> Result<ResponseBuilder<Incomplete>, HandshakeError> {
+    if request.method != RequestMethod::Get {
+        return Err(HandshakeError::NotGet(request.method));
+    }
+    if request.version < Version::HTTP_11 {
+        return Err(HandshakeError::UnsupportedHttpVersion(request.version));
+    }
+    if !request.wants_upgrade("websocket") {
+        return Err(HandshakeError::NotUpgrade);
+    }
+    let version = request.headers.get("sec-websocket-version");
+    if version.is_none_or(|v| v != VERSION) {
+        return Err(HandshakeError::UnsupportedVersion);
+    }
+    let key = request.headers.get("sec-websocket-key").ok_or(HandshakeError::MissingKey)?.to_string();
+    // The key is a random 16 byte nonce
+    if base64::decode(&key).is_none_or(|nonce| nonce.len() != 16) {
+        return Err(HandshakeError::InvalidKey);
+    }
+    Ok(Response::switching_protocols("websocket")
+        .and_then(|r| r.header("sec-websocket-accept", accept_key(&key)))
+        // Fixed names and base64 values are valid
+        .unwrap())
+}
+
+/// The `sec-websocket-accept` answering `key`: the base64 of the SHA-1 of
+/// the key and a fixed GUID.
+pub fn accept_key(key: &str) -> String {
+    base64::encode(&sha1::digest(format!("{key}{GUID}").as_bytes()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HandshakeError {
+    /// The handshake has to be a `GET`
+    NotGet(RequestMethod),
+    /// WebSocket needs at least HTTP/1.1
+    UnsupportedHttpVersion(Version),
+    /// The request lacks `upgrade: websocket` or `connection: upgrade`
+    NotUpgrade,
+    /// `sec-websocket-version` is missing or not [13][VERSION]
+    UnsupportedVersion,
+    /// There is no `sec-websocket-key`
+    MissingKey,
+    /// `sec-websocket-key` is not the base64 of 16 bytes
+    InvalidKey,
+}
+impl HandshakeError {
+    /// A [426][Response::UpgradeRequired] listing the supported version
+    /// for an unsupported one, as RFC 6455 asks, and a
+    /// [400][Response::BadRequest] otherwise.
+    pub fn appropriate_response(&self) -> ResponseBuilder<Incomplete> {
+        match self {
+            Self::UnsupportedVersion => Response::upgrade_required(&["websocket"])
+                .and_then(|r| r.header("sec-websocket-version", VERSION))
+                // Fixed values are valid
+                .unwrap(),
+            _ => ResponseBuilder::from(Response::BadRequest),
+        }
+    }
+}
+impl Error for HandshakeError {}
+impl Display for HandshakeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::NotGet(method) => write!(f, "WebSocket handshake with {method} instead of GET"),
+            Self::UnsupportedHttpVersion(v) => write!(f, "WebSocket handshake in {}", v.http_string()),
+            Self::NotUpgrade => write!(f, "request does not ask to upgrade to WebSocket"),
+            Self::UnsupportedVersion => write!(f, "WebSocket version missing or not {VERSION}"),
+            Self::MissingKey => write!(f, "no sec-websocket-key"),
+            Self::InvalidKey => write!(f, "sec-websocket-key is no base64 16 byte nonce"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgrade(extra: &str) -> Request {
+        format!("GET /chat HTTP/1.1\r\nHost: x\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\n{extra}\r\n")
+            .parse().unwrap()
+    }
+
+    #[test]
+    fn rfc_example() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        let request = upgrade("Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n");
+        assert!(is_upgrade(&request));
+        assert_eq!(handshake(&request).unwrap().body("").to_string(), "\
+            HTTP/1.0 101 Switching Protocols\r\n\
+            upgrade: websocket\r\n\
+            connection: Upgrade\r\n\
+            sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n");
+    }
+    #[test]
+    fn refused() {
+        let key = "Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n";
+        assert_eq!(handshake(&upgrade(key)), Err(HandshakeError::UnsupportedVersion));
+        assert_eq!(handshake(&upgrade("Sec-WebSocket-Version: 13\r\n")), Err(HandshakeError::MissingKey));
+        let short = "Sec-WebSocket-Key: c2hvcnQ=\r\nSec-WebSocket-Version: 13\r\n";
+        assert_eq!(handshake(&upgrade(short)), Err(HandshakeError::InvalidKey));
+        let plain: Request = "GET /chat HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap();
+        assert!(!is_upgrade(&plain));
+        assert_eq!(handshake(&plain), Err(HandshakeError::NotUpgrade));
+        let version = HandshakeError::UnsupportedVersion.appropriate_response().body("");
+        assert_eq!(version.get_status(), &Response::UpgradeRequired);
+        assert_eq!(version.get_header("sec-websocket-version").unwrap(), "13");
+    }
+}
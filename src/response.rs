@@ -1,37 +1,159 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
     error::Error,
+    io::{self, Write},
     marker::PhantomData,
+    time::{Duration, SystemTime},
     fmt::{Display, Formatter, Result as FmtResult},
     string::FromUtf8Error,
 };
 
 use crate::{
-    header::{key::Key, value::Value, HeaderError},
+    extensions::Extensions,
+    header::{key::Key, value::Value, ByteRange, Challenge, ContentDisposition, ContentRange, EntityTag, HeaderError, HeaderMap, Link, Protocol, SetCookie, ToHeader, Unsatisfiable, ValueError, ValueLimits},
+    Request,
+    RequestMethod,
+    UnsupportedVersion,
     Version
 };
 
+mod chunked;
+mod cors;
+mod defaults;
+mod encoding;
+mod event_stream;
+mod file;
+mod into_response;
+mod multipart;
+mod problem;
+mod security;
+mod sequence;
+mod status_line;
+mod trace;
+#[cfg(feature = "gzip")]
+mod gzip;
+
+pub use chunked::{allowed_in_trailer, decode_chunked, ChunkedError, TrailerPolicy, Trailers};
+pub use cors::{AllowOrigin, Cors};
+pub use defaults::ResponseDefaults;
+pub use encoding::{negotiate_encoding, Encoding};
+pub use event_stream::EventStream;
+pub use file::mime_type;
+pub use into_response::IntoResponse;
+pub use problem::ProblemBuilder;
+pub use security::{FrameOptions, ReferrerPolicy, SecurityPreset};
+pub use sequence::{ResponseSequence, SequenceError};
+pub use status_line::{StatusLine, StatusLineError};
+pub use trace::TRACE_REDACTED;
+use status_line::StatusLineRef;
+#[cfg(feature = "gzip")]
+pub use gzip::GZIP_THRESHOLD;
+
+/// A status code, implemented by [Response] and by application enums
+/// mapping onto it, either by hand or through [impl_response_code].
+///
+/// Only [code][ResponseCode::code] is required. Codes missing from the
+/// standard table get an empty reason phrase, which HTTP allows; override
+/// [standard_phrase][ResponseCode::standard_phrase] to supply one.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{ResponseCode, Version};
+/// struct Stale;
+/// impl ResponseCode for Stale {
+///     fn code(&self) -> u16 {
+///         299
+///     }
+///     fn standard_phrase(&self) -> &'static str {
+///         "Stale"
+///     }
+/// }
+/// assert_eq!(Stale.first_line(Version(1, 1)), "HTTP/1.1 299 Stale");
+/// ```
 pub trait ResponseCode {
     fn code(&self) -> u16;
-    fn standard_phrase(&self) -> &'static str{
-        standard_phrase(self.code()).unwrap()
+    /// The canonical reason phrase, or `""` for codes outside the standard table.
+    fn standard_phrase(&self) -> &'static str {
+        standard_phrase(self.code()).unwrap_or("")
+    }
+    /// The reason phrase in `style`, falling back to
+    /// [standard_phrase][ResponseCode::standard_phrase] for unknown codes.
+    fn phrase(&self, style: PhraseStyle) -> &'static str {
+        phrase(self.code(), style).unwrap_or_else(|| self.standard_phrase())
+    }
+    /// The status line without its line ending, e.g. `HTTP/1.1 404 Not Found`.
+    fn first_line(&self, version: Version) -> String {
+        status_line(self, version, PhraseStyle::Canonical).to_string()
     }
 }
 
-pub trait Byteable {
-    fn into_bytes(self) -> Vec<u8>;
-    fn max_version(&self) -> Version;
+fn status_line<C: ResponseCode + ?Sized>(code: &C, version: Version, style: PhraseStyle) -> StatusLineRef<'static> {
+    StatusLineRef {
+        version,
+        code: code.code(),
+        phrase: code.phrase(style),
+    }
 }
 
-impl<T: Byteable + ResponseCode> CanBePrinted for T {}
+/// Implements [ResponseCode] and [Byteable] for an application enum by
+/// mapping each of its unit variants onto a [Response], and allows
+/// converting it into a [ResponseBuilder] to add headers and a body.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{impl_response_code, Byteable, ResponseCode};
+/// # use heggemann_http::response::{Incomplete, ResponseBuilder};
+/// enum ApiError {
+///     Missing,
+///     Throttled,
+/// }
+/// impl_response_code! {
+///     ApiError {
+///         Missing => NotFound,
+///         Throttled => TooManyRequests,
+///     }
+/// }
+/// assert_eq!(ApiError::Throttled.code(), 429);
+/// assert_eq!(ApiError::Missing.into_bytes(), b"HTTP/1.0 404 Not Found\r\n\r\n");
+/// let builder: ResponseBuilder<Incomplete> = ApiError::Missing.into();
+/// ```
+#[macro_export]
+macro_rules! impl_response_code {
+    ($name:ident { $($variant:ident => $response:ident),+ $(,)? }) => {
+        impl ::core::convert::From<&$name> for $crate::Response {
+            fn from(value: &$name) -> Self {
+                match value {
+                    $($name::$variant => $crate::Response::$response,)+
+                }
+            }
+        }
+        impl $crate::ResponseCode for $name {
+            fn code(&self) -> u16 {
+                $crate::ResponseCode::code(&$crate::Response::from(self))
+            }
+        }
+        impl $crate::Byteable for $name {
+            fn into_bytes(self) -> ::std::vec::Vec<u8> {
+                $crate::Byteable::into_bytes($crate::Response::from(&self))
+            }
+            fn max_version(&self) -> $crate::Version {
+                $crate::Byteable::max_version(&$crate::Response::from(self))
+            }
+        }
+        impl ::core::convert::From<$name> for $crate::response::ResponseBuilder<$crate::response::Incomplete> {
+            fn from(value: $name) -> Self {
+                $crate::Response::from(&value).into()
+            }
+        }
+    };
+}
 
-trait CanBePrinted: Byteable + ResponseCode {
-    fn response_header(&self) -> String {
-        format!("HTTP/{}.{} {} {}", 
-            self.max_version().0,
-            self.max_version().1,
-            self.code(),
-            self.standard_phrase())
+pub trait Byteable {
+    fn into_bytes(self) -> Vec<u8>;
+    fn max_version(&self) -> Version;
+    /// Serializes without consuming, producing the same bytes as
+    /// [into_bytes][Byteable::into_bytes].
+    fn to_bytes(&self) -> Vec<u8> where Self: Clone {
+        self.clone().into_bytes()
     }
 }
 
@@ -53,7 +175,7 @@ trait CanBePrinted: Byteable + ResponseCode {
 /// dbg!(res.max_version());
 /// assert_eq!(res.to_string(),
 ///     "HTTP/1.1 200 OK\r\n\
-///     host:github.com:80\r\n\r\n\
+///     host: github.com:80\r\n\r\n\
 ///     this is some body");
 /// # Ok(())
 /// # }
@@ -323,9 +445,99 @@ pub enum Response{
     /// The client needs to authenticate to gain network access. Intended for use by 
     /// intercepting proxies used to control access to the network
     NetworkAuthenticationRequired = 511,
+
+    /// ## 444 NO RESPONSE
+    /// *Unofficial, nginx.* Logged when the server closed the connection
+    /// without sending any response.
+    #[cfg(feature = "unofficial-codes")]
+    NoResponse = 444,
+    /// ## 499 CLIENT CLOSED REQUEST
+    /// *Unofficial, nginx.* Logged when the client closed the connection
+    /// before the server could respond.
+    #[cfg(feature = "unofficial-codes")]
+    ClientClosedRequest = 499,
+    /// ## 520 WEB SERVER RETURNED AN UNKNOWN ERROR
+    /// *Unofficial, Cloudflare.* The origin server returned an empty,
+    /// unknown or unexpected response.
+    #[cfg(feature = "unofficial-codes")]
+    WebServerUnknownError = 520,
+    /// ## 521 WEB SERVER IS DOWN
+    /// *Unofficial, Cloudflare.* The origin server refused the connection.
+    #[cfg(feature = "unofficial-codes")]
+    WebServerIsDown = 521,
+    /// ## 522 CONNECTION TIMED OUT
+    /// *Unofficial, Cloudflare.* The TCP handshake with the origin server timed out.
+    #[cfg(feature = "unofficial-codes")]
+    ConnectionTimedOut = 522,
+    /// ## 523 ORIGIN IS UNREACHABLE
+    /// *Unofficial, Cloudflare.* The origin server could not be reached.
+    #[cfg(feature = "unofficial-codes")]
+    OriginIsUnreachable = 523,
+    /// ## 524 A TIMEOUT OCCURRED
+    /// *Unofficial, Cloudflare.* The connection to the origin server was made,
+    /// but it did not respond in time.
+    #[cfg(feature = "unofficial-codes")]
+    TimeoutOccurred = 524,
+    /// ## 525 SSL HANDSHAKE FAILED
+    /// *Unofficial, Cloudflare.* The TLS handshake with the origin server failed.
+    #[cfg(feature = "unofficial-codes")]
+    SslHandshakeFailed = 525,
+    /// ## 526 INVALID SSL CERTIFICATE
+    /// *Unofficial, Cloudflare.* The origin server's certificate could not be validated.
+    #[cfg(feature = "unofficial-codes")]
+    InvalidSslCertificate = 526,
 }
 
+#[allow(deprecated)]
+const STANDARD_CODES: &[Response] = &[
+    Response::Continue, Response::SwitchingProtocols, Response::Processing,
+    Response::EarlyHints, Response::Ok, Response::Created, Response::Accepted,
+    Response::NonAuthoritativeInformation, Response::NoContent, Response::ResetContent,
+    Response::PartialContent, Response::MultiStatus, Response::AlreadyReported,
+    Response::ImUsed, Response::MultipleChoices, Response::MovedPermanently, Response::Found,
+    Response::SeeOther, Response::NotModified, Response::UseProxy, Response::SwitchProxy,
+    Response::TemporaryRedirect, Response::PermanentRedirect, Response::BadRequest,
+    Response::Unauthorized, Response::PaymentRequired, Response::Forbidden, Response::NotFound,
+    Response::MethodNotAllowed, Response::NotAcceptable, Response::ProxyAuthenticationRequired,
+    Response::RequestTimeout, Response::Conflict, Response::Gone, Response::LengthRequired,
+    Response::PreconditonFailed, Response::PayloadTooLarge, Response::UriTooLong,
+    Response::UnsupportedMediaType, Response::RangeNotSatisfiable, Response::ExpectationFailed,
+    Response::ImATeapot, Response::MisdirectedRequest, Response::UnprocessableEntity,
+    Response::Locked, Response::FailedDependency, Response::TooEarly, Response::UpgradeRequired,
+    Response::PreconditionRequired, Response::TooManyRequests,
+    Response::RequestHeaderFieldsTooLarge, Response::UnavailableForLegalReasons,
+    Response::ServerError, Response::NotImplemented, Response::BadGateway,
+    Response::ServiceUnavailable, Response::GatewayTimeout, Response::HttpVersionNotSupported,
+    Response::VariantAlsoNegotiates, Response::InsufficientStorage, Response::LoopDetected,
+    Response::NotExtended, Response::NetworkAuthenticationRequired,
+];
+#[cfg(feature = "unofficial-codes")]
+const UNOFFICIAL_CODES: &[Response] = &[
+    Response::NoResponse, Response::ClientClosedRequest, Response::WebServerUnknownError,
+    Response::WebServerIsDown, Response::ConnectionTimedOut, Response::OriginIsUnreachable,
+    Response::TimeoutOccurred, Response::SslHandshakeFailed, Response::InvalidSslCertificate,
+];
+
 impl Response {
+    /// Every known status code in ascending order, followed by the
+    /// unofficial ones if the `unofficial-codes` feature is enabled.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// assert!(Response::iter().any(|r| r == Response::ImATeapot));
+    /// assert!(Response::iter().all(|r| (100..600).contains(&r.code_u16())));
+    /// ```
+    pub fn iter() -> impl Iterator<Item = Response> {
+        let codes = STANDARD_CODES.iter();
+        #[cfg(feature = "unofficial-codes")]
+        let codes = codes.chain(UNOFFICIAL_CODES);
+        codes.cloned()
+    }
+    /// The numeric status code, without having to import [ResponseCode].
+    pub fn code_u16(&self) -> u16 {
+        self.code()
+    }
     pub fn new(code: u16) -> Result<Self, InvalidCode> {
         Response::try_from(code)
     }
@@ -334,18 +546,185 @@ impl Response {
             response: self,
             marker: PhantomData,
             body: body.into(),
-            headers: HashMap::new(),
+            headers: HeaderMap::new(),
+            version: None,
+            phrase_style: PhraseStyle::default(),
+            extensions: Extensions::new(),
         }
     }
+    /// See [ResponseBuilder::with_body].
+    pub fn with_body<B: AsRef<[u8]>>(self, body: B) -> ResponseBuilder<Complete, B> {
+        ResponseBuilder::new(self).with_body(body)
+    }
     pub fn header<K: AsRef<str>, V: AsRef<str>>(self, k: K, v: V) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
-        let (k, v) = (k.as_ref(), v.as_ref());
-        let headers = HashMap::from([(Key::new(k)?, Value::new(v)?)]);
-        Ok(ResponseBuilder {
-            response: self,
-            marker: PhantomData,
-            body: vec![],
-            headers
-        })
+        ResponseBuilder::new(self).header(k, v)
+    }
+    /// See [ResponseBuilder::headers].
+    pub fn headers<I, K, V>(self, headers: I) -> Result<ResponseBuilder<Incomplete>, HeaderError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        ResponseBuilder::new(self).headers(headers)
+    }
+    /// See [ResponseBuilder::attachment].
+    pub fn attachment<S: AsRef<str>>(self, filename: S) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(self).attachment(filename)
+    }
+    /// A [103][Response::EarlyHints] interim response announcing `links`
+    /// in a single comma-separated `link` header, failing if that header
+    /// would be empty or longer than [MAX_VALUE_LEN][crate::header::MAX_VALUE_LEN].
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Response, header::Link};
+    /// let css = Link::new("/style.css").unwrap().rel("preload").unwrap().param("as", "style").unwrap();
+    /// assert_eq!(Response::early_hints(&[css]).unwrap().to_string(),
+    ///     "HTTP/1.0 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style\r\n\r\n");
+    /// ```
+    pub fn early_hints(links: &[Link]) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let mut builder = ResponseBuilder::new(Response::EarlyHints);
+        let value = links.iter().map(Link::to_string).collect::<Vec<_>>().join(", ");
+        let value = Value::new(value).map_err(|error| HeaderError::Value { key: Some(Key::LINK), error })?;
+        builder.headers.insert(Key::LINK, value);
+        Ok(builder)
+    }
+    /// A [401][Response::Unauthorized] carrying the given challenge.
+    ///
+    /// Further challenges can be added with [ResponseBuilder::www_authenticate].
+    pub fn unauthorized(challenge: &Challenge) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(Response::Unauthorized).www_authenticate(challenge)
+    }
+    /// A [429][Response::TooManyRequests] asking the client to wait `retry`,
+    /// rounded up to whole seconds and at least one.
+    pub fn too_many_requests(retry: Duration) -> ResponseBuilder<Incomplete> {
+        ResponseBuilder::new(Response::TooManyRequests).retry_after_secs(retry_secs(retry))
+    }
+    /// A [503][Response::ServiceUnavailable] asking the client to wait `retry`,
+    /// rounded up to whole seconds and at least one.
+    pub fn service_unavailable(retry: Duration) -> ResponseBuilder<Incomplete> {
+        ResponseBuilder::new(Response::ServiceUnavailable).retry_after_secs(retry_secs(retry))
+    }
+    /// A [405][Response::MethodNotAllowed] listing the `allowed` methods.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Response, RequestMethod};
+    /// let response = Response::method_not_allowed(&[RequestMethod::Get, RequestMethod::Head]).unwrap();
+    /// assert_eq!(response.to_string(), "HTTP/1.0 405 Method Not Allowed\r\nallow: GET, HEAD\r\n\r\n");
+    /// ```
+    pub fn method_not_allowed(allowed: &[RequestMethod]) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(Response::MethodNotAllowed).allow(allowed)
+    }
+    /// A [426][Response::UpgradeRequired] naming the `protocols` the
+    /// client has to switch to, each `name` or `name/version`, in `upgrade`
+    /// with `connection: upgrade` as RFC 9110 requires. Fails if a
+    /// protocol is malformed or there is none.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// let response = Response::upgrade_required(&["TLS/1.2", "websocket"]).unwrap();
+    /// assert_eq!(response.to_string(),
+    ///     "HTTP/1.0 426 Upgrade Required\r\nupgrade: TLS/1.2, websocket\r\nconnection: Upgrade\r\n\r\n");
+    /// ```
+    pub fn upgrade_required(protocols: &[&str]) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(Response::UpgradeRequired).upgrade(protocols)
+    }
+    /// A [101][Response::SwitchingProtocols] switching to `protocol`, with
+    /// the same `upgrade` and `connection` headers as
+    /// [upgrade_required][Response::upgrade_required].
+    pub fn switching_protocols(protocol: &str) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(Response::SwitchingProtocols).upgrade(&[protocol])
+    }
+    /// Answers a range request for `body`.
+    ///
+    /// Produces a [206][Response::PartialContent] with the selected slice and
+    /// matching `content-range` and `content-length` headers, or a
+    /// [416][Response::RangeNotSatisfiable] with `content-range: bytes */len`
+    /// if `range` selects nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Response, header::ByteRange};
+    /// let response = Response::partial_content(b"0123456789", &ByteRange::FromTo(2, 4));
+    /// assert_eq!(response.to_string(),
+    ///     "HTTP/1.0 206 Partial Content\r\n\
+    ///     content-range: bytes 2-4/10\r\n\
+    ///     content-length: 3\r\n\r\n\
+    ///     234");
+    /// ```
+    pub fn partial_content(body: &[u8], range: &ByteRange) -> ResponseBuilder<Complete> {
+        let len = body.len() as u64;
+        match range.try_resolve(len) {
+            Ok(range) => {
+                let (first, last) = (*range.start(), *range.end());
+                let slice = &body[first as usize..=last as usize];
+                ResponseBuilder::new(Response::PartialContent)
+                    .content_range(ContentRange::Bytes { first, last, complete_length: Some(len) })
+                    .unwrap()
+                    .content_length(slice.len())
+                    .body(slice)
+            },
+            Err(unsatisfiable) => ResponseBuilder::from(unsatisfiable).body(""),
+        }
+    }
+    /// A [416][Response::RangeNotSatisfiable] telling the client the
+    /// actual length through `content-range: bytes */<complete_length>`.
+    ///
+    /// An explanatory body can still be added with
+    /// [body][ResponseBuilder::body].
+    pub fn range_not_satisfiable(complete_length: u64) -> ResponseBuilder<Incomplete> {
+        ResponseBuilder::new(Response::RangeNotSatisfiable)
+            .content_range(ContentRange::Unsatisfied { complete_length })
+            .unwrap()
+    }
+    /// Starts a builder whose status line uses the given [PhraseStyle].
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Byteable, Response, response::PhraseStyle};
+    /// let legacy = Response::NotFound.phrase_style(PhraseStyle::UpperLegacy);
+    /// assert_eq!(legacy.into_bytes(), b"HTTP/1.0 404 NOT FOUND\r\n\r\n");
+    /// ```
+    pub fn phrase_style(self, style: PhraseStyle) -> ResponseBuilder<Incomplete> {
+        ResponseBuilder::new(self).phrase_style(style)
+    }
+    /// Starts a response answering `request`.
+    ///
+    /// The response version is [negotiated][Version::negotiate] against
+    /// HTTP/1.1; requests with another major version are answered in 1.1.
+    /// The response carries a `connection` header whenever the client's
+    /// keep-alive wish differs from that version's default.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Request, Response};
+    /// let request: Request = "GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n".parse().unwrap();
+    /// let response = Response::Ok.for_request(&request);
+    /// assert_eq!(response.to_string(),
+    ///     "HTTP/1.0 200 OK\r\n\
+    ///     connection: keep-alive\r\n\r\n");
+    /// ```
+    pub fn for_request(self, request: &Request) -> ResponseBuilder<Incomplete> {
+        let version = Version::negotiate(&request.version, &Version::HTTP_11).unwrap_or(Version::HTTP_11);
+        let mut builder = ResponseBuilder::new(self);
+        builder.version = Some(version);
+        builder.connection(request.keep_alive(), &version)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Response {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.code())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Response {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Response::try_from(u16::deserialize(deserializer)?).map_err(serde::de::Error::custom)
     }
 }
 
@@ -357,11 +736,14 @@ impl ResponseCode for Response {
 
 impl Byteable for Response {
     fn into_bytes(self) -> Vec<u8> {
-        String::from(self).into()
+        self.to_bytes()
     }
     fn max_version(&self) -> Version {
         Version (1, 0)
     }
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
 }
 
 impl From<Response> for String {
@@ -372,7 +754,7 @@ impl From<Response> for String {
 
 impl Display for Response {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}\r\n\r\n", self.response_header())
+        write!(f, "{}\r\n\r\n", self.first_line(self.max_version()))
     }
 }
 
@@ -454,11 +836,58 @@ impl TryFrom<u16> for Response {
             508 => Ok(Self::LoopDetected),
             510 => Ok(Self::NotExtended),
             511 => Ok(Self::NetworkAuthenticationRequired),
+
+            #[cfg(feature = "unofficial-codes")]
+            444 => Ok(Self::NoResponse),
+            #[cfg(feature = "unofficial-codes")]
+            499 => Ok(Self::ClientClosedRequest),
+            #[cfg(feature = "unofficial-codes")]
+            520 => Ok(Self::WebServerUnknownError),
+            #[cfg(feature = "unofficial-codes")]
+            521 => Ok(Self::WebServerIsDown),
+            #[cfg(feature = "unofficial-codes")]
+            522 => Ok(Self::ConnectionTimedOut),
+            #[cfg(feature = "unofficial-codes")]
+            523 => Ok(Self::OriginIsUnreachable),
+            #[cfg(feature = "unofficial-codes")]
+            524 => Ok(Self::TimeoutOccurred),
+            #[cfg(feature = "unofficial-codes")]
+            525 => Ok(Self::SslHandshakeFailed),
+            #[cfg(feature = "unofficial-codes")]
+            526 => Ok(Self::InvalidSslCertificate),
             _ => Err(InvalidCode)
         }
     }
 }
 
+/// Reads a `retry-after` value, given either as delta-seconds or as an
+/// HTTP-date, as the time to wait from `now`.
+///
+/// Dates in the past result in a zero duration.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok().map(Duration::from_secs);
+    }
+    let date = crate::header::date::parse(value).ok()?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseError {
+    /// The `content-length` header disagrees with the length of the body
+    ContentLengthMismatch { declared: u64, actual: usize },
+}
+impl Error for ResponseError {}
+impl Display for ResponseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::ContentLengthMismatch { declared, actual } => write!(f,
+                "content-length declares {declared} bytes, but the body has {actual}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct InvalidCode;
 impl Error for InvalidCode {}
@@ -469,20 +898,176 @@ impl Display for InvalidCode {
 }
 
 #[derive(PartialEq, Debug, Clone)]
-pub struct ResponseBuilder<S: State> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "B: serde::Serialize",
+    deserialize = "B: serde::Deserialize<'de>",
+)))]
+/// A response under construction, see [Response] for examples.
+///
+/// The body is usually an owned `Vec<u8>`. Any other `B: AsRef<[u8]>`,
+/// such as `&'static [u8]` or `Arc<[u8]>`, can be set with
+/// [with_body][ResponseBuilder::with_body] to avoid copying it before
+/// serialization.
+pub struct ResponseBuilder<S: State, B = Vec<u8>> {
     response: Response,
+    #[cfg_attr(feature = "serde", serde(skip))]
     marker: std::marker::PhantomData<S>,
-    body: Vec<u8>,
-    headers: HashMap<Key, Value>,
+    body: B,
+    headers: HeaderMap,
+    version: Option<Version>,
+    phrase_style: PhraseStyle,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    extensions: Extensions,
+}
+
+impl<S: State, B: AsRef<[u8]>> ResponseBuilder<S, B> {
+    /// Looks up the value of a header, ignoring the case of `k`.
+    pub fn get_header<K: AsRef<str>>(&self, k: K) -> Option<&Value> {
+        self.headers.get(k)
+    }
+    /// All headers in the order they were first set.
+    pub fn header_map(&self) -> &HeaderMap {
+        &self.headers
+    }
+    /// All values of a header, one per field line. Only `set-cookie`
+    /// can have more than one.
+    pub fn get_all_headers<'a>(&'a self, k: &'a str) -> impl Iterator<Item = &'a Value> {
+        self.headers.get_all(k)
+    }
+    /// The header names listed in `vary`, which a cache has to pass to
+    /// [Request::cache_key]. `None` if the response varies on `*`, meaning
+    /// a stored copy never matches a later request.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// let response = Response::Ok.header("Vary", "Accept-Encoding, Origin").unwrap();
+    /// let vary = response.vary_keys().unwrap();
+    /// assert_eq!(vary.iter().map(|k| k.as_str()).collect::<Vec<_>>(), ["accept-encoding", "origin"]);
+    /// ```
+    pub fn vary_keys(&self) -> Option<Vec<Key>> {
+        let mut keys = vec![];
+        for name in self.headers.get_all("vary").flat_map(Value::elements) {
+            if name == "*" {
+                return None;
+            }
+            if let Ok(key) = Key::new(name) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        Some(keys)
+    }
+    pub fn get_status(&self) -> &Response {
+        &self.response
+    }
+    pub fn get_body(&self) -> &[u8] {
+        self.body.as_ref()
+    }
+    /// Length of the body in bytes.
+    pub fn body_len(&self) -> usize {
+        self.body.as_ref().len()
+    }
+    /// Data attached by the application, see [Extensions].
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+    /// Sets the HTTP version of the status line explicitly, instead of
+    /// deriving it from the headers.
+    ///
+    /// Targeting HTTP/1.0 enables downgrade rules: a `transfer-encoding:
+    /// chunked` response is sent close-delimited, without `transfer-encoding`
+    /// and `content-length` (see [closes_connection][Self::closes_connection]),
+    /// and interim responses are refused by [ResponseSequence].
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+    /// The explicitly set [version][Self::version], if any.
+    pub fn get_version(&self) -> Option<Version> {
+        self.version
+    }
+    /// Removes the hop-by-hop fields before the response is forwarded, see
+    /// [HeaderMap::strip_hop_by_hop].
+    pub fn strip_hop_by_hop(&mut self) -> Vec<Key> {
+        self.headers.strip_hop_by_hop()
+    }
+    pub(crate) fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+    /// A copy borrowing the body, to adjust the head without copying it.
+    pub(crate) fn borrow_body(&self) -> ResponseBuilder<S, &[u8]> {
+        ResponseBuilder {
+            response: self.response.clone(),
+            marker: std::marker::PhantomData,
+            body: self.body.as_ref(),
+            headers: self.headers.clone(),
+            version: self.version,
+            phrase_style: self.phrase_style,
+            extensions: Extensions::new(),
+        }
+    }
+    /// See [connection][ResponseBuilder::connection].
+    pub(crate) fn set_connection(&mut self, keep_alive: bool, version: &Version) {
+        self.headers.remove("connection");
+        let default = version.supports_persistent_by_default();
+        if keep_alive != default {
+            let v = if keep_alive { "keep-alive" } else { "close" };
+            self.headers.insert(Key::CONNECTION, Value::new(v).unwrap());
+        }
+    }
+    /// Replaces the status code, keeping headers, body and version.
+    ///
+    /// The body is kept as is, even when switching to a code that must
+    /// not carry one ([1xx][Response::Continue], [204][Response::NoContent]
+    /// and [304][Response::NotModified]). Clear it by building anew in that case.
+    pub fn with_status(mut self, code: Response) -> Self {
+        self.response = code;
+        self
+    }
+    /// Chooses how the reason phrase of the status line is written.
+    pub fn phrase_style(mut self, style: PhraseStyle) -> Self {
+        self.phrase_style = style;
+        self
+    }
 }
 
-impl<S: State> ResponseCode for ResponseBuilder<S> {
+impl<S: State, B> ResponseCode for ResponseBuilder<S, B> {
     fn code(&self) -> u16 {
         self.response.code()
     }
 }
 
 impl ResponseBuilder<Incomplete> {
+    pub(crate) fn new(response: Response) -> Self {
+        ResponseBuilder {
+            response,
+            marker: PhantomData,
+            body: vec![],
+            headers: HeaderMap::new(),
+            version: None,
+            phrase_style: PhraseStyle::default(),
+            extensions: Extensions::new(),
+        }
+    }
+    /// Sets a body of any byte container, which is kept as is until
+    /// serialization instead of being copied into a `Vec<u8>`.
+    pub fn with_body<B: AsRef<[u8]>>(self, body: B) -> ResponseBuilder<Complete, B> {
+        ResponseBuilder {
+            response: self.response,
+            marker: PhantomData,
+            body,
+            headers: self.headers,
+            version: self.version,
+            phrase_style: self.phrase_style,
+            extensions: self.extensions,
+        }
+    }
     pub fn body<B: Into<Vec<u8>>>(self , body: B) -> ResponseBuilder<Complete> {
         let body = body.into();
         ResponseBuilder {
@@ -490,70 +1075,431 @@ impl ResponseBuilder<Incomplete> {
             marker: PhantomData,
             body,
             headers: self.headers,
+            version: self.version,
+            phrase_style: self.phrase_style,
+            extensions: self.extensions,
         }
     }
+    /// Adds a header, appending to the values of an existing key.
+    ///
+    /// [Singleton][Key::is_singleton] fields like `content-type` replace
+    /// any earlier value instead. A `content-length` also has to be a
+    /// decimal number.
     pub fn header<K: AsRef<str>, V: AsRef<str>>(mut self, k: K, v: V) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
         let k = Key::new(k.as_ref())?;
-        match self.headers.entry(k) {
-            Entry::Occupied(mut e) => {e.get_mut().append(v.as_ref())?;},
-            Entry::Vacant(e) => {e.insert(Value::new(v.as_ref())?);},
+        if k == "content-length" {
+            let v = Value::new(v.as_ref()).ok()
+                .filter(|v| v.as_u64().is_some())
+                .ok_or_else(|| HeaderError::from(ValueError::invalid_content_length(v.as_ref())).for_key(&k))?;
+            self.headers.insert(k, v);
+            return Ok(self);
+        }
+        let v = Value::new(v).map_err(|e| HeaderError::from(e).for_key(&k))?;
+        if k.is_singleton() {
+            self.headers.insert(k, v);
+        } else {
+            self.headers.append_value(k.clone(), v).map_err(|e| HeaderError::from(e).for_key(&k))?;
+        }
+        Ok(self)
+    }
+    /// Adds the members of `v` to the list of `k` that it does not contain
+    /// yet, ignoring case, so that repeated layers adding e.g. `vary:
+    /// origin` do not list it twice.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// let response = Response::Ok.header("Vary", "Origin").unwrap()
+    ///     .header_unique("vary", "origin, accept-encoding").unwrap()
+    ///     .body("");
+    /// assert_eq!(response.get_header("vary").unwrap(), "Origin,accept-encoding");
+    /// ```
+    pub fn header_unique<K: AsRef<str>, V: AsRef<str>>(mut self, k: K, v: V) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let k = Key::new(k.as_ref())?;
+        self.headers.append_unique(k.clone(), v).map_err(|e| HeaderError::from(e).for_key(&k))?;
+        Ok(self)
+    }
+    /// Adds the header like [header][Self::header] if `v` is `Some`,
+    /// otherwise leaves the builder unchanged.
+    pub fn header_opt<K: AsRef<str>, V: AsRef<str>>(self, k: K, v: Option<V>) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        match v {
+            Some(v) => self.header(k, v),
+            None => Ok(self),
+        }
+    }
+    /// Adds the header like [header][Self::header] only if `cond` holds.
+    ///
+    /// The pair is validated only when it is added.
+    pub fn header_if<K: AsRef<str>, V: AsRef<str>>(self, cond: bool, k: K, v: V) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        if cond {
+            self.header(k, v)
+        } else {
+            Ok(self)
+        }
+    }
+    /// Adds all `headers` in order, like repeated calls to [header][Self::header].
+    ///
+    /// Stops at the first invalid pair, reporting its position with
+    /// [HeaderError::AtIndex].
+    pub fn headers<I, K, V>(mut self, headers: I) -> Result<ResponseBuilder<Incomplete>, HeaderError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (i, (k, v)) in headers.into_iter().enumerate() {
+            self = self.header(k, v).map_err(|e| HeaderError::AtIndex(i, Box::new(e)))?;
+        }
+        Ok(self)
+    }
+    /// Adds a challenge to the `www-authenticate` header.
+    pub fn www_authenticate(mut self, challenge: &Challenge) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        self.headers.append(Key::WWW_AUTHENTICATE, challenge.to_string())?;
+        Ok(self)
+    }
+    /// Sets `retry-after` to a number of seconds, replacing any previous value.
+    pub fn retry_after_secs(mut self, secs: u64) -> ResponseBuilder<Incomplete> {
+        self.headers.insert(Key::RETRY_AFTER, Value::new(secs.to_string()).unwrap());
+        self
+    }
+    /// Sets `retry-after` to an HTTP-date, replacing any previous value.
+    pub fn retry_after_date(mut self, time: SystemTime) -> ResponseBuilder<Incomplete> {
+        let date = crate::header::date::format(time);
+        self.headers.insert(Key::RETRY_AFTER, Value::new(date).unwrap());
+        self
+    }
+    /// Sets the `allow` header to the given methods, dropping duplicates.
+    ///
+    /// Used by [405][Response::MethodNotAllowed] responses as well as for
+    /// answering [OPTIONS][RequestMethod::Options] requests. Fails if
+    /// `methods` is empty.
+    pub fn allow(mut self, methods: &[RequestMethod]) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let mut unique: Vec<RequestMethod> = Vec::with_capacity(methods.len());
+        for m in methods {
+            if !unique.contains(m) {
+                unique.push(*m);
+            }
         }
+        if unique.is_empty() {
+            return Err(ValueError::EmptyString.into());
+        }
+        let value = unique.iter().map(RequestMethod::as_str).collect::<Vec<_>>().join(", ");
+        self.headers.insert(Key::ALLOW, Value::new(value)?);
         Ok(self)
     }
+    fn upgrade(mut self, protocols: &[&str]) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let protocols = protocols.iter()
+            .map(|p| p.parse::<Protocol>().map_err(|e| HeaderError::from(e).for_key(&Key::UPGRADE)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if protocols.is_empty() {
+            return Err(HeaderError::from(ValueError::EmptyString).for_key(&Key::UPGRADE));
+        }
+        let value = protocols.iter().map(Protocol::to_string).collect::<Vec<_>>().join(", ");
+        let value = Value::new(value).map_err(|e| HeaderError::from(e).for_key(&Key::UPGRADE))?;
+        self.headers.insert(Key::UPGRADE, value);
+        self.headers.insert(Key::CONNECTION, Value::new("Upgrade").unwrap());
+        Ok(self)
+    }
+    /// Sets a structured header, replacing any previous value of that field.
+    ///
+    /// Fails if the serialized value is longer than
+    /// [MAX_VALUE_LEN][crate::header::MAX_VALUE_LEN].
+    pub fn typed_header<H: ToHeader>(mut self, h: H) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let k = h.name();
+        let v = h.value().map_err(|e| HeaderError::from(e).for_key(&k))?;
+        self.headers.insert(k, v);
+        Ok(self)
+    }
+    /// Sets the `content-range` header, replacing any previous one.
+    pub fn content_range(self, range: ContentRange) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        self.typed_header(range)
+    }
+    /// Sets the `content-length` header, replacing any previous one.
+    pub fn content_length(mut self, len: usize) -> ResponseBuilder<Incomplete> {
+        self.headers.insert(Key::CONTENT_LENGTH, Value::new(len.to_string()).unwrap());
+        self
+    }
+    /// Adds a cookie. Every cookie is written on its own `set-cookie` line,
+    /// and unlike parsed input the number of lines is not limited.
+    ///
+    /// Fails like [typed_header][Self::typed_header] if the line is too long.
+    pub fn set_cookie(mut self, cookie: SetCookie) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        let v = ToHeader::value(&cookie).map_err(|e| HeaderError::from(e).for_key(&Key::SET_COOKIE))?;
+        let limits = ValueLimits { elements: usize::MAX, ..ValueLimits::default() };
+        self.headers.append_value_with_limits(Key::SET_COOKIE, v, limits)
+            .map_err(|e| HeaderError::from(e).for_key(&Key::SET_COOKIE))?;
+        Ok(self)
+    }
+    /// Marks the body as a download named `filename`, replacing any
+    /// previous `content-disposition`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// let download = Response::Ok.attachment("report.pdf").unwrap().body("%PDF");
+    /// assert_eq!(download.get_header("content-disposition").unwrap(), "attachment; filename=report.pdf");
+    /// ```
+    pub fn attachment<S: AsRef<str>>(self, filename: S) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        self.typed_header(ContentDisposition::attachment().filename(filename))
+    }
+    /// Sets the `etag` header, replacing any previous one.
+    pub fn etag(self, tag: EntityTag) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        self.typed_header(tag)
+    }
+    /// Sets the `connection` header for the given keep-alive disposition.
+    ///
+    /// HTTP/1.1 keeps connections alive by default and HTTP/1.0 closes them,
+    /// so the header is only written when `keep_alive` differs from the
+    /// default of `version`. Any previous `connection` value is replaced.
+    pub fn connection(mut self, keep_alive: bool, version: &Version) -> ResponseBuilder<Incomplete> {
+        self.set_connection(keep_alive, version);
+        self
+    }
+}
+
+impl<B: AsRef<[u8]>> ResponseBuilder<Complete, B> {
+    /// Takes the response apart into status, headers in their order of
+    /// insertion and body, e.g. to rewrite the body outside the crate.
+    ///
+    /// An explicitly set [version][ResponseBuilder::version] and
+    /// [phrase_style][ResponseBuilder::phrase_style] are not part of the
+    /// parts and have to be set again after [from_parts][Self::from_parts].
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Byteable, Response, response::ResponseBuilder};
+    /// let response = Response::Ok.header("content-type", "text/html").unwrap().body("<p>hi</p>");
+    /// let (status, headers, body) = response.into_parts();
+    /// let body = String::from_utf8(body).unwrap().replace("hi", "hello");
+    /// let rewritten = ResponseBuilder::from_parts(status, headers, body.into_bytes());
+    /// assert!(rewritten.to_string().ends_with("<p>hello</p>"));
+    /// ```
+    pub fn into_parts(self) -> (Response, HeaderMap, B) {
+        (self.response, self.headers, self.body)
+    }
+    /// Reassembles a response from [into_parts][Self::into_parts].
+    pub fn from_parts(status: Response, headers: HeaderMap, body: B) -> Self {
+        ResponseBuilder {
+            response: status,
+            marker: PhantomData,
+            body,
+            headers,
+            version: None,
+            phrase_style: PhraseStyle::default(),
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl ResponseBuilder<Complete> {
+    /// Extends the body by `b`.
+    ///
+    /// A `content-length` header that was already set is kept in sync
+    /// with the new length.
+    pub fn append_body<B: Into<Vec<u8>>>(&mut self, b: B) {
+        self.body.extend(b.into());
+        if self.headers.contains_key("content-length") {
+            self.headers.insert(
+                Key::CONTENT_LENGTH,
+                Value::new(self.body.len().to_string()).unwrap(),
+            );
+        }
+    }
+    /// Chaining variant of [append_body][ResponseBuilder::append_body].
+    pub fn and_body<B: Into<Vec<u8>>>(mut self, b: B) -> Self {
+        self.append_body(b);
+        self
+    }
+}
+
+impl<S: State, B: AsRef<[u8]>> ResponseBuilder<S, B> {
+    /// Status line and header section, including the blank line
+    /// separating them from the body.
+    fn head(&self) -> String {
+        let status = status_line(&self.response, self.max_version(), self.phrase_style);
+        let mut head = Vec::with_capacity(self.head_len(&status));
+        self.write_head(&status, &mut head);
+        // Status line, keys and values are all ascii
+        String::from_utf8(head).unwrap()
+    }
+    /// Headers as they go on the wire. Framing headers are left out
+    /// when the body is sent close-delimited.
+    fn wire_headers(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        let close_delimited = self.close_delimited();
+        self.headers.iter().filter(move |(k, _)| {
+            !close_delimited || !matches!(k.as_str(), "transfer-encoding" | "content-length")
+        })
+    }
+    /// A chunked response explicitly downgraded to HTTP/1.0 or older, which has
+    /// no chunked transfer coding, is delimited by closing the connection.
+    fn close_delimited(&self) -> bool {
+        self.version.is_some_and(|v| !v.supports_chunked()) && self.headers.get("transfer-encoding")
+            .is_some_and(|v| v.contains_token("chunked"))
+    }
+    /// Whether the connection has to be closed after sending this response.
+    ///
+    /// That is the case for close-delimited bodies, an explicit
+    /// `connection: close`, and HTTP/1.0 responses not announcing keep-alive.
+    pub fn closes_connection(&self) -> bool {
+        let has = |token| self.headers.get("connection").is_some_and(|v| v.contains_token(token));
+        let version = self.max_version();
+        self.close_delimited()
+            || has("close")
+            || (!version.supports_persistent_by_default() && !has("keep-alive"))
+    }
+    /// Exact length of the head written by [write_head][Self::write_head].
+    fn head_len(&self, status: &StatusLineRef) -> usize {
+        status.len() + 2
+            + self.wire_headers()
+                .map(|(k, v)| k.as_str().len() + 2 + v.as_bytes().len() + 2)
+                .sum::<usize>()
+            + 2
+    }
+    fn write_head(&self, status: &StatusLineRef, out: &mut Vec<u8>) {
+        // Writing into a `Vec` cannot fail
+        write!(out, "{status}").unwrap();
+        out.extend_from_slice(b"\r\n");
+        for (k, v) in self.wire_headers() {
+            out.extend_from_slice(k.as_str().as_bytes());
+            out.extend_from_slice(b": ");
+            out.extend_from_slice(v.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+    /// Appends the whole message to `out`, reserving exactly its length
+    /// up front. Every byte form of the response goes through here.
+    fn serialize(&self, out: &mut Vec<u8>) {
+        debug_assert_eq!(self.check_content_length(), Ok(()));
+        let status = status_line(&self.response, self.max_version(), self.phrase_style);
+        let len = self.head_len(&status) + self.body.as_ref().len();
+        out.reserve_exact(len);
+        let start = out.len();
+        self.write_head(&status, out);
+        out.extend_from_slice(self.body.as_ref());
+        debug_assert_eq!(out.len() - start, len);
+    }
+    /// Checks an explicit `content-length` against the body.
+    ///
+    /// Responses without a body, such as answers to `HEAD`, and
+    /// [304][Response::NotModified] may declare the length of the
+    /// representation they stand in for, so they are not checked.
+    pub(crate) fn check_content_length(&self) -> Result<(), ResponseError> {
+        let actual = self.body.as_ref().len();
+        if actual == 0 || self.response == Response::NotModified || self.close_delimited() {
+            return Ok(());
+        }
+        let Some(declared) = self.headers.get("content-length").and_then(Value::as_u64) else {
+            return Ok(());
+        };
+        if declared != actual as u64 {
+            return Err(ResponseError::ContentLengthMismatch { declared, actual });
+        }
+        Ok(())
+    }
+    /// Serializes like [into_bytes][Byteable::into_bytes], but fails
+    /// instead of producing a message whose `content-length` is wrong.
+    /// `into_bytes` only checks this in debug builds, by panicking.
+    pub fn try_into_bytes(self) -> Result<Vec<u8>, ResponseError> {
+        self.check_content_length()?;
+        Ok(self.into_bytes())
+    }
+    /// Async variant of [write_to][Self::write_to].
+    #[cfg(feature = "tokio")]
+    pub async fn write_to_async<W: tokio::io::AsyncWrite + Unpin>(&self, w: &mut W) -> io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+        self.check_content_length().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut bytes = Vec::new();
+        self.serialize(&mut bytes);
+        w.write_all(&bytes).await
+    }
+    /// Writes only the status line and header section, as in answers to `HEAD`.
+    pub(crate) fn write_head_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(self.head().as_bytes())
+    }
+    /// Writes the message to `w` with a single [write_all][Write::write_all].
+    ///
+    /// A wrong `content-length` is reported as [InvalidData][io::ErrorKind::InvalidData]
+    /// wrapping a [ResponseError], without writing anything.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.check_content_length().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut bytes = Vec::new();
+        self.serialize(&mut bytes);
+        w.write_all(&bytes)
+    }
 }
 
-impl<S: State> Byteable for ResponseBuilder<S> {
+impl<S: State, B: AsRef<[u8]>> Byteable for ResponseBuilder<S, B> {
     fn into_bytes(self) -> Vec<u8> {
-        [
-            std::iter::once(
-                self.response_header()
-            ).chain(
-                self.headers
-                    .into_iter()
-                    .map(|(k, v)| format!("{k}:{v}"))
-            )
-            .collect::<Vec<String>>()
-            .join("\r\n").into_bytes(),
-            "\r\n\r\n".into(),
-            self.body,
-        ]
-        .concat()
+        let mut bytes = Vec::new();
+        self.serialize(&mut bytes);
+        bytes
     }
     fn max_version(&self) -> Version {
-        let k = Key::new("host").unwrap();
-        if self.headers.contains_key(&k) {
-            Version(1,1)
+        if let Some(version) = self.version {
+            return version;
+        }
+        if self.headers.contains_key("host") {
+            Version::HTTP_11
         } else {
-            Version(1,0)
+            Version::HTTP_10
         }
     }
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize(&mut bytes);
+        bytes
+    }
 }
 
-impl<S: State> TryFrom<ResponseBuilder<S>> for String {
+impl<S: State, B: AsRef<[u8]>> TryFrom<ResponseBuilder<S, B>> for String {
     type Error = FromUtf8Error;
-    fn try_from(value: ResponseBuilder<S>) -> Result<Self, Self::Error> {
+    fn try_from(value: ResponseBuilder<S, B>) -> Result<Self, Self::Error> {
         String::from_utf8(value.into_bytes())
     }
 }
 
-impl<S: State> From<ResponseBuilder<S>> for Vec<u8> {
-    fn from(value: ResponseBuilder<S>) -> Self {
+impl<S: State, B: AsRef<[u8]>> From<ResponseBuilder<S, B>> for Vec<u8> {
+    fn from(value: ResponseBuilder<S, B>) -> Self {
         value.into_bytes()
     }
 }
 
-impl<S: State> Display for ResponseBuilder<S> {
+/// Bodies longer than this are cut off when displayed.
+pub const DISPLAY_BODY_LIMIT: usize = 4096;
+
+/// Human-readable form of the response. Invalid UTF-8 in the body is
+/// replaced by `U+FFFD` and bodies above [DISPLAY_BODY_LIMIT] bytes are
+/// truncated, so use [into_bytes][Byteable::into_bytes] to get the exact message.
+impl<S: State, B: AsRef<[u8]>> Display for ResponseBuilder<S, B> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}\r\n\r\n{}",
-            std::iter::once(
-                self.response_header()
-            ).chain( 
-                self.headers.iter()
-                    .map(|(k, v)| format!("{k}:{v}"))
-            ).collect::<Vec<_>>().join("\r\n"),
-            String::from_utf8(self.body.clone()).unwrap_or_else(|_| {
-                format!("{:?}", self.body)
-            }))
+        write!(f, "{}", self.head())?;
+        let body = self.body.as_ref();
+        if body.len() > DISPLAY_BODY_LIMIT {
+            write!(f, "{}… ({} bytes total)",
+                String::from_utf8_lossy(&body[..DISPLAY_BODY_LIMIT]),
+                body.len())
+        } else {
+            write!(f, "{}", String::from_utf8_lossy(body))
+        }
+    }
+}
+
+impl From<Response> for ResponseBuilder<Incomplete> {
+    fn from(value: Response) -> Self {
+        ResponseBuilder::new(value)
+    }
+}
+
+impl From<UnsupportedVersion> for ResponseBuilder<Incomplete> {
+    fn from(_: UnsupportedVersion) -> Self {
+        Response::HttpVersionNotSupported.into()
+    }
+}
+
+impl From<Unsatisfiable> for ResponseBuilder<Incomplete> {
+    fn from(value: Unsatisfiable) -> Self {
+        Response::range_not_satisfiable(value.complete_length)
     }
 }
 
@@ -563,75 +1509,132 @@ impl From<ResponseBuilder<Incomplete>> for ResponseBuilder<Complete> {
     }
 }
 
+/// The reason phrase of a known status code in canonical RFC 9110 casing,
+/// e.g. `"Not Found"` or `"Internal Server Error"`.
 pub fn standard_phrase(code: u16) -> Option<&'static str> {
+    phrase(code, PhraseStyle::Canonical)
+}
+
+/// The reason phrase of a known status code in the given [PhraseStyle].
+pub fn phrase(code: u16, style: PhraseStyle) -> Option<&'static str> {
+    let (canonical, upper) = phrases(code)?;
+    Some(match style {
+        PhraseStyle::Canonical => canonical,
+        PhraseStyle::UpperLegacy => upper,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How the reason phrase in the status line is written.
+///
+/// The phrase carries no meaning for HTTP clients, but byte-exact
+/// comparisons downstream may depend on it.
+pub enum PhraseStyle {
+    /// Phrases as given by RFC 9110, e.g. `404 Not Found`.
+    #[default]
+    Canonical,
+    /// All-uppercase phrases as written by earlier versions of this crate,
+    /// e.g. `404 NOT FOUND`, reproduced byte for byte including their
+    /// quirks such as `202 Accepted` and `412 PRECONDITON FAILED`.
+    UpperLegacy,
+}
+
+/// `retry` in whole seconds for `retry-after`, rounded up so clients do
+/// not come back early, and at least one.
+fn retry_secs(retry: Duration) -> u64 {
+    let secs = retry.as_secs().saturating_add(u64::from(retry.subsec_nanos() > 0));
+    secs.max(1)
+}
+
+fn phrases(code: u16) -> Option<(&'static str, &'static str)> {
     match code {
-        100 => Some("CONTINUE"),
-        101 => Some("SWITCHING PROTOCOLS"),
-        102 => Some("PROCESSING"),
-        103 => Some("EARLY HINTS"),
-
-        200 => Some("OK"),
-        201 => Some("CREATED"),
-        202 => Some("Accepted"),
-        203 => Some("NON-AUTHORITATIVE INFORMATION"),
-        204 => Some("NO CONTENT"),
-        205 => Some("RESET CONTENT"),
-        206 => Some("PARTIAL CONTENT"),
-        207 => Some("MULTI-STATUS"),
-        208 => Some("ALREADY REPORTED"),
-        226 => Some("IM USED"),
-
-        300 => Some("MULTIPLE CHOICES"),
-        301 => Some("MOVED PERMANENTLY"),
-        302 => Some("FOUND"),
-        303 => Some("SEE OTHER"),
-        304 => Some("NOT MODIFIED"),
-        305 => Some("USE PROXY"),
-        306 => Some("SWITCH PROXY"),
-        307 => Some("TEMPORARY REDIRECT"),
-        308 => Some("PERMANENT REDIRECT"),
-
-        400 => Some("BAD REQUEST"),
-        401 => Some("UNAUTHORIZED"),
-        402 => Some("PAYMENT REQUIRED"),
-        403 => Some("FORBIDDEN"),
-        404 => Some("NOT FOUND"),
-        405 => Some("METHOD NOT ALLOWED"),
-        406 => Some("NOT ACCCEPTABLE"),
-        407 => Some("PROXY AUTHENTICATION REQUIRED"),
-        408 => Some("REQUEST TIMEOUT"),
-        409 => Some("CONFLICT"),
-        410 => Some("GONE"),
-        411 => Some("LENGTH REQUIRED"),
-        412 => Some("PRECONDITON FAILED"),
-        413 => Some("PAYLOAD TOO LARGE"),
-        414 => Some("URI TOO LONG"),
-        415 => Some("UNSUPPORTED MEDIA TYPE"),
-        416 => Some("RANGE NOT SATISFIABLE"),
-        417 => Some("EXPECTATION FAILED"),
-        418 => Some("IM A TEAPOT"),
-        421 => Some("MISDIRECTED REQUEST"),
-        422 => Some("UNPROCESSABLE ENTITY"),
-        423 => Some("LOCKED"),
-        424 => Some("FAILED DEPENDENCY"),
-        425 => Some("TOO EARLY"),
-        426 => Some("UPGRADE REQUIRED"),
-        428 => Some("PRECONDITION REQUIRED"),
-        429 => Some("TOO MANY REQUESTS"),
-        431 => Some("REQUEST HEADER FIELDS TOO LARGE"),
-        451 => Some("UNAVAILABLE FOR LEGAL REASONS"),
-
-        500 => Some("SERVER ERROR"),
-        501 => Some("NOT IMPLEMENTED"),
-        502 => Some("BAD GATEWAY"),
-        503 => Some("SERVICE UNAVAILABLE"),
-        504 => Some("GATEWAY TIMEOUT"),
-        505 => Some("HTTP VERSION NOT SUPPORTED"),
-        506 => Some("VARIANT ALSO NEGOTIATES"),
-        507 => Some("INSUFFICIENT STORAGE"),
-        508 => Some("LOOP DETECTED"),
-        510 => Some("NOT EXTENDED"),
-        511 => Some("NETWORK AUTHENTICATION REQUIRED"),
+        100 => Some(("Continue", "CONTINUE")),
+        101 => Some(("Switching Protocols", "SWITCHING PROTOCOLS")),
+        102 => Some(("Processing", "PROCESSING")),
+        103 => Some(("Early Hints", "EARLY HINTS")),
+
+        200 => Some(("OK", "OK")),
+        201 => Some(("Created", "CREATED")),
+        202 => Some(("Accepted", "Accepted")),
+        203 => Some(("Non-Authoritative Information", "NON-AUTHORITATIVE INFORMATION")),
+        204 => Some(("No Content", "NO CONTENT")),
+        205 => Some(("Reset Content", "RESET CONTENT")),
+        206 => Some(("Partial Content", "PARTIAL CONTENT")),
+        207 => Some(("Multi-Status", "MULTI-STATUS")),
+        208 => Some(("Already Reported", "ALREADY REPORTED")),
+        226 => Some(("IM Used", "IM USED")),
+
+        300 => Some(("Multiple Choices", "MULTIPLE CHOICES")),
+        301 => Some(("Moved Permanently", "MOVED PERMANENTLY")),
+        302 => Some(("Found", "FOUND")),
+        303 => Some(("See Other", "SEE OTHER")),
+        304 => Some(("Not Modified", "NOT MODIFIED")),
+        305 => Some(("Use Proxy", "USE PROXY")),
+        306 => Some(("Switch Proxy", "SWITCH PROXY")),
+        307 => Some(("Temporary Redirect", "TEMPORARY REDIRECT")),
+        308 => Some(("Permanent Redirect", "PERMANENT REDIRECT")),
+
+        400 => Some(("Bad Request", "BAD REQUEST")),
+        401 => Some(("Unauthorized", "UNAUTHORIZED")),
+        402 => Some(("Payment Required", "PAYMENT REQUIRED")),
+        403 => Some(("Forbidden", "FORBIDDEN")),
+        404 => Some(("Not Found", "NOT FOUND")),
+        405 => Some(("Method Not Allowed", "METHOD NOT ALLOWED")),
+        406 => Some(("Not Acceptable", "NOT ACCCEPTABLE")),
+        407 => Some(("Proxy Authentication Required", "PROXY AUTHENTICATION REQUIRED")),
+        408 => Some(("Request Timeout", "REQUEST TIMEOUT")),
+        409 => Some(("Conflict", "CONFLICT")),
+        410 => Some(("Gone", "GONE")),
+        411 => Some(("Length Required", "LENGTH REQUIRED")),
+        412 => Some(("Precondition Failed", "PRECONDITON FAILED")),
+        413 => Some(("Content Too Large", "PAYLOAD TOO LARGE")),
+        414 => Some(("URI Too Long", "URI TOO LONG")),
+        415 => Some(("Unsupported Media Type", "UNSUPPORTED MEDIA TYPE")),
+        416 => Some(("Range Not Satisfiable", "RANGE NOT SATISFIABLE")),
+        417 => Some(("Expectation Failed", "EXPECTATION FAILED")),
+        418 => Some(("I'm a teapot", "IM A TEAPOT")),
+        421 => Some(("Misdirected Request", "MISDIRECTED REQUEST")),
+        422 => Some(("Unprocessable Content", "UNPROCESSABLE ENTITY")),
+        423 => Some(("Locked", "LOCKED")),
+        424 => Some(("Failed Dependency", "FAILED DEPENDENCY")),
+        425 => Some(("Too Early", "TOO EARLY")),
+        426 => Some(("Upgrade Required", "UPGRADE REQUIRED")),
+        428 => Some(("Precondition Required", "PRECONDITION REQUIRED")),
+        429 => Some(("Too Many Requests", "TOO MANY REQUESTS")),
+        431 => Some(("Request Header Fields Too Large", "REQUEST HEADER FIELDS TOO LARGE")),
+        451 => Some(("Unavailable For Legal Reasons", "UNAVAILABLE FOR LEGAL REASONS")),
+
+        500 => Some(("Internal Server Error", "SERVER ERROR")),
+        501 => Some(("Not Implemented", "NOT IMPLEMENTED")),
+        502 => Some(("Bad Gateway", "BAD GATEWAY")),
+        503 => Some(("Service Unavailable", "SERVICE UNAVAILABLE")),
+        504 => Some(("Gateway Timeout", "GATEWAY TIMEOUT")),
+        505 => Some(("HTTP Version Not Supported", "HTTP VERSION NOT SUPPORTED")),
+        506 => Some(("Variant Also Negotiates", "VARIANT ALSO NEGOTIATES")),
+        507 => Some(("Insufficient Storage", "INSUFFICIENT STORAGE")),
+        508 => Some(("Loop Detected", "LOOP DETECTED")),
+        510 => Some(("Not Extended", "NOT EXTENDED")),
+        511 => Some(("Network Authentication Required", "NETWORK AUTHENTICATION REQUIRED")),
+
+        #[cfg(feature = "unofficial-codes")]
+        444 => Some(("No Response", "NO RESPONSE")),
+        #[cfg(feature = "unofficial-codes")]
+        499 => Some(("Client Closed Request", "CLIENT CLOSED REQUEST")),
+        #[cfg(feature = "unofficial-codes")]
+        520 => Some(("Web Server Returned an Unknown Error", "WEB SERVER RETURNED AN UNKNOWN ERROR")),
+        #[cfg(feature = "unofficial-codes")]
+        521 => Some(("Web Server Is Down", "WEB SERVER IS DOWN")),
+        #[cfg(feature = "unofficial-codes")]
+        522 => Some(("Connection Timed Out", "CONNECTION TIMED OUT")),
+        #[cfg(feature = "unofficial-codes")]
+        523 => Some(("Origin Is Unreachable", "ORIGIN IS UNREACHABLE")),
+        #[cfg(feature = "unofficial-codes")]
+        524 => Some(("A Timeout Occurred", "A TIMEOUT OCCURRED")),
+        #[cfg(feature = "unofficial-codes")]
+        525 => Some(("SSL Handshake Failed", "SSL HANDSHAKE FAILED")),
+        #[cfg(feature = "unofficial-codes")]
+        526 => Some(("Invalid SSL Certificate", "INVALID SSL CERTIFICATE")),
         _ => None,
     }
 }
@@ -660,21 +1663,226 @@ mod tests {
         assert_eq!(result.into_bytes(), b"HTTP/1.0 200 OK\r\n\r\nSomeBODY");
     }
     #[test]
+    fn to_bytes_matches_into_bytes() {
+        let result = Response::Ok
+            .header("a", "b").unwrap()
+            .header("c", "d").unwrap()
+            .body(vec![0, 255, 1]);
+        let borrowed = result.to_bytes();
+        assert_eq!(borrowed, result.into_bytes());
+        assert_eq!(Response::Gone.to_bytes(), Response::Gone.into_bytes());
+    }
+    #[test]
+    fn conditional_headers_in_one_chain() {
+        let build = |compressed: bool, tag: Option<EntityTag>| {
+            Response::Ok
+                .header("content-type", "text/plain").unwrap()
+                .header_if(compressed, "content-encoding", "gzip").unwrap()
+                .header_opt("etag", tag.map(|t| t.to_string())).unwrap()
+                .header_opt("vary", Some("accept-encoding")).unwrap()
+                .header_opt("vary", Some("origin")).unwrap()
+                .body("data")
+        };
+        let plain = build(false, None);
+        assert_eq!(plain.get_header("content-encoding"), None);
+        assert_eq!(plain.get_header("etag"), None);
+        assert_eq!(plain.get_header("vary").unwrap(), "accept-encoding,origin");
+        let full = build(true, Some(EntityTag::strong("v1").unwrap()));
+        assert_eq!(full.get_header("content-encoding").unwrap(), "gzip");
+        assert_eq!(full.get_header("etag").unwrap(), "\"v1\"");
+        assert!(ResponseBuilder::from(Response::Ok).header_if(false, "", "x").is_ok());
+        assert!(ResponseBuilder::from(Response::Ok).header_if(true, "", "x").is_err());
+        assert!(ResponseBuilder::from(Response::Ok).header_opt("", Some("x")).is_err());
+    }
+    mod allocations {
+        use std::{
+            alloc::{GlobalAlloc, Layout, System},
+            cell::Cell,
+        };
+
+        thread_local! {
+            static COUNT: Cell<usize> = const { Cell::new(0) };
+        }
+        struct Counting;
+        unsafe impl GlobalAlloc for Counting {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = COUNT.try_with(|c| c.set(c.get() + 1));
+                System.alloc(layout)
+            }
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+        #[global_allocator]
+        static GLOBAL: Counting = Counting;
+
+        /// Runs `f`, returning its result and the allocations it made on this thread.
+        pub(super) fn count<T>(f: impl FnOnce() -> T) -> (T, usize) {
+            let before = COUNT.with(Cell::get);
+            let result = f();
+            (result, COUNT.with(Cell::get) - before)
+        }
+    }
+    #[test]
+    fn serialization_allocates_once() {
+        let response = Response::Ok
+            .headers((0..10).map(|i| (format!("x-header-{i}"), format!("value {i}")))).unwrap()
+            .version(Version(1, 1))
+            .body(vec![b'a'; 1000]);
+        let (bytes, allocations) = allocations::count(|| response.to_bytes());
+        assert_eq!(allocations, 1);
+        assert_eq!(bytes.capacity(), bytes.len());
+        let mut out = Vec::with_capacity(2048);
+        let ((), allocations) = allocations::count(|| response.write_to(&mut out).unwrap());
+        assert_eq!(allocations, 1);
+    }
+    #[test]
+    fn string_forms_agree_for_utf8_bodies() {
+        let bodies = ["", "a", "ünïcödé ✓", "line\r\nbreak", "\0", &"x".repeat(DISPLAY_BODY_LIMIT)];
+        let header_sets: [&[(&str, &str)]; 3] = [
+            &[],
+            &[("Host", "example.com")],
+            &[("x-a", "1"), ("Transfer-Encoding", "chunked"), ("x-b", " padded ")],
+        ];
+        for (body, headers) in bodies.iter().flat_map(|b| header_sets.iter().map(move |h| (b, h))) {
+            for version in [None, Some(Version(1, 0)), Some(Version(1, 1))] {
+                let mut builder = Response::Accepted.headers(headers.iter().copied()).unwrap().body(*body);
+                if let Some(version) = version {
+                    builder = builder.version(version);
+                }
+                let text = builder.to_string();
+                assert_eq!(String::from_utf8(builder.to_bytes()).unwrap(), text);
+                let mut written = vec![];
+                builder.write_to(&mut written).unwrap();
+                assert_eq!(written, builder.to_bytes());
+                assert_eq!(String::try_from(builder).unwrap(), text);
+            }
+        }
+    }
+    #[test]
+    fn append_body_in_parts() {
+        let mut parts = Response::Ok.header("a", "b").unwrap().body("one ");
+        parts.append_body("two ");
+        let parts = parts.and_body(b"three".to_vec());
+        let whole = Response::Ok.header("a", "b").unwrap().body("one two three");
+        assert_eq!(parts.body_len(), 13);
+        assert_eq!(parts.into_bytes(), whole.into_bytes());
+    }
+    #[test]
+    fn shared_body_not_copied() {
+        let asset: std::sync::Arc<[u8]> = vec![7u8; 1 << 20].into();
+        let first = Response::Ok.header("content-type", "application/octet-stream").unwrap()
+            .with_body(asset.clone());
+        let second = Response::Ok.with_body(asset.clone()).version(Version(1, 1));
+        assert_eq!(std::sync::Arc::strong_count(&asset), 3);
+        assert_eq!(first.body_len(), 1 << 20);
+        let bytes = second.to_bytes();
+        assert!(bytes.starts_with(b"HTTP/1.1 200 OK\r\n\r\n\x07"));
+        assert_eq!(bytes.len(), 19 + (1 << 20));
+        drop(second);
+        assert_eq!(std::sync::Arc::strong_count(&asset), 2);
+        let embedded: &'static [u8] = b"static";
+        assert_eq!(Response::Ok.with_body(embedded).into_bytes(), Response::Ok.body("static").into_bytes());
+    }
+    #[test]
+    fn one_line_per_cookie() {
+        let response = ResponseBuilder::from(Response::Ok)
+            .set_cookie(SetCookie::new("a", "1").unwrap().http_only(true)).unwrap()
+            .header("x", "y").unwrap()
+            .set_cookie(SetCookie::new("b", "2").unwrap().expire_now()).unwrap()
+            .body("");
+        assert_eq!(response.to_string(), "HTTP/1.0 200 OK\r\n\
+            set-cookie: a=1; HttpOnly\r\n\
+            x: y\r\n\
+            set-cookie: b=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0\r\n\r\n");
+        assert_eq!(response.get_all_headers("Set-Cookie").count(), 2);
+    }
+    #[test]
+    fn cookie_count_not_limited() {
+        let mut builder = ResponseBuilder::from(Response::Ok);
+        for i in 0..300 {
+            builder = builder.set_cookie(SetCookie::new(format!("c{i}"), "1").unwrap()).unwrap();
+        }
+        assert_eq!(builder.body("").get_all_headers("set-cookie").count(), 300);
+    }
+    #[test]
+    fn range_not_satisfiable_star_form() {
+        let response = Response::range_not_satisfiable(1234).body("requested range is past the end");
+        assert_eq!(response.to_string(), "HTTP/1.0 416 Range Not Satisfiable\r\n\
+            content-range: bytes */1234\r\n\r\n\
+            requested range is past the end");
+    }
+    #[test]
+    fn parts_roundtrip() {
+        let response = Response::Created
+            .header("Location", "/items/1").unwrap()
+            .header("Host", "example.com").unwrap()
+            .set_cookie(SetCookie::new("a", "1").unwrap()).unwrap()
+            .set_cookie(SetCookie::new("b", "2").unwrap()).unwrap()
+            .body(vec![0u8, 159, 146, 150]);
+        let expected = response.to_bytes();
+        let (status, headers, body) = response.into_parts();
+        assert_eq!(status, Response::Created);
+        assert_eq!(headers.len(), 4);
+        assert_eq!(ResponseBuilder::from_parts(status, headers, body).into_bytes(), expected);
+    }
+    #[test]
+    fn content_length_mismatch() {
+        let lying = Response::Ok.header("Content-Length", "10").unwrap().body("hello");
+        let mut out = vec![];
+        let err = lying.write_to(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(out.is_empty());
+        assert_eq!(lying.try_into_bytes(),
+            Err(ResponseError::ContentLengthMismatch { declared: 10, actual: 5 }));
+    }
+    #[test]
+    fn content_length_match() {
+        let response = Response::Ok.header("Content-Length", " 5 ").unwrap().body("hello");
+        assert_eq!(response.try_into_bytes().unwrap(), b"HTTP/1.0 200 OK\r\ncontent-length: 5\r\n\r\nhello");
+        let head = Response::Ok.header("content-length", "5").unwrap().body("");
+        assert!(head.try_into_bytes().is_ok());
+    }
+    #[test]
+    fn content_length_not_a_number() {
+        for bad in ["", "ten", "-1", "5, 5", "0x10"] {
+            assert_eq!(Response::Ok.header("content-length", bad).unwrap_err(), HeaderError::Value {
+                key: Some(Key::CONTENT_LENGTH),
+                error: ValueError::InvalidContentLength { input: bad.to_owned() },
+            }, "{bad:?}");
+        }
+        let twice = Response::Ok.header("content-length", "3").unwrap().header("content-length", "4").unwrap();
+        assert_eq!(twice.get_header("content-length").unwrap(), "4");
+        let twice = Response::Ok.header("content-type", "text/plain").unwrap().header("Content-Type", "text/html").unwrap();
+        assert_eq!(twice.get_header("content-type").unwrap(), "text/html");
+    }
+    #[test]
+    fn no_content_length_without_one() {
+        let response = Response::Ok.header("a", "b").unwrap().body("data").and_body("more");
+        assert_eq!(response.get_header("content-length"), None);
+        assert_eq!(response.try_into_bytes().unwrap(), b"HTTP/1.0 200 OK\r\na: b\r\n\r\ndatamore");
+    }
+    #[test]
+    fn append_body_updates_content_length() {
+        let res = Response::Ok.header("Content-Length", "1").unwrap()
+            .body("a")
+            .and_body("bc");
+        assert_eq!(res.get_header("content-length").unwrap(), "3");
+    }
+    #[test]
     fn response_header_bytes() {
         let result = Response::Ok.header("hi", "its me").unwrap().body("someBODY");
-        assert_eq!(result.into_bytes(), b"HTTP/1.0 200 OK\r\nhi:its me\r\n\r\nsomeBODY");
+        assert_eq!(result.into_bytes(), b"HTTP/1.0 200 OK\r\nhi: its me\r\n\r\nsomeBODY");
     }
     #[test]
-    // Header fields with different keys may appear in arbitrary order
+    // Header fields are written in the order they were first added
     fn reponse_multiple_headers() {
         let result = Response::Ok
             .header("hey", "man").unwrap()
             .header("how", "are you").unwrap()
             .body("someBODY");
-        assert!(result.clone().into_bytes()
-            == b"HTTP/1.0 200 OK\r\nhey:man\r\nhow:are you\r\n\r\nsomeBODY"
-            || result.into_bytes()
-            == b"HTTP/1.0 200 OK\r\nhow:are you\r\nhey:man\r\n\r\nsomeBODY"
+        assert_eq!(result.into_bytes(),
+            b"HTTP/1.0 200 OK\r\nhey: man\r\nhow: are you\r\n\r\nsomeBODY"
         )
     }
     #[test]
@@ -723,15 +1931,15 @@ mod tests {
             .body("is great");
         let string: String = response.try_into()?;
         assert_eq!(string,
-            "HTTP/1.0 404 NOT FOUND\r\n\
-            your:mom\r\n\r\n\
+            "HTTP/1.0 404 Not Found\r\n\
+            your: mom\r\n\r\n\
             is great".to_owned());
         Ok(())
     }
     #[test]
     fn complete_correct_string() {
-        let test_string ="HTTP/1.0 400 BAD REQUEST\r\n\
-        header:stuff\r\n\r\n".to_owned();
+        let test_string ="HTTP/1.0 400 Bad Request\r\n\
+        header: stuff\r\n\r\n".to_owned();
         let raw = Response::BadRequest
             .header("header","stuff")
             .unwrap();
@@ -740,25 +1948,398 @@ mod tests {
     }
     #[test]
     fn print_invalid_utf8() {
-        let test_string = "HTTP/1.0 400 BAD REQUEST\r\n\r\n\
-        [14, 147, 94]".to_owned();
+        let test_string = "HTTP/1.0 400 Bad Request\r\n\r\n\
+        \u{e}\u{fffd}^".to_owned();
         let response = Response::BadRequest
             .body(vec![14, 147, 94]);
         assert_eq!(test_string, response.to_string());
     }
     #[test]
+    fn print_truncates_long_body() {
+        let response = Response::Ok.body("a".repeat(DISPLAY_BODY_LIMIT + 10));
+        let printed = response.to_string();
+        assert!(printed.ends_with(&format!("a… ({} bytes total)", DISPLAY_BODY_LIMIT + 10)));
+        assert_eq!(response.into_bytes().len(), "HTTP/1.0 200 OK\r\n\r\n".len() + DISPLAY_BODY_LIMIT + 10);
+    }
+    #[test]
     fn print_no_header_only_two_rns() {
-        let test_string = "HTTP/1.0 418 IM A TEAPOT\r\n\r\n".to_owned();
+        let test_string = "HTTP/1.0 418 I'm a teapot\r\n\r\n".to_owned();
         let response = Response::ImATeapot;
         assert_eq!(test_string, response.to_string())
     }
     #[test]
+    fn print_upper_legacy_phrase() {
+        let response = Response::ServerError
+            .header("a", "b").unwrap()
+            .phrase_style(PhraseStyle::UpperLegacy);
+        assert_eq!(response.to_string(), "HTTP/1.0 500 SERVER ERROR\r\na: b\r\n\r\n");
+        assert_eq!(Response::ServerError.to_string(), "HTTP/1.0 500 Internal Server Error\r\n\r\n");
+    }
+    #[test]
+    fn phrase_table_matches_codes() {
+        for code in 0..1000 {
+            let known = Response::try_from(code).is_ok();
+            assert_eq!(known, phrase(code, PhraseStyle::Canonical).is_some(), "{code}");
+            assert_eq!(known, phrase(code, PhraseStyle::UpperLegacy).is_some(), "{code}");
+        }
+    }
+    #[test]
+    fn every_variant_roundtrips() {
+        let codes: Vec<u16> = Response::iter().map(|r| r.code_u16()).collect();
+        assert!(codes.windows(2).all(|w| w[0] != w[1]));
+        for response in Response::iter() {
+            assert_eq!(Response::try_from(response.code()), Ok(response.clone()));
+            assert!(standard_phrase(response.code()).is_some(), "{response:?}");
+        }
+        let known = (0..1000).filter(|&c| Response::try_from(c).is_ok()).count();
+        assert_eq!(known, codes.len());
+    }
+    #[test]
+    fn unofficial_codes_only_with_feature() {
+        let unofficial = [444, 499, 520, 521, 522, 523, 524, 525, 526];
+        for code in unofficial {
+            assert_eq!(Response::try_from(code).is_ok(), cfg!(feature = "unofficial-codes"), "{code}");
+        }
+        #[cfg(feature = "unofficial-codes")]
+        assert_eq!(Response::ClientClosedRequest.to_string(), "HTTP/1.0 499 Client Closed Request\r\n\r\n");
+    }
+    #[test]
+    fn legacy_phrase_is_uppercase() {
+        // The one phrase earlier versions wrote in mixed case
+        for code in (0..1000).filter(|&c| c != 202 && Response::try_from(c).is_ok()) {
+            let upper = phrase(code, PhraseStyle::UpperLegacy).unwrap();
+            assert_eq!(upper, upper.to_ascii_uppercase());
+        }
+    }
+    #[test]
+    fn legacy_phrase_keeps_old_bytes() {
+        let legacy = |response: Response| response.phrase_style(PhraseStyle::UpperLegacy).to_string();
+        assert_eq!(legacy(Response::Accepted), "HTTP/1.0 202 Accepted\r\n\r\n");
+        assert_eq!(legacy(Response::NotAcceptable), "HTTP/1.0 406 NOT ACCCEPTABLE\r\n\r\n");
+        assert_eq!(legacy(Response::PreconditonFailed), "HTTP/1.0 412 PRECONDITON FAILED\r\n\r\n");
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip_binary_body() {
+        let response = Response::PartialContent
+            .header("Content-Type", "application/octet-stream").unwrap()
+            .header("X-Id", "1").unwrap()
+            .body(vec![0, 159, 146, 150, 255]);
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"response\":206"));
+        let back: ResponseBuilder<Complete> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, response);
+    }
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn write_to_async_matches_write_to() {
+        let response = Response::Ok.header("content-type", "text/plain").unwrap().body("hi");
+        let (mut sync, mut async_) = (vec![], vec![]);
+        response.write_to(&mut sync).unwrap();
+        response.write_to_async(&mut async_).await.unwrap();
+        assert_eq!(sync, async_);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_unknown_code() {
+        assert!(serde_json::from_str::<Response>("299").is_err());
+        assert_eq!(serde_json::from_str::<Response>("404").unwrap(), Response::NotFound);
+    }
+    #[derive(Debug)]
+    enum ApiError {
+        NotFound,
+        RateLimited,
+        Broken,
+    }
+    impl_response_code! {
+        ApiError {
+            NotFound => NotFound,
+            RateLimited => TooManyRequests,
+            Broken => ServerError,
+        }
+    }
+    #[test]
+    fn application_enum_status_lines() {
+        assert_eq!(ApiError::NotFound.into_bytes(), b"HTTP/1.0 404 Not Found\r\n\r\n");
+        assert_eq!(ApiError::RateLimited.into_bytes(), b"HTTP/1.0 429 Too Many Requests\r\n\r\n");
+        assert_eq!(ApiError::Broken.standard_phrase(), "Internal Server Error");
+        let builder = ResponseBuilder::from(ApiError::Broken)
+            .header("retry-after", "10").unwrap();
+        assert_eq!(builder.to_string(), "HTTP/1.0 500 Internal Server Error\r\nretry-after: 10\r\n\r\n");
+    }
+    enum Custom {
+        Stale,
+        Gone,
+    }
+    impl ResponseCode for Custom {
+        fn code(&self) -> u16 {
+            match self {
+                Custom::Stale => 299,
+                Custom::Gone => 410,
+            }
+        }
+    }
+    #[test]
+    fn custom_code_outside_table() {
+        assert_eq!(standard_phrase(299), None);
+        assert_eq!(Custom::Stale.standard_phrase(), "");
+        assert_eq!(Custom::Stale.phrase(PhraseStyle::UpperLegacy), "");
+        assert_eq!(Custom::Stale.first_line(Version(1, 1)), "HTTP/1.1 299 ");
+        assert_eq!(Custom::Gone.first_line(Version(1, 0)), "HTTP/1.0 410 Gone");
+        assert_eq!(Custom::Gone.phrase(PhraseStyle::UpperLegacy), "GONE");
+    }
+    #[test]
+    fn etag_not_modified_flow() {
+        let body = "representation";
+        let tag = EntityTag::from_body(body.as_bytes());
+        let first = Response::Ok.header("a", "b").unwrap().etag(tag.clone()).unwrap().body(body);
+        let sent = first.get_header("etag").unwrap().to_string();
+
+        let request: Request = format!("GET / HTTP/1.1\r\nIf-None-Match: {sent}\r\n\r\n").parse().unwrap();
+        let response = if request.if_none_match(&tag) {
+            Response::NotModified.header("a", "b").unwrap().etag(tag.clone()).unwrap().body("")
+        } else {
+            Response::Ok.header("a", "b").unwrap().etag(tag.clone()).unwrap().body(body)
+        };
+        assert_eq!(response.code(), 304);
+        assert_eq!(response.get_header("etag").unwrap(), &sent);
+    }
+    #[test]
+    fn partial_content_middle() {
+        let response = Response::partial_content(b"abcdefghij", &ByteRange::FromTo(3, 5));
+        assert_eq!(response.code(), 206);
+        assert_eq!(response.get_header("content-range").unwrap(), "bytes 3-5/10");
+        assert_eq!(response.get_header("content-length").unwrap(), "3");
+        assert_eq!(response.body, b"def");
+    }
+    #[test]
+    fn partial_content_suffix() {
+        let response = Response::partial_content(b"abcdefghij", &ByteRange::Suffix(4));
+        assert_eq!(response.get_header("content-range").unwrap(), "bytes 6-9/10");
+        assert_eq!(response.body, b"ghij");
+    }
+    #[test]
+    fn partial_content_unsatisfiable() {
+        let response = Response::partial_content(b"abcdefghij", &ByteRange::From(10));
+        assert_eq!(response.code(), 416);
+        assert_eq!(response.get_header("content-range").unwrap(), "bytes */10");
+        assert!(response.body.is_empty());
+    }
+    #[test]
+    fn method_not_allowed_lists_methods() {
+        use RequestMethod::*;
+        let response = Response::method_not_allowed(&[Get, Head, Post, Get]).unwrap();
+        assert_eq!(response.code(), 405);
+        assert_eq!(response.get_header("allow").unwrap(), "GET, HEAD, POST");
+        assert!(Response::method_not_allowed(&[]).is_err());
+    }
+    #[test]
+    fn upgrade_headers() {
+        let response = Response::upgrade_required(&["h2c", "websocket/13"]).unwrap().body("");
+        assert_eq!(response.to_string(),
+            "HTTP/1.0 426 Upgrade Required\r\nupgrade: h2c, websocket/13\r\nconnection: Upgrade\r\n\r\n");
+        assert!(Response::upgrade_required(&[]).is_err());
+        assert!(Response::upgrade_required(&["web socket"]).is_err());
+        let long = "a".repeat(crate::header::MAX_VALUE_LEN);
+        let error = HeaderError::Value {
+            key: Some(Key::UPGRADE),
+            error: ValueError::TooLong { len: long.len() * 2 + 2, limit: crate::header::MAX_VALUE_LEN },
+        };
+        assert_eq!(Response::upgrade_required(&[&long, &long]).unwrap_err(), error);
+        let response = Response::switching_protocols("h2c").unwrap().body("");
+        assert_eq!(response.to_string(),
+            "HTTP/1.0 101 Switching Protocols\r\nupgrade: h2c\r\nconnection: Upgrade\r\n\r\n");
+    }
+    #[test]
+    fn unauthorized_two_challenges() {
+        let response = Response::unauthorized(&Challenge::basic("api").unwrap()).unwrap()
+            .www_authenticate(&Challenge::bearer().param("error", "invalid_token").unwrap()).unwrap();
+        assert_eq!(response.code(), 401);
+        assert_eq!(response.get_header("www-authenticate").unwrap(),
+            r#"Basic realm="api",Bearer error="invalid_token""#);
+    }
+    #[test]
+    fn retry_after_forms() {
+        let response = Response::too_many_requests(Duration::from_secs(120));
+        assert_eq!(response.code(), 429);
+        assert_eq!(response.get_header("retry-after").unwrap(), "120");
+        // A client must not be told to retry right away, or before `retry`
+        let response = Response::too_many_requests(Duration::from_millis(300));
+        assert_eq!(response.get_header("retry-after").unwrap(), "1");
+        let response = Response::service_unavailable(Duration::from_millis(1500));
+        assert_eq!(response.get_header("retry-after").unwrap(), "2");
+        let response = Response::service_unavailable(Duration::ZERO);
+        assert_eq!(response.get_header("retry-after").unwrap(), "1");
+
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+        let response = Response::service_unavailable(Duration::from_secs(5)).retry_after_date(date);
+        assert_eq!(response.get_header("retry-after").unwrap(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+    #[test]
+    fn retry_after_parse() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(784111700);
+        assert_eq!(parse_retry_after("30", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now), Some(Duration::from_secs(77)));
+        let later = now + Duration::from_secs(1000);
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", later), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("+30", now), None);
+    }
+    #[test]
+    fn with_status_keeps_headers() {
+        let response = Response::Ok
+            .header("x-request-id", "42").unwrap()
+            .header("host", "example.com").unwrap()
+            .with_status(Response::NotFound)
+            .body("gone");
+        assert_eq!(response.into_bytes(),
+            b"HTTP/1.1 404 Not Found\r\nx-request-id: 42\r\nhost: example.com\r\n\r\ngone");
+    }
+    #[test]
+    fn bulk_headers() {
+        let response = Response::Ok
+            .header("accept", "text/html").unwrap()
+            .headers([
+                ("x-a", "1"),
+                ("Accept", "text/plain"),
+                ("x-b", "2"),
+                ("x-c", "3"),
+                ("x-d", "4"),
+            ]).unwrap();
+        assert_eq!(response.to_string(),
+            "HTTP/1.0 200 OK\r\naccept: text/html,text/plain\r\nx-a: 1\r\nx-b: 2\r\nx-c: 3\r\nx-d: 4\r\n\r\n");
+    }
+    #[test]
+    fn bulk_headers_report_index() {
+        let result = Response::Ok.headers([("a", "1"), ("b", ""), ("c", "3")]);
+        let error = HeaderError::Value { key: Some(Key::new("b").unwrap()), error: ValueError::EmptyString };
+        assert_eq!(result, Err(HeaderError::AtIndex(1, Box::new(error))));
+    }
+    #[test]
+    fn typed_headers_serialized() {
+        use crate::header::{cache_control::Directive, CacheControl};
+        let response = Response::Ok.header("a", "b").unwrap()
+            .typed_header(CacheControl::new(Directive::NoCache).with(Directive::MaxAge(0))).unwrap()
+            .typed_header(EntityTag::weak("v2").unwrap()).unwrap();
+        assert_eq!(response.to_string(),
+            "HTTP/1.0 200 OK\r\na: b\r\ncache-control: no-cache, max-age=0\r\netag: W/\"v2\"\r\n\r\n");
+    }
+    #[test]
+    fn typed_headers_too_long() {
+        use crate::header::{cache_control::Directive, CacheControl, KeepAlive, MAX_VALUE_LEN};
+        let long = "a".repeat(17000);
+        let too_long = |key: Key, len| Err(HeaderError::Value { key: Some(key), error: ValueError::TooLong { len, limit: MAX_VALUE_LEN } });
+        let builder = || ResponseBuilder::from(Response::Ok);
+        assert_eq!(builder().typed_header(EntityTag::strong(&long).unwrap()), too_long(Key::ETAG, 17002));
+        assert_eq!(builder().typed_header(Challenge::new(&long).unwrap()), too_long(Key::WWW_AUTHENTICATE, 17000));
+        assert_eq!(builder().set_cookie(SetCookie::new("a", &long).unwrap()), too_long(Key::SET_COOKIE, 17002));
+        assert_eq!(builder().typed_header(Link::new(format!("/{long}")).unwrap()), too_long(Key::LINK, 17003));
+        assert_eq!(builder().attachment(&long), too_long(Key::CONTENT_DISPOSITION, 17021));
+        let extension = Directive::Extension { name: long.clone(), value: None };
+        assert_eq!(builder().typed_header(CacheControl::new(extension)), too_long(Key::CACHE_CONTROL, 17000));
+        // numeric values cannot grow past the limit
+        let range = ContentRange::Bytes { first: u64::MAX, last: u64::MAX, complete_length: Some(u64::MAX) };
+        assert!(builder().content_range(range).is_ok());
+        let keep_alive = KeepAlive { timeout: Some(Duration::MAX), max: Some(u64::MAX) };
+        assert!(builder().typed_header(keep_alive).is_ok());
+    }
+    #[test]
+    fn downgrade_chunked_to_close_delimited() {
+        let response = Response::Ok
+            .header("Transfer-Encoding", "chunked").unwrap()
+            .header("Content-Length", "4").unwrap()
+            .header("a", "b").unwrap()
+            .version(Version(1, 0))
+            .body("data");
+        assert!(response.closes_connection());
+        assert_eq!(response.to_bytes(), b"HTTP/1.0 200 OK\r\na: b\r\n\r\ndata");
+        assert_eq!(response.to_string(), "HTTP/1.0 200 OK\r\na: b\r\n\r\ndata");
+    }
+    #[test]
+    fn chunked_kept_at_one_one() {
+        let response = Response::Ok
+            .header("Transfer-Encoding", "chunked").unwrap()
+            .version(Version(1, 1))
+            .body("");
+        assert!(!response.closes_connection());
+        assert_eq!(response.into_bytes(), b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n");
+    }
+    #[test]
+    fn early_hints_two_links() {
+        let links = [
+            Link::new("/style.css").unwrap().rel("preload").unwrap().param("as", "style").unwrap(),
+            Link::new("https://fonts.example/font.woff2").unwrap().rel("preload").unwrap()
+                .param("as", "font").unwrap()
+                .param("crossorigin", "anonymous").unwrap(),
+        ];
+        let hints = Response::early_hints(&links).unwrap();
+        let value = hints.get_header("link").unwrap();
+        assert_eq!(value, "</style.css>; rel=preload; as=style, \
+            <https://fonts.example/font.woff2>; rel=preload; as=font; crossorigin=anonymous");
+        assert_eq!(Link::parse_list(&value.as_str_lossy()).unwrap(), links);
+    }
+    #[test]
+    fn early_hints_refuses_unsendable_links() {
+        let error = |error| Err(HeaderError::Value { key: Some(Key::LINK), error });
+        assert_eq!(Response::early_hints(&[]).map(|_| ()), error(ValueError::EmptyString));
+        let long = Link::new(format!("/{}", "a".repeat(crate::header::MAX_VALUE_LEN))).unwrap();
+        let len = crate::header::MAX_VALUE_LEN + 3;
+        assert_eq!(Response::early_hints(&[long]).map(|_| ()), error(ValueError::TooLong { len, limit: crate::header::MAX_VALUE_LEN }));
+    }
+    #[test]
     fn version_host_key() {
         let res = Response::Ok
             .header("Host", "github.com").unwrap();
         assert_eq!(res.max_version(), Version(1,1));
     }
     #[test]
+    fn connection_one_one_keep_alive_is_default() {
+        let res = Response::Ok.header("a", "b").unwrap()
+            .connection(true, &Version(1, 1));
+        assert_eq!(res.headers.get("connection"), None);
+    }
+    #[test]
+    fn connection_one_one_close() {
+        let res = Response::Ok.header("a", "b").unwrap()
+            .connection(false, &Version(1, 1));
+        assert_eq!(res.headers.get("connection").unwrap(), "close");
+    }
+    #[test]
+    fn connection_one_zero_keep_alive() {
+        let res = Response::Ok.header("a", "b").unwrap()
+            .connection(true, &Version(1, 0));
+        assert_eq!(res.headers.get("connection").unwrap(), "keep-alive");
+    }
+    #[test]
+    fn connection_one_zero_close_is_default() {
+        let res = Response::Ok.header("a", "b").unwrap()
+            .connection(false, &Version(1, 0));
+        assert_eq!(res.headers.get("connection"), None);
+    }
+    #[test]
+    fn for_request_uses_request_version() {
+        let request: Request = "GET / HTTP/1.1\r\nConnection: close\r\n\r\n".parse().unwrap();
+        let res = Response::Ok.for_request(&request);
+        assert_eq!(res.max_version(), Version(1, 1));
+        assert_eq!(res.into_bytes(), b"HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n");
+    }
+    #[test]
+    fn extensions_survive_body() {
+        let mut res = Response::Ok.header("a", "b").unwrap();
+        res.extensions_mut().insert(42u32);
+        let res = res.body("done");
+        assert_eq!(res.extensions().get::<u32>(), Some(&42));
+        assert_eq!(res.extensions().get::<u64>(), None);
+        assert_eq!(res, Response::Ok.header("a", "b").unwrap().body("done"));
+    }
+    #[test]
+    fn vary_keys() {
+        let res = Response::Ok.header("vary", "origin, Origin").unwrap().header("vary", "accept").unwrap();
+        assert_eq!(res.vary_keys().unwrap(), [Key::ORIGIN, Key::ACCEPT]);
+        assert_eq!(Response::Ok.header("vary", "origin, *").unwrap().vary_keys(), None);
+        assert_eq!(Response::Ok.body("").vary_keys(), Some(vec![]));
+    }
+    #[test]
     fn version_no_host_key() {
         let res = Response::Ok;
         assert_eq!(res.max_version(), Version(1,0));
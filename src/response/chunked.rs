@@ -0,0 +1,293 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use crate::{
+    framing::ChunkedDecoder,
+    header::{key::Key, value::Value, HeaderError},
+    Request,
+    Version,
+};
+use super::{Complete, Incomplete, Response, ResponseBuilder};
+
+/// Whether `k` may be sent as a trailer field. Fields a recipient needs
+/// before the content are [forbidden][crate::header::HeaderInfo::forbidden_in_trailers].
+pub fn allowed_in_trailer(k: &Key) -> bool {
+    !k.info().is_some_and(|info| info.forbidden_in_trailers)
+}
+
+#[derive(PartialEq, Debug, Clone, Copy, Eq, Default)]
+/// What to do with trailers for a request that did not announce
+/// [te: trailers][Request::accepts_trailers].
+pub enum TrailerPolicy {
+    /// Fail with [HeaderError::TrailersNotAccepted]
+    #[default]
+    Reject,
+    /// Leave the trailers out
+    Skip,
+}
+
+impl Response {
+    /// See [ResponseBuilder::declare_trailer].
+    pub fn declare_trailer<K: AsRef<str>>(self, k: K) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(self).declare_trailer(k)
+    }
+    /// See [ResponseBuilder::declare_trailer_for].
+    pub fn declare_trailer_for<K: AsRef<str>>(self, k: K, request: &Request, policy: TrailerPolicy) -> Result<ResponseBuilder<Incomplete>, HeaderError> {
+        ResponseBuilder::new(self).declare_trailer_for(k, request, policy)
+    }
+}
+
+impl ResponseBuilder<Incomplete> {
+    /// Announces a field that will be sent in the trailer section
+    /// by [finish_with_trailers][ResponseBuilder::finish_with_trailers].
+    pub fn declare_trailer<K: AsRef<str>>(mut self, k: K) -> Result<Self, HeaderError> {
+        let k = Key::new(k)?;
+        if !allowed_in_trailer(&k) {
+            return Err(HeaderError::IllegalTrailer);
+        }
+        self.headers.append(Key::TRAILER, k.to_string())?;
+        Ok(self)
+    }
+    /// [declare_trailer][Self::declare_trailer] in answer to `request`,
+    /// which applies `policy` if the client does not accept trailers.
+    pub fn declare_trailer_for<K: AsRef<str>>(self, k: K, request: &Request, policy: TrailerPolicy) -> Result<Self, HeaderError> {
+        match (request.accepts_trailers(), policy) {
+            (true, _) => self.declare_trailer(k),
+            (false, TrailerPolicy::Reject) => Err(HeaderError::TrailersNotAccepted),
+            (false, TrailerPolicy::Skip) => Ok(self),
+        }
+    }
+}
+
+impl ResponseBuilder<Complete> {
+    /// Serializes the response with `transfer-encoding: chunked`,
+    /// followed by a trailer section containing `trailers`.
+    ///
+    /// Any `content-length` is dropped, as chunked framing replaces it.
+    /// Unless a version was chosen explicitly, the response is sent as
+    /// HTTP/1.1, which introduced chunked transfer coding. A response
+    /// [downgraded][ResponseBuilder::version] to HTTP/1.0 is sent
+    /// close-delimited instead, and the trailers are dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::Response;
+    /// let bytes = Response::Ok
+    ///     .declare_trailer("digest").unwrap()
+    ///     .body("hello")
+    ///     .finish_with_trailers([("digest", "abc")]).unwrap();
+    /// assert_eq!(bytes,
+    ///     b"HTTP/1.1 200 OK\r\n\
+    ///     trailer: digest\r\n\
+    ///     transfer-encoding: chunked\r\n\r\n\
+    ///     5\r\nhello\r\n\
+    ///     0\r\ndigest: abc\r\n\r\n");
+    /// ```
+    pub fn finish_with_trailers<I, K, V>(mut self, trailers: I) -> Result<Vec<u8>, HeaderError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let trailers = trailers.into_iter()
+            .map(|(k, v)| {
+                let k = Key::new(k)?;
+                if !allowed_in_trailer(&k) {
+                    return Err(HeaderError::IllegalTrailer);
+                }
+                Ok((k, Value::new(v)?))
+            })
+            .collect::<Result<Vec<_>, HeaderError>>()?;
+
+        self.headers.remove("content-length");
+        self.headers.insert(Key::TRANSFER_ENCODING, Value::new("chunked").unwrap());
+        self.version.get_or_insert(Version(1, 1));
+
+        if self.close_delimited() {
+            return Ok(crate::Byteable::into_bytes(self));
+        }
+        let body = std::mem::take(&mut self.body);
+        let mut bytes = crate::Byteable::into_bytes(self);
+        if !body.is_empty() {
+            bytes.extend(format!("{:x}\r\n", body.len()).into_bytes());
+            bytes.extend(body);
+            bytes.extend(b"\r\n");
+        }
+        bytes.extend(b"0\r\n");
+        for (k, v) in trailers {
+            bytes.extend(k.as_str().as_bytes());
+            bytes.extend(b": ");
+            bytes.extend(v.as_bytes());
+            bytes.extend(b"\r\n");
+        }
+        bytes.extend(b"\r\n");
+        Ok(bytes)
+    }
+    /// [finish_with_trailers][Self::finish_with_trailers] in answer to
+    /// `request`, which applies `policy` if the client does not accept trailers.
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Request, Response, response::TrailerPolicy};
+    /// let request: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+    /// let bytes = Response::Ok.body("hi")
+    ///     .finish_with_trailers_for([("digest", "abc")], &request, TrailerPolicy::Skip).unwrap();
+    /// assert!(bytes.ends_with(b"0\r\n\r\n"));
+    /// ```
+    pub fn finish_with_trailers_for<I, K, V>(self, trailers: I, request: &Request, policy: TrailerPolicy) -> Result<Vec<u8>, HeaderError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        if request.accepts_trailers() {
+            return self.finish_with_trailers(trailers);
+        }
+        let mut trailers = trailers.into_iter().peekable();
+        match policy {
+            TrailerPolicy::Reject if trailers.peek().is_some() => Err(HeaderError::TrailersNotAccepted),
+            _ => self.finish_with_trailers(std::iter::empty::<(K, V)>()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChunkedError {
+    /// A chunk size line is not a hexadecimal number
+    InvalidSize,
+    /// The input ended before the last chunk or the trailer section
+    Truncated,
+    /// A chunk is not followed by `\r\n`
+    MissingLineEnd,
+    /// A trailer field is not a valid header
+    BadTrailer(HeaderError),
+}
+impl Error for ChunkedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::BadTrailer(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl Display for ChunkedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::InvalidSize => "invalid chunk size",
+            Self::Truncated => "truncated chunked body",
+            Self::MissingLineEnd => "chunk not terminated by CRLF",
+            Self::BadTrailer(_) => "trailer invalid",
+        })
+    }
+}
+impl From<HeaderError> for ChunkedError {
+    fn from(value: HeaderError) -> Self {
+        Self::BadTrailer(value)
+    }
+}
+
+/// Fields of a trailer section in the order they were received.
+pub type Trailers = Vec<(Key, Value)>;
+
+/// Decodes a chunked message body, returning the content and the trailer fields.
+///
+/// Chunk extensions are ignored.
+pub fn decode_chunked(bytes: &[u8]) -> Result<(Vec<u8>, Trailers), ChunkedError> {
+    let (mut decoder, mut body) = (ChunkedDecoder::keeping_trailers(), vec![]);
+    decoder.feed(bytes, &mut body)?;
+    if !decoder.is_done() {
+        return Err(ChunkedError::Truncated);
+    }
+    let trailers = decoder.trailers().iter().map(|field| {
+        let field = String::from_utf8_lossy(field);
+        let (k, v) = field.split_once(':').ok_or(HeaderError::MissingValue)?;
+        Ok((
+            Key::new(k).map_err(HeaderError::from)?,
+            Value::new(v).map_err(HeaderError::from)?,
+        ))
+    }).collect::<Result<_, ChunkedError>>()?;
+    Ok((body, trailers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailer_roundtrip() {
+        let bytes = Response::Ok
+            .declare_trailer("Digest").unwrap()
+            .body("some body")
+            .finish_with_trailers([("Digest", "sha-256=abc")]).unwrap();
+        let split = bytes.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = std::str::from_utf8(&bytes[..split]).unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("trailer: digest"));
+        assert!(head.contains("transfer-encoding: chunked"));
+
+        let (body, trailers) = decode_chunked(&bytes[split + 4..]).unwrap();
+        assert_eq!(body, b"some body");
+        assert_eq!(trailers, [(Key::new("digest").unwrap(), Value::new("sha-256=abc").unwrap())]);
+    }
+    #[test]
+    fn reject_framing_trailer() {
+        let result = Response::Ok.body("x").finish_with_trailers([("Content-Length", "1")]);
+        assert_eq!(result, Err(HeaderError::IllegalTrailer));
+        assert!(Response::Ok.header("a", "b").unwrap().declare_trailer("authorization").is_err());
+    }
+    #[test]
+    fn drops_content_length() {
+        let bytes = Response::Ok
+            .header("Content-Length", "3").unwrap()
+            .body("abc")
+            .finish_with_trailers(Vec::<(&str, &str)>::new()).unwrap();
+        assert_eq!(bytes, b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n");
+    }
+    #[test]
+    fn downgraded_is_close_delimited() {
+        let response = Response::Ok.header("a", "b").unwrap()
+            .version(Version(1, 0))
+            .body("abc");
+        let bytes = response.finish_with_trailers([("digest", "x")]).unwrap();
+        assert_eq!(bytes, b"HTTP/1.0 200 OK\r\na: b\r\n\r\nabc");
+    }
+    #[test]
+    fn trailer_policy() {
+        let without: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        let with: Request = "GET / HTTP/1.1\r\nTE: trailers\r\n\r\n".parse().unwrap();
+        assert_eq!(
+            Response::Ok.declare_trailer_for("digest", &without, TrailerPolicy::Reject),
+            Err(HeaderError::TrailersNotAccepted)
+        );
+        let skipped = Response::Ok.declare_trailer_for("digest", &without, TrailerPolicy::Skip).unwrap();
+        assert_eq!(skipped.get_header("trailer"), None);
+        let declared = Response::Ok.declare_trailer_for("digest", &with, TrailerPolicy::Reject).unwrap();
+        assert_eq!(declared.get_header("trailer").unwrap(), "digest");
+
+        let bytes = declared.body("x")
+            .finish_with_trailers_for([("digest", "abc")], &with, TrailerPolicy::Reject).unwrap();
+        assert!(bytes.ends_with(b"0\r\ndigest: abc\r\n\r\n"));
+        let result = Response::Ok.body("x")
+            .finish_with_trailers_for([("digest", "abc")], &without, TrailerPolicy::Reject);
+        assert_eq!(result, Err(HeaderError::TrailersNotAccepted));
+    }
+    #[test]
+    fn decode_multiple_chunks_with_extension() {
+        let (body, trailers) = decode_chunked(b"3;ext=1\r\nabc\r\n2\r\nde\r\n0\r\n\r\n").unwrap();
+        assert_eq!(body, b"abcde");
+        assert!(trailers.is_empty());
+    }
+    #[test]
+    fn decode_truncated() {
+        assert_eq!(decode_chunked(b"5\r\nab"), Err(ChunkedError::Truncated));
+    }
+    #[test]
+    fn decode_refuses_bad_sizes() {
+        assert_eq!(decode_chunked(b"ffffffffffffffff\r\nab"), Err(ChunkedError::Truncated));
+        assert_eq!(decode_chunked(b"10000000000000000\r\nab"), Err(ChunkedError::InvalidSize));
+        assert_eq!(decode_chunked(b"+2\r\nab\r\n0\r\n\r\n"), Err(ChunkedError::InvalidSize));
+        assert_eq!(decode_chunked(b"2\r\nabc\r\n0\r\n\r\n"), Err(ChunkedError::MissingLineEnd));
+    }
+}
@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use crate::{
+    header::{key::Key, value::Value},
+    Request,
+    RequestMethod,
+};
+use super::{Complete, Response, ResponseBuilder, State};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Which origins may read responses.
+pub enum AllowOrigin {
+    /// Any origin, announced as `*`, which browsers refuse together with
+    /// credentials, see [Cors::credentials].
+    Any,
+    /// Any origin, echoed back. With credentials, every site may read
+    /// responses as the user.
+    Echo,
+    /// Only the listed origins, e.g. `https://example.com`, echoed back.
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Cross-origin resource sharing policy.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{Request, RequestMethod, Response, response::{AllowOrigin, Cors}};
+/// let cors = Cors::new(AllowOrigin::List(vec!["https://app.example".to_owned()]))
+///     .methods(&[RequestMethod::Get, RequestMethod::Post]);
+/// let request: Request = "GET /data HTTP/1.1\r\nOrigin: https://app.example\r\n\r\n".parse().unwrap();
+/// let response = cors.apply(Response::Ok.body("{}"), &request);
+/// assert_eq!(response.get_header("access-control-allow-origin").unwrap(), "https://app.example");
+/// assert_eq!(response.get_header("vary").unwrap(), "origin");
+/// ```
+pub struct Cors {
+    origin: AllowOrigin,
+    methods: Vec<RequestMethod>,
+    allow_headers: Vec<Key>,
+    expose_headers: Vec<Key>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+impl Cors {
+    /// A policy for `origin`, allowing only `GET`, `HEAD` and `POST`
+    /// with no extra headers and no credentials.
+    pub fn new(origin: AllowOrigin) -> Self {
+        Self {
+            origin,
+            methods: vec![RequestMethod::Get, RequestMethod::Head, RequestMethod::Post],
+            allow_headers: vec![],
+            expose_headers: vec![],
+            credentials: false,
+            max_age: None,
+        }
+    }
+    /// Sets the methods allowed in preflights.
+    pub fn methods(mut self, methods: &[RequestMethod]) -> Self {
+        self.methods = methods.to_vec();
+        self
+    }
+    /// Allows clients to send `k`.
+    pub fn allow_header(mut self, k: Key) -> Self {
+        self.allow_headers.push(k);
+        self
+    }
+    /// Allows scripts to read `k` from responses.
+    pub fn expose_header(mut self, k: Key) -> Self {
+        self.expose_headers.push(k);
+        self
+    }
+    /// Whether to allow cookies and other credentials.
+    ///
+    /// # Panics
+    /// If `allowed` for a policy of [Any][AllowOrigin::Any] origin. Pick
+    /// the origins with [List][AllowOrigin::List], or, to really let every
+    /// site make credentialed reads, [Echo][AllowOrigin::Echo].
+    pub fn credentials(mut self, allowed: bool) -> Self {
+        assert!(!allowed || self.origin != AllowOrigin::Any, "credentials cannot be allowed for any origin");
+        self.credentials = allowed;
+        self
+    }
+    /// How long browsers may cache a preflight result.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+    /// Whether the `access-control-allow-origin` value depends on the
+    /// request's origin, so caches have to key on it.
+    fn echoes(&self) -> bool {
+        !matches!(self.origin, AllowOrigin::Any)
+    }
+    /// The `access-control-allow-origin` value for `origin`, if allowed.
+    fn allowed_origin<'a>(&self, origin: &'a str) -> Option<&'a str> {
+        match &self.origin {
+            AllowOrigin::Any => Some("*"),
+            AllowOrigin::Echo => Some(origin),
+            AllowOrigin::List(origins) => origins.iter().any(|o| o == origin).then_some(origin),
+        }
+    }
+    /// Adds the headers for an actual (non-preflight) cross-origin request.
+    ///
+    /// Requests without an `origin` header or from a disallowed origin get
+    /// no access-control headers; `vary: origin` is added whenever the
+    /// answer depends on the origin.
+    pub fn apply<S: State, B>(&self, mut builder: ResponseBuilder<S, B>, request: &Request) -> ResponseBuilder<S, B> {
+        self.add_vary(&mut builder);
+        let Some(origin) = request.headers.get("origin").and_then(|o| self.allowed_origin(o.to_str()?)) else {
+            return builder;
+        };
+        let headers = &mut builder.headers;
+        headers.insert(Key::ACCESS_CONTROL_ALLOW_ORIGIN, Value::new(origin).unwrap());
+        if self.credentials {
+            headers.insert(Key::ACCESS_CONTROL_ALLOW_CREDENTIALS, Value::new("true").unwrap());
+        }
+        if let Some(exposed) = join(&self.expose_headers) {
+            headers.insert(Key::ACCESS_CONTROL_EXPOSE_HEADERS, exposed);
+        }
+        builder
+    }
+    /// Answers a preflight request.
+    ///
+    /// Returns `None` if `request` is no preflight, i.e. not an `OPTIONS`
+    /// request with `origin` and `access-control-request-method`. Allowed
+    /// preflights get a [204][Response::NoContent] describing the policy,
+    /// others a bare [403][Response::Forbidden].
+    pub fn preflight(&self, request: &Request) -> Option<ResponseBuilder<Complete>> {
+        if request.method != RequestMethod::Options {
+            return None;
+        }
+        let origin = request.headers.get("origin")?;
+        let method = request.headers.get("access-control-request-method")?;
+        let method_allowed = method.parse::<RequestMethod>()
+            .is_ok_and(|m| self.methods.contains(&m));
+        let headers_allowed = request.headers.get("access-control-request-headers")
+            .is_none_or(|requested| requested.elements()
+                .all(|h| self.allow_headers.iter().any(|k| k.as_str().eq_ignore_ascii_case(h))));
+        let origin = match origin.to_str().and_then(|o| self.allowed_origin(o)) {
+            Some(origin) if method_allowed && headers_allowed => origin,
+            _ => return Some(Response::Forbidden.body("")),
+        };
+        let mut builder = ResponseBuilder::new(Response::NoContent);
+        self.add_vary(&mut builder);
+        let headers = &mut builder.headers;
+        headers.insert(Key::ACCESS_CONTROL_ALLOW_ORIGIN, Value::new(origin).unwrap());
+        let methods = self.methods.iter().map(RequestMethod::as_str).collect::<Vec<_>>().join(", ");
+        headers.insert(Key::ACCESS_CONTROL_ALLOW_METHODS, Value::new(methods).unwrap());
+        if let Some(allowed) = join(&self.allow_headers) {
+            headers.insert(Key::ACCESS_CONTROL_ALLOW_HEADERS, allowed);
+        }
+        if self.credentials {
+            headers.insert(Key::ACCESS_CONTROL_ALLOW_CREDENTIALS, Value::new("true").unwrap());
+        }
+        if let Some(max_age) = self.max_age {
+            headers.insert(Key::ACCESS_CONTROL_MAX_AGE, Value::new(max_age.as_secs().to_string()).unwrap());
+        }
+        Some(builder.body(""))
+    }
+    fn add_vary<S: State, B>(&self, builder: &mut ResponseBuilder<S, B>) {
+        if self.echoes() {
+            // A `vary` already at its limits is left as it is
+            let _ = builder.headers.append_unique(Key::VARY, "origin");
+        }
+    }
+}
+
+fn join(keys: &[Key]) -> Option<Value> {
+    let joined = keys.iter().map(Key::as_str).collect::<Vec<_>>().join(", ");
+    Value::new(joined).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(s: &str) -> Request {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn wildcard() {
+        let cors = Cors::new(AllowOrigin::Any).expose_header(Key::new("X-Total").unwrap());
+        let req = request("GET / HTTP/1.1\r\nOrigin: https://a.example\r\n\r\n");
+        let response = cors.apply(Response::Ok.body("x"), &req);
+        assert_eq!(response.get_header("access-control-allow-origin").unwrap(), "*");
+        assert_eq!(response.get_header("access-control-expose-headers").unwrap(), "x-total");
+        assert_eq!(response.get_header("vary"), None);
+        let with_credentials = Cors::new(AllowOrigin::Echo).credentials(true);
+        let response = with_credentials.apply(Response::Ok.body("x"), &req);
+        assert_eq!(response.get_header("access-control-allow-origin").unwrap(), "https://a.example");
+        assert_eq!(response.get_header("access-control-allow-credentials").unwrap(), "true");
+        assert_eq!(response.get_header("vary").unwrap(), "origin");
+    }
+    #[test]
+    #[should_panic = "credentials cannot be allowed for any origin"]
+    fn wildcard_credentials_refused() {
+        let _ = Cors::new(AllowOrigin::Any).credentials(true);
+    }
+    #[test]
+    fn specific_origin() {
+        let cors = Cors::new(AllowOrigin::List(vec!["https://a.example".to_owned()]))
+            .methods(&[RequestMethod::Get, RequestMethod::Put])
+            .allow_header(Key::CONTENT_TYPE)
+            .max_age(Duration::from_secs(600));
+        let preflight = cors.preflight(&request(
+            "OPTIONS /item HTTP/1.1\r\nOrigin: https://a.example\r\n\
+            Access-Control-Request-Method: PUT\r\nAccess-Control-Request-Headers: Content-Type\r\n\r\n"
+        )).unwrap();
+        assert_eq!(preflight.to_string(), "HTTP/1.0 204 No Content\r\n\
+            vary: origin\r\n\
+            access-control-allow-origin: https://a.example\r\n\
+            access-control-allow-methods: GET, PUT\r\n\
+            access-control-allow-headers: content-type\r\n\
+            access-control-max-age: 600\r\n\r\n");
+        let other = cors.apply(
+            Response::Ok.header("vary", "accept-encoding").unwrap().body(""),
+            &request("GET / HTTP/1.1\r\nOrigin: https://b.example\r\n\r\n"),
+        );
+        assert_eq!(other.get_header("access-control-allow-origin"), None);
+        assert_eq!(other.get_header("vary").unwrap(), "accept-encoding,origin");
+        assert!(cors.preflight(&request("GET / HTTP/1.1\r\nOrigin: https://a.example\r\n\r\n")).is_none());
+    }
+    #[test]
+    fn denied_preflight() {
+        let cors = Cors::new(AllowOrigin::List(vec!["https://a.example".to_owned()]));
+        for req in [
+            "OPTIONS / HTTP/1.1\r\nOrigin: https://evil.example\r\nAccess-Control-Request-Method: GET\r\n\r\n",
+            "OPTIONS / HTTP/1.1\r\nOrigin: https://a.example\r\nAccess-Control-Request-Method: DELETE\r\n\r\n",
+            "OPTIONS / HTTP/1.1\r\nOrigin: https://a.example\r\nAccess-Control-Request-Method: GET\r\n\
+            Access-Control-Request-Headers: x-secret\r\n\r\n",
+        ] {
+            let response = cors.preflight(&request(req)).unwrap();
+            assert_eq!(response.get_status(), &Response::Forbidden, "{req}");
+            assert_eq!(response.get_header("access-control-allow-origin"), None);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::SystemTime;
+
+use crate::{
+    header::{key::Key, value::Value, HeaderError, HeaderMap},
+    Byteable,
+};
+use super::{ResponseBuilder, State};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Headers every response of a service should carry, unless the
+/// response sets them itself.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{Response, response::ResponseDefaults};
+/// let defaults = ResponseDefaults::new().server("myapp/1.2").unwrap();
+/// let response = defaults.apply(Response::Ok.header("x", "y").unwrap());
+/// assert_eq!(response.get_header("server").unwrap(), "myapp/1.2");
+/// ```
+pub struct ResponseDefaults {
+    headers: HeaderMap,
+    date: bool,
+    keep_alive: Option<bool>,
+}
+impl ResponseDefaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a default header. Setting the same key twice replaces the
+    /// earlier default instead of appending to it.
+    pub fn header<K: AsRef<str>, V: AsRef<str>>(mut self, k: K, v: V) -> Result<Self, HeaderError> {
+        self.headers.insert(Key::new(k)?, Value::new(v)?);
+        Ok(self)
+    }
+    /// Sets the default `server` header.
+    pub fn server<V: AsRef<str>>(self, v: V) -> Result<Self, HeaderError> {
+        self.header("server", v)
+    }
+    /// Whether to add a `date` header with the current time.
+    pub fn date(mut self, enabled: bool) -> Self {
+        self.date = enabled;
+        self
+    }
+    /// The keep-alive disposition to announce through the `connection`
+    /// header when the response does not decide itself. As with
+    /// [ResponseBuilder::connection], the header is only written when the
+    /// disposition differs from the default of the response's version.
+    pub fn connection(mut self, keep_alive: bool) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+    /// Adds every default whose key `builder` does not have yet.
+    /// Values set on the builder are never changed or appended to.
+    pub fn apply<S: State, B: AsRef<[u8]>>(&self, builder: ResponseBuilder<S, B>) -> ResponseBuilder<S, B> {
+        self.apply_at(builder, SystemTime::now())
+    }
+    fn apply_at<S: State, B: AsRef<[u8]>>(&self, mut builder: ResponseBuilder<S, B>, now: SystemTime) -> ResponseBuilder<S, B> {
+        for (k, v) in self.headers.iter() {
+            if !builder.headers.contains_key(k.to_string()) {
+                builder.headers.insert(k.clone(), v.clone());
+            }
+        }
+        if self.date && !builder.headers.contains_key("date") {
+            let date = crate::header::date::format(now);
+            builder.headers.insert(Key::DATE, Value::new(date).unwrap());
+        }
+        if let (Some(keep_alive), false) = (self.keep_alive, builder.headers.contains_key("connection")) {
+            let version = builder.max_version();
+            if keep_alive != version.supports_persistent_by_default() {
+                let v = if keep_alive { "keep-alive" } else { "close" };
+                builder.headers.insert(Key::CONNECTION, Value::new(v).unwrap());
+            }
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::Response;
+    use super::*;
+
+    #[test]
+    fn defaults_applied() {
+        let defaults = ResponseDefaults::new()
+            .server("myapp/1.2").unwrap()
+            .header("x-frame-options", "DENY").unwrap();
+        let response = defaults.apply(Response::Ok.header("a", "b").unwrap().body("hi"));
+        assert_eq!(response.to_string(),
+            "HTTP/1.0 200 OK\r\na: b\r\nserver: myapp/1.2\r\nx-frame-options: DENY\r\n\r\nhi");
+    }
+    #[test]
+    fn defaults_do_not_clobber() {
+        let defaults = ResponseDefaults::new()
+            .server("myapp/1.2").unwrap()
+            .connection(false);
+        let response = defaults.apply(
+            Response::Ok.header("Server", "special").unwrap()
+                .header("connection", "upgrade").unwrap()
+                .header("host", "example.com").unwrap()
+        );
+        assert_eq!(response.get_header("server").unwrap(), "special");
+        assert_eq!(response.get_header("connection").unwrap(), "upgrade");
+    }
+    #[test]
+    fn defaults_with_date() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        let defaults = ResponseDefaults::new().server("myapp").unwrap().date(true).connection(false);
+        let response = defaults.apply_at(
+            Response::Ok.header("host", "example.com").unwrap(),
+            now,
+        );
+        assert_eq!(response.get_header("server").unwrap(), "myapp");
+        assert_eq!(response.get_header("date").unwrap(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(response.get_header("connection").unwrap(), "close");
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    header::{key::Key, qvalue, QValue, Value},
+    Request,
+};
+use super::{Complete, Response, ResponseBuilder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Content codings the crate can apply to a body.
+pub enum Encoding {
+    Identity,
+    #[cfg(feature = "gzip")]
+    Gzip,
+}
+impl Encoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            #[cfg(feature = "gzip")]
+            Self::Gzip => "gzip",
+        }
+    }
+}
+
+/// The quality the client's `accept-encoding` assigns to `coding`, or
+/// `None` if the coding is not mentioned, not even through `*`.
+/// An invalid weight counts as zero.
+fn quality(accepted: &Value, coding: &str) -> Option<QValue> {
+    let mut wildcard = None;
+    for element in accepted.elements() {
+        let (name, q) = qvalue::split(element);
+        let q = q.unwrap_or(QValue::ZERO);
+        if name.eq_ignore_ascii_case(coding) || (coding == "gzip" && name.eq_ignore_ascii_case("x-gzip")) {
+            return Some(q);
+        }
+        if name == "*" {
+            wildcard = Some(q);
+        }
+    }
+    wildcard
+}
+
+/// Picks the coding of `available` the client prefers, following the
+/// `accept-encoding` rules of RFC 9110.
+///
+/// Ties go to the coding listed first in `available`. Identity is
+/// acceptable unless excluded by `identity;q=0` or `*;q=0`, but loses
+/// to any explicitly accepted coding. `None` means nothing in `available`
+/// is acceptable, which calls for a [406][Response::NotAcceptable].
+pub fn negotiate_encoding(request: &Request, available: &[Encoding]) -> Option<Encoding> {
+    let Some(accepted) = request.headers.get("accept-encoding") else {
+        return available.iter().copied()
+            .find(|&e| e == Encoding::Identity)
+            .or_else(|| available.first().copied());
+    };
+    // Ranked by whether the coding was asked for, then by weight
+    let mut best: Option<(Encoding, (bool, QValue))> = None;
+    for &encoding in available {
+        let rank = match (quality(accepted, encoding.as_str()), encoding) {
+            (Some(q), _) => (true, q),
+            // Implicitly acceptable, but below anything asked for
+            (None, Encoding::Identity) => (false, QValue::ONE),
+            #[cfg(feature = "gzip")]
+            (None, _) => (false, QValue::ZERO),
+        };
+        if !rank.1.is_zero() && best.is_none_or(|(_, best_rank)| rank > best_rank) {
+            best = Some((encoding, rank));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+impl ResponseBuilder<Complete> {
+    /// Encodes the body with the best coding of `available` for `request`,
+    /// setting `content-encoding` and `vary: accept-encoding`.
+    ///
+    /// Bodies that already have a `content-encoding` are left alone. If
+    /// none of `available` is acceptable, [NotAcceptable][Response::NotAcceptable]
+    /// is returned for the caller to answer with.
+    pub fn encode_for(mut self, request: &Request, available: &[Encoding]) -> Result<Self, Response> {
+        if self.headers.contains_key("content-encoding") {
+            return Ok(self);
+        }
+        match negotiate_encoding(request, available).ok_or(Response::NotAcceptable)? {
+            Encoding::Identity => {
+                // A `vary` already at its limits is left as it is
+                let _ = self.headers.append_unique(Key::VARY, "Accept-Encoding");
+                Ok(self)
+            },
+            #[cfg(feature = "gzip")]
+            Encoding::Gzip => Ok(self.compress_gzip()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(accept_encoding: &str) -> Request {
+        format!("GET / HTTP/1.1\r\nAccept-Encoding: {accept_encoding}\r\n\r\n").parse().unwrap()
+    }
+
+    #[test]
+    fn identity_by_default() {
+        let plain: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(negotiate_encoding(&plain, &[Encoding::Identity]), Some(Encoding::Identity));
+        assert_eq!(negotiate_encoding(&request("br"), &[Encoding::Identity]), Some(Encoding::Identity));
+        let response = Response::Ok.body("text").encode_for(&request("br"), &[Encoding::Identity]).unwrap();
+        assert_eq!(response.get_header("vary").unwrap(), "Accept-Encoding");
+        assert_eq!(response.get_header("content-encoding"), None);
+    }
+    #[test]
+    fn identity_excluded() {
+        for header in ["identity;q=0", "*;q=0", "br, *;q=0"] {
+            assert_eq!(negotiate_encoding(&request(header), &[Encoding::Identity]), None, "{header}");
+            assert_eq!(Response::Ok.body("x").encode_for(&request(header), &[Encoding::Identity]),
+                Err(Response::NotAcceptable));
+        }
+        assert_eq!(negotiate_encoding(&request("*;q=0, identity"), &[Encoding::Identity]), Some(Encoding::Identity));
+    }
+    #[test]
+    fn encoded_bodies_untouched() {
+        let response = Response::Ok.header("content-encoding", "br").unwrap().body("x")
+            .encode_for(&request("identity;q=0"), &[Encoding::Identity])
+            .unwrap();
+        assert_eq!(response.get_header("vary"), None);
+    }
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn prefers_gzip() {
+        use std::io::Read;
+        use flate2::read::GzDecoder;
+
+        let req = request("gzip;q=1.0, identity;q=0.5");
+        let available = [Encoding::Identity, Encoding::Gzip];
+        assert_eq!(negotiate_encoding(&req, &available), Some(Encoding::Gzip));
+        assert_eq!(negotiate_encoding(&request("gzip;q=0.1"), &available), Some(Encoding::Gzip));
+        assert_eq!(negotiate_encoding(&request("gzip;q=0"), &available), Some(Encoding::Identity));
+        let response = Response::Ok.body("hello").encode_for(&req, &available).unwrap();
+        assert_eq!(response.get_header("content-encoding").unwrap(), "gzip");
+        let mut body = String::new();
+        GzDecoder::new(response.get_body()).read_to_string(&mut body).unwrap();
+        assert_eq!(body, "hello");
+    }
+}
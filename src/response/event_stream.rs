@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+
+use crate::Version;
+use super::Response;
+
+/// Server-sent events (`text/event-stream`) written to `W`.
+///
+/// The response head is sent on creation. HTTP/1.1 clients get the events
+/// chunked, HTTP/1.0 clients a body delimited by closing the connection.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{response::EventStream, Version};
+/// let mut stream = EventStream::new(Vec::new(), Version(1, 0)).unwrap();
+/// stream.send_event(Some("tick"), "1", None).unwrap();
+/// let written = String::from_utf8(stream.finish().unwrap()).unwrap();
+/// assert!(written.ends_with("\r\n\r\nevent: tick\ndata: 1\n\n"));
+/// ```
+pub struct EventStream<W: Write> {
+    writer: W,
+    chunked: bool,
+}
+impl<W: Write> EventStream<W> {
+    /// Writes the response head for a client speaking `version`.
+    pub fn new(mut writer: W, version: Version) -> io::Result<Self> {
+        let chunked = version.supports_chunked();
+        let head = Response::Ok
+            .header("content-type", "text/event-stream")
+            .and_then(|b| b.header("cache-control", "no-store"))
+            .and_then(|b| b.header("transfer-encoding", "chunked"))
+            .expect("static headers are valid")
+            .body("")
+            .version(if chunked { Version::HTTP_11 } else { Version::HTTP_10 });
+        head.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(Self { writer, chunked })
+    }
+    /// Sends one event. Line breaks in `data` (`\r\n`, `\r` or `\n`) split
+    /// it into several `data:` lines, which the client joins with `\n`.
+    ///
+    /// `event` and `id` may not contain line breaks.
+    pub fn send_event(&mut self, event: Option<&str>, data: &str, id: Option<&str>) -> io::Result<()> {
+        let mut message = String::new();
+        for (field, value) in [("event", event), ("id", id)] {
+            let Some(value) = value else { continue };
+            if value.contains(['\r', '\n']) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("line break in {field}")));
+            }
+            message.push_str(&format!("{field}: {value}\n"));
+        }
+        for line in data.replace("\r\n", "\n").split(['\r', '\n']) {
+            message.push_str("data: ");
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push('\n');
+        self.send(message.as_bytes())
+    }
+    /// Sends a comment, which clients ignore. Useful to keep idle
+    /// connections from timing out.
+    pub fn send_comment(&mut self, comment: &str) -> io::Result<()> {
+        let mut message = String::new();
+        for line in comment.replace("\r\n", "\n").split(['\r', '\n']) {
+            message.push_str(": ");
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push('\n');
+        self.send(message.as_bytes())
+    }
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        if self.chunked {
+            write!(self.writer, "{:x}\r\n", message.len())?;
+            self.writer.write_all(message)?;
+            self.writer.write_all(b"\r\n")?;
+        } else {
+            self.writer.write_all(message)?;
+        }
+        self.writer.flush()
+    }
+    /// Ends the stream, returning the writer. A close-delimited stream
+    /// still needs the connection to be closed.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.chunked {
+            self.writer.write_all(b"0\r\n\r\n")?;
+            self.writer.flush()?;
+        }
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunked_two_line_data() {
+        let mut stream = EventStream::new(Vec::new(), Version(1, 1)).unwrap();
+        stream.send_event(Some("update"), "first\r\nsecond", Some("7")).unwrap();
+        stream.send_comment("ping").unwrap();
+        let written = String::from_utf8(stream.finish().unwrap()).unwrap();
+        assert_eq!(written, "HTTP/1.1 200 OK\r\n\
+            content-type: text/event-stream\r\n\
+            cache-control: no-store\r\n\
+            transfer-encoding: chunked\r\n\r\n\
+            2e\r\nevent: update\nid: 7\ndata: first\ndata: second\n\n\r\n\
+            8\r\n: ping\n\n\r\n\
+            0\r\n\r\n");
+    }
+    #[test]
+    fn close_delimited_for_http10() {
+        let mut stream = EventStream::new(Vec::new(), Version(1, 0)).unwrap();
+        stream.send_event(None, "a\rb\n", None).unwrap();
+        let written = String::from_utf8(stream.finish().unwrap()).unwrap();
+        assert_eq!(written, "HTTP/1.0 200 OK\r\n\
+            content-type: text/event-stream\r\n\
+            cache-control: no-store\r\n\r\n\
+            data: a\ndata: b\ndata: \n\n");
+    }
+    #[test]
+    fn rejects_line_break_in_event() {
+        let mut stream = EventStream::new(Vec::new(), Version(1, 1)).unwrap();
+        let err = stream.send_event(Some("a\nb"), "x", None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
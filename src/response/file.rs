@@ -0,0 +1,142 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    header::{date, key::Key, value::Value, ByteRange},
+    Request,
+    RequestMethod,
+};
+use super::{Complete, Response, ResponseBuilder};
+
+/// Most parts a multipart answer to a `range` header has after merging;
+/// more are answered with the whole file.
+const MAX_RANGE_PARTS: usize = 16;
+
+/// The media type for a file extension, ignoring case, and
+/// `application/octet-stream` for unknown ones.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::response::mime_type;
+/// assert_eq!(mime_type("HTML"), "text/html; charset=utf-8");
+/// assert_eq!(mime_type("bin"), "application/octet-stream");
+/// ```
+pub fn mime_type(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+impl Response {
+    /// Answers `request` with the file at `path`.
+    ///
+    /// The response carries a `content-type` guessed by [mime_type],
+    /// `last-modified` and `accept-ranges: bytes`. A `GET` or `HEAD` with
+    /// an `if-modified-since` no older than the file is answered with a
+    /// [304][Response::NotModified], and a `GET` with a `range` header
+    /// like [multipart_byteranges][Response::multipart_byteranges]
+    /// does, after overlapping and adjacent ranges are merged. A range
+    /// header asking for more than 16 separate parts is ignored. A `HEAD`
+    /// gets the head of the whole file's response without the file being
+    /// read. Fails if the file cannot be read, e.g. with
+    /// [NotFound][io::ErrorKind::NotFound].
+    ///
+    /// Nothing keeps `path` within a directory, see
+    /// [static_files][crate::server::static_files] for that.
+    pub fn from_file<P: AsRef<Path>>(path: P, request: &Request) -> io::Result<ResponseBuilder<Complete>> {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok().map(whole_seconds);
+        let content_type = mime_type(path.extension().and_then(|e| e.to_str()).unwrap_or_default());
+        let read = matches!(request.method, RequestMethod::Get | RequestMethod::Head);
+        let unmodified = modified.zip(request.if_modified_since())
+            .is_some_and(|(modified, since)| modified <= since);
+        // if-modified-since is ignored in the presence of if-none-match
+        if read && unmodified && request.headers.get("if-none-match").is_none() {
+            let mut response = Response::NotModified.body("");
+            set_last_modified(&mut response, modified);
+            return Ok(response);
+        }
+        let mut response = if request.method == RequestMethod::Head {
+            let mut response = ResponseBuilder::new(Response::Ok).body(Vec::new());
+            response.headers.insert(Key::CONTENT_LENGTH, Value::new(metadata.len().to_string()).unwrap());
+            response
+        } else {
+            let body = fs::read(path)?;
+            let ranges = request.headers.get("range")
+                .filter(|_| request.method == RequestMethod::Get)
+                .and_then(|range| ByteRange::parse_header(&range.to_string()).ok())
+                .map(|ranges| coalesce(&ranges, body.len() as u64))
+                .filter(|ranges| ranges.len() <= MAX_RANGE_PARTS);
+            match ranges {
+                // The content type is valid
+                Some(ranges) => Response::multipart_byteranges(&body, &ranges, content_type).unwrap(),
+                None => ResponseBuilder::new(Response::Ok).content_length(body.len()).body(body),
+            }
+        };
+        // A multipart body has its own type, a 416 no content
+        if response.get_header("content-type").is_none() && response.get_status() != &Response::RangeNotSatisfiable {
+            response.headers.insert(Key::CONTENT_TYPE, Value::new(content_type).unwrap());
+        }
+        response.headers.insert(Key::ACCEPT_RANGES, Value::new("bytes").unwrap());
+        set_last_modified(&mut response, modified);
+        Ok(response)
+    }
+}
+
+/// The satisfiable `ranges` of a representation of `len` bytes in
+/// ascending order, overlapping and adjacent ones merged.
+fn coalesce(ranges: &[ByteRange], len: u64) -> Vec<ByteRange> {
+    let mut resolved = ranges.iter()
+        .filter_map(|range| range.resolve(len))
+        .map(|range| (*range.start(), *range.end()))
+        .collect::<Vec<_>>();
+    resolved.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (first, last) in resolved {
+        match merged.last_mut() {
+            Some((_, end)) if first <= end.saturating_add(1) => *end = last.max(*end),
+            _ => merged.push((first, last)),
+        }
+    }
+    merged.into_iter().map(|(first, last)| ByteRange::FromTo(first, last)).collect()
+}
+
+/// HTTP-dates have no fractions, so comparisons are done in seconds.
+fn whole_seconds(time: SystemTime) -> SystemTime {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn set_last_modified(response: &mut ResponseBuilder<Complete>, modified: Option<SystemTime>) {
+    if let Some(modified) = modified {
+        response.headers.insert(Key::LAST_MODIFIED, Value::new(date::format(modified)).unwrap());
+    }
+}
@@ -0,0 +1,145 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    header::{key::Key, value::Value},
+    Request,
+};
+use super::{negotiate_encoding, Complete, Encoding, ResponseBuilder};
+
+/// Bodies shorter than this many bytes are not worth compressing
+/// when using [`ResponseBuilder::compress_gzip_for`].
+pub const GZIP_THRESHOLD: usize = 256;
+
+impl ResponseBuilder<Complete> {
+    /// Compresses the body with gzip.
+    ///
+    /// Sets `content-encoding: gzip`, replaces `content-length` with the
+    /// compressed length and adds `Accept-Encoding` to the `vary` header.
+    /// Bodies that already carry a `content-encoding` are left untouched.
+    pub fn compress_gzip(mut self) -> Self {
+        if self.headers.contains_key("content-encoding") {
+            return self;
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        // Writing into a `Vec` cannot fail
+        encoder.write_all(&self.body).unwrap();
+        self.body = encoder.finish().unwrap();
+
+        self.headers.insert(Key::CONTENT_ENCODING, Value::new("gzip").unwrap());
+        self.headers.insert(
+            Key::CONTENT_LENGTH,
+            Value::new(self.body.len().to_string()).unwrap(),
+        );
+        // A `vary` already at its limits is left as it is
+        let _ = self.headers.append_unique(Key::VARY, "Accept-Encoding");
+        self
+    }
+    /// Compresses the body with gzip if [negotiate_encoding] prefers it
+    /// over identity for `request` and the body is at least
+    /// [`GZIP_THRESHOLD`] bytes long.
+    ///
+    /// Bodies left uncompressed because of the client's `accept-encoding`
+    /// get `Accept-Encoding` in `vary` as well, like
+    /// [encode_for][Self::encode_for] adds it.
+    pub fn compress_gzip_for(mut self, request: &Request) -> Self {
+        if self.body.len() < GZIP_THRESHOLD || self.headers.contains_key("content-encoding") {
+            return self;
+        }
+        match negotiate_encoding(request, &[Encoding::Gzip, Encoding::Identity]) {
+            Some(Encoding::Gzip) => self.compress_gzip(),
+            _ => {
+                // A `vary` already at its limits is left as it is
+                let _ = self.headers.append_unique(Key::VARY, "Accept-Encoding");
+                self
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use crate::{Byteable, Response};
+    use super::*;
+
+    fn gunzip(bytes: &[u8]) -> Vec<u8> {
+        let mut out = vec![];
+        GzDecoder::new(bytes).read_to_end(&mut out).unwrap();
+        out
+    }
+    fn split_body(bytes: &[u8]) -> &[u8] {
+        let pos = bytes.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        &bytes[pos + 4..]
+    }
+
+    #[test]
+    fn roundtrip_body() {
+        let body = "some text ".repeat(100);
+        let res = Response::Ok.body(body.clone()).compress_gzip();
+        assert_eq!(res.headers.get("content-encoding").unwrap(), "gzip");
+        assert_eq!(
+            res.headers.get("content-length").unwrap(),
+            &res.body.len().to_string()
+        );
+        let bytes = res.into_bytes();
+        assert_eq!(gunzip(split_body(&bytes)), body.as_bytes());
+    }
+    #[test]
+    fn vary_appended() {
+        let res = Response::Ok
+            .header("Vary", "Origin").unwrap()
+            .body("x")
+            .compress_gzip();
+        assert_eq!(res.headers.get("vary").unwrap(), "Origin,Accept-Encoding");
+    }
+    #[test]
+    fn vary_not_duplicated() {
+        let res = Response::Ok
+            .header("Vary", "accept-encoding").unwrap()
+            .body("x")
+            .compress_gzip();
+        assert_eq!(res.headers.get("vary").unwrap(), "accept-encoding");
+    }
+    #[test]
+    fn full_vary_kept() {
+        let full = (0..crate::header::MAX_VALUE_ELEMENTS).map(|i| format!("x{i}")).collect::<Vec<_>>().join(",");
+        let res = Response::Ok.header("Vary", &full).unwrap().body("x").compress_gzip();
+        assert_eq!(res.headers.get("vary").unwrap(), full.as_str());
+        assert_eq!(res.headers.get("content-encoding").unwrap(), "gzip");
+    }
+    #[test]
+    fn request_without_gzip_untouched() {
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: br, gzip;q=0\r\n\r\n".parse().unwrap();
+        let body = "a".repeat(GZIP_THRESHOLD);
+        let res = Response::Ok.body(body.clone()).compress_gzip_for(&request);
+        assert_eq!(res.body, body.as_bytes());
+        assert_eq!(res.headers.get("vary").unwrap(), "Accept-Encoding");
+    }
+    #[test]
+    fn refused_gzip_not_overridden_by_wildcard() {
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: gzip;q=0, *\r\n\r\n".parse().unwrap();
+        let body = "a".repeat(GZIP_THRESHOLD);
+        let res = Response::Ok.body(body.clone()).compress_gzip_for(&request);
+        assert_eq!(res.body, body.as_bytes());
+        assert!(!res.headers.contains_key("content-encoding"));
+    }
+    #[test]
+    fn request_with_gzip_compressed() {
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: deflate, gzip\r\n\r\n".parse().unwrap();
+        let body = "a".repeat(GZIP_THRESHOLD);
+        let res = Response::Ok.body(body.clone()).compress_gzip_for(&request);
+        assert_eq!(gunzip(&res.body), body.as_bytes());
+    }
+    #[test]
+    fn small_body_untouched() {
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n".parse().unwrap();
+        let res = Response::Ok.body("short").compress_gzip_for(&request);
+        assert_eq!(res.body, b"short");
+        assert!(!res.headers.contains_key("content-encoding"));
+    }
+}
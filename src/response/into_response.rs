@@ -0,0 +1,105 @@
+use super::{Complete, Incomplete, Response, ResponseBuilder};
+
+/// Values a request handler can return, turned into a complete response.
+///
+/// Plain bodies become a [200][Response::Ok], a bare status gets an empty
+/// body, and both sides of a [Result] answer for themselves.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{response::IntoResponse, Response, ResponseCode};
+/// fn handler(id: u32) -> Result<String, Response> {
+///     match id {
+///         1 => Ok("first".to_owned()),
+///         _ => Err(Response::NotFound),
+///     }
+/// }
+/// assert_eq!(handler(1).into_response().body_len(), 5);
+/// assert_eq!(handler(2).into_response().get_status().code(), 404);
+/// ```
+pub trait IntoResponse {
+    fn into_response(self) -> ResponseBuilder<Complete>;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        self.body("")
+    }
+}
+
+impl IntoResponse for ResponseBuilder<Incomplete> {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        self.body("")
+    }
+}
+
+impl IntoResponse for ResponseBuilder<Complete> {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        self
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        Response::Ok.body(self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        Response::Ok.body(self)
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        Response::Ok.body(self)
+    }
+}
+
+impl<B: Into<Vec<u8>>> IntoResponse for (Response, B) {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        self.0.body(self.1)
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        match self {
+            Ok(t) => t.into_response(),
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ResponseCode;
+    use super::*;
+
+    fn parts<R: IntoResponse>(r: R) -> (u16, Vec<u8>) {
+        let response = r.into_response();
+        (response.get_status().code(), response.get_body().to_vec())
+    }
+
+    #[test]
+    fn statuses() {
+        assert_eq!(parts(Response::NoContent), (204, vec![]));
+        assert_eq!(parts(Response::Created.header("location", "/1").unwrap()), (201, vec![]));
+        assert_eq!(parts(Response::Gone.body("bye")), (410, b"bye".to_vec()));
+    }
+    #[test]
+    fn bodies() {
+        assert_eq!(parts("text"), (200, b"text".to_vec()));
+        assert_eq!(parts(String::from("owned")), (200, b"owned".to_vec()));
+        assert_eq!(parts(vec![0u8, 255]), (200, vec![0, 255]));
+        assert_eq!(parts((Response::Accepted, "queued")), (202, b"queued".to_vec()));
+    }
+    #[test]
+    fn results() {
+        let ok: Result<&str, Response> = Ok("fine");
+        let err: Result<&str, (Response, String)> = Err((Response::BadRequest, "no".to_owned()));
+        assert_eq!(parts(ok), (200, b"fine".to_vec()));
+        assert_eq!(parts(err), (400, b"no".to_vec()));
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::header::{key::Key, value::Value, ByteRange, ContentRange, HeaderError};
+use super::{Complete, Response, ResponseBuilder};
+
+/// A boundary unlikely to appear in any body: a process-wide counter
+/// mixed with the current time.
+fn boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64;
+    format!("{nanos:016x}{count:08x}")
+}
+
+impl Response {
+    /// Answers a request for several `ranges` of `body`.
+    ///
+    /// If more than one range is satisfiable, the result is a
+    /// [206][Response::PartialContent] with a `multipart/byteranges` body,
+    /// each part carrying `content_type` and its own `content-range`.
+    /// Unsatisfiable ranges are skipped. A single satisfiable range is
+    /// answered like [Response::partial_content], none at all with a
+    /// [416][Response::RangeNotSatisfiable].
+    pub fn multipart_byteranges<T: AsRef<str>>(
+        body: &[u8],
+        ranges: &[ByteRange],
+        content_type: T,
+    ) -> Result<ResponseBuilder<Complete>, HeaderError> {
+        let len = body.len() as u64;
+        let resolved = ranges.iter()
+            .filter_map(|r| r.resolve(len).map(|resolved| (r, resolved)))
+            .collect::<Vec<_>>();
+        match resolved.as_slice() {
+            [] => return Ok(Response::partial_content(body, &ByteRange::From(len))),
+            [(range, _)] => return Ok(Response::partial_content(body, range)),
+            _ => {},
+        }
+        let content_type = Value::new(content_type)?;
+        let boundary = boundary();
+        let mut multipart = vec![];
+        for (_, range) in resolved {
+            let (first, last) = (*range.start(), *range.end());
+            let content_range = ContentRange::Bytes { first, last, complete_length: Some(len) };
+            multipart.extend(format!(
+                "--{boundary}\r\ncontent-type: {content_type}\r\ncontent-range: {content_range}\r\n\r\n"
+            ).into_bytes());
+            multipart.extend_from_slice(&body[first as usize..=last as usize]);
+            multipart.extend(b"\r\n");
+        }
+        multipart.extend(format!("--{boundary}--\r\n").into_bytes());
+
+        let mut builder = ResponseBuilder::new(Response::PartialContent);
+        builder.headers.insert(
+            Key::CONTENT_TYPE,
+            Value::new(format!("multipart/byteranges; boundary={boundary}")).unwrap(),
+        );
+        Ok(builder.content_length(multipart.len()).body(multipart))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ResponseCode;
+    use super::*;
+
+    /// Splits a multipart body into the headers and content of each part.
+    fn parse_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<(String, &'a [u8])> {
+        let delimiter = format!("--{boundary}");
+        let mut parts = vec![];
+        let mut rest = body;
+        loop {
+            assert!(rest.starts_with(delimiter.as_bytes()));
+            rest = &rest[delimiter.len()..];
+            if rest.starts_with(b"--") {
+                return parts;
+            }
+            rest = &rest[2..];
+            let head_end = rest.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+            let head = String::from_utf8(rest[..head_end].to_vec()).unwrap();
+            rest = &rest[head_end + 4..];
+            let next = format!("\r\n{delimiter}");
+            let end = rest.windows(next.len()).position(|w| w == next.as_bytes()).unwrap();
+            parts.push((head, &rest[..end]));
+            rest = &rest[end + 2..];
+        }
+    }
+
+    #[test]
+    fn two_ranges_recovered() {
+        let body = b"abcdefghijklmnopqrstuvwxyz";
+        let response = Response::multipart_byteranges(
+            body,
+            &[ByteRange::FromTo(0, 2), ByteRange::Suffix(3), ByteRange::From(100)],
+            "text/plain",
+        ).unwrap();
+        assert_eq!(response.code(), 206);
+        let content_type = response.get_header("content-type").unwrap().to_string();
+        let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+        assert_eq!(response.get_header("content-length").unwrap(), &response.body.len().to_string());
+
+        let parts = parse_multipart(&response.body, boundary);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0, "content-type: text/plain\r\ncontent-range: bytes 0-2/26");
+        assert_eq!(parts[0].1, b"abc");
+        assert_eq!(parts[1].0, "content-type: text/plain\r\ncontent-range: bytes 23-25/26");
+        assert_eq!(parts[1].1, b"xyz");
+    }
+    #[test]
+    fn single_range_is_plain() {
+        let response = Response::multipart_byteranges(b"abcdef", &[ByteRange::FromTo(1, 2)], "text/plain").unwrap();
+        assert_eq!(response.get_header("content-range").unwrap(), "bytes 1-2/6");
+        assert_eq!(response.body, b"bc");
+    }
+    #[test]
+    fn no_range_satisfiable() {
+        let response = Response::multipart_byteranges(b"abcdef", &[ByteRange::From(6)], "text/plain").unwrap();
+        assert_eq!(response.code(), 416);
+    }
+    #[test]
+    fn boundaries_differ() {
+        assert_ne!(boundary(), boundary());
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt::Write;
+
+use super::{Complete, IntoResponse, Response, ResponseBuilder, ResponseCode};
+
+#[derive(Debug, Clone, PartialEq)]
+/// An `application/problem+json` error body (RFC 9457, formerly RFC 7807),
+/// started with [Response::problem].
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::Response;
+/// let response = Response::Forbidden.problem("Out of credit")
+///     .detail("Your balance is 30, but that costs 50.")
+///     .build();
+/// assert_eq!(response.get_header("content-type").unwrap(), "application/problem+json");
+/// assert_eq!(response.get_body(), br#"{"title":"Out of credit","status":403,"detail":"Your balance is 30, but that costs 50."}"#);
+/// ```
+pub struct ProblemBuilder {
+    response: Response,
+    problem_type: Option<String>,
+    title: String,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Vec<(String, String)>,
+}
+impl ProblemBuilder {
+    /// Sets the `type` URI identifying the problem. Left out, it means `about:blank`.
+    pub fn problem_type<S: Into<String>>(mut self, uri: S) -> Self {
+        self.problem_type = Some(uri.into());
+        self
+    }
+    /// Replaces the status code of both the response and the `status` member.
+    pub fn status(mut self, response: Response) -> Self {
+        self.response = response;
+        self
+    }
+    pub fn detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+    /// Sets the `instance` URI identifying this occurrence of the problem.
+    pub fn instance<S: Into<String>>(mut self, uri: S) -> Self {
+        self.instance = Some(uri.into());
+        self
+    }
+    /// Adds an extension member with a string value.
+    pub fn extension<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.extensions.push((key.into(), value.into()));
+        self
+    }
+    /// The response carrying the problem as its body.
+    pub fn build(self) -> ResponseBuilder<Complete> {
+        let mut json = String::from("{");
+        if let Some(problem_type) = &self.problem_type {
+            push_member(&mut json, "type", problem_type);
+            json.push(',');
+        }
+        push_member(&mut json, "title", &self.title);
+        // Writing into a `String` cannot fail
+        write!(json, ",\"status\":{}", self.response.code()).unwrap();
+        let members = [("detail", &self.detail), ("instance", &self.instance)];
+        let members = members.into_iter().filter_map(|(k, v)| Some((k, v.as_deref()?)))
+            .chain(self.extensions.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        for (key, value) in members {
+            json.push(',');
+            push_member(&mut json, key, value);
+        }
+        json.push('}');
+        ResponseBuilder::new(self.response)
+            .header("content-type", "application/problem+json")
+            .unwrap()
+            .body(json)
+    }
+}
+impl IntoResponse for ProblemBuilder {
+    fn into_response(self) -> ResponseBuilder<Complete> {
+        self.build()
+    }
+}
+
+impl Response {
+    /// Starts a problem details body with the given `title`, with the
+    /// `status` member taken from this code.
+    pub fn problem(self, title: &str) -> ProblemBuilder {
+        ProblemBuilder {
+            response: self,
+            problem_type: None,
+            title: title.to_owned(),
+            detail: None,
+            instance: None,
+            extensions: vec![],
+        }
+    }
+}
+
+fn push_member(json: &mut String, key: &str, value: &str) {
+    push_json_string(json, key);
+    json.push(':');
+    push_json_string(json, value);
+}
+
+/// Writes `s` as a JSON string literal.
+fn push_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn not_found_problem() {
+        let response = Response::NotFound.problem("Item not found")
+            .problem_type("https://example.com/probs/missing")
+            .detail("No item called \"lamp\"")
+            .instance("/items/lamp")
+            .extension("trace", "a\\b\u{1}")
+            .build();
+        assert_eq!(response.get_status(), &Response::NotFound);
+        assert_eq!(std::str::from_utf8(response.get_body()).unwrap(), concat!(
+            r#"{"type":"https://example.com/probs/missing","title":"Item not found","status":404,"#,
+            r#""detail":"No item called \"lamp\"","instance":"/items/lamp","trace":"a\\b\u0001"}"#,
+        ));
+    }
+    #[test]
+    fn status_override() {
+        let response = Response::BadRequest.problem("Slow down").status(Response::TooManyRequests).into_response();
+        assert_eq!(response.get_status(), &Response::TooManyRequests);
+        assert_eq!(response.get_body(), br#"{"title":"Slow down","status":429}"#);
+    }
+}
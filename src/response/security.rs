@@ -0,0 +1,180 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    time::Duration,
+};
+
+use crate::header::{key::Key, value::Value, ValueError};
+use super::{ResponseBuilder, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Values of the `x-frame-options` header.
+pub enum FrameOptions {
+    Deny,
+    SameOrigin,
+}
+impl Display for FrameOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::Deny => "DENY",
+            Self::SameOrigin => "SAMEORIGIN",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Values of the `referrer-policy` header.
+pub enum ReferrerPolicy {
+    NoReferrer,
+    NoReferrerWhenDowngrade,
+    Origin,
+    OriginWhenCrossOrigin,
+    SameOrigin,
+    StrictOrigin,
+    StrictOriginWhenCrossOrigin,
+    UnsafeUrl,
+}
+impl Display for ReferrerPolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::NoReferrer => "no-referrer",
+            Self::NoReferrerWhenDowngrade => "no-referrer-when-downgrade",
+            Self::Origin => "origin",
+            Self::OriginWhenCrossOrigin => "origin-when-cross-origin",
+            Self::SameOrigin => "same-origin",
+            Self::StrictOrigin => "strict-origin",
+            Self::StrictOriginWhenCrossOrigin => "strict-origin-when-cross-origin",
+            Self::UnsafeUrl => "unsafe-url",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A set of security headers to add with
+/// [with_security_headers][ResponseBuilder::with_security_headers].
+///
+/// The default sends HSTS for one year including subdomains, `nosniff`,
+/// `x-frame-options: DENY` and `strict-origin-when-cross-origin`, but no
+/// content security policy.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{Response, response::{FrameOptions, SecurityPreset}};
+/// let preset = SecurityPreset::default()
+///     .frame_options(Some(FrameOptions::SameOrigin))
+///     .content_security_policy("default-src 'self'").unwrap();
+/// let response = Response::Ok.body("").with_security_headers(preset);
+/// assert_eq!(response.get_header("x-frame-options").unwrap(), "SAMEORIGIN");
+/// ```
+pub struct SecurityPreset {
+    hsts: Option<(Duration, bool)>,
+    nosniff: bool,
+    frame_options: Option<FrameOptions>,
+    referrer_policy: Option<ReferrerPolicy>,
+    content_security_policy: Option<Value>,
+}
+impl Default for SecurityPreset {
+    fn default() -> Self {
+        Self {
+            hsts: Some((Duration::from_secs(365 * 24 * 60 * 60), true)),
+            nosniff: true,
+            frame_options: Some(FrameOptions::Deny),
+            referrer_policy: Some(ReferrerPolicy::StrictOriginWhenCrossOrigin),
+            content_security_policy: None,
+        }
+    }
+}
+impl SecurityPreset {
+    /// Sets `strict-transport-security`, or leaves it out for `None`.
+    pub fn hsts(mut self, max_age: Option<Duration>, include_subdomains: bool) -> Self {
+        self.hsts = max_age.map(|max_age| (max_age, include_subdomains));
+        self
+    }
+    /// Whether to send `x-content-type-options: nosniff`.
+    pub fn nosniff(mut self, enabled: bool) -> Self {
+        self.nosniff = enabled;
+        self
+    }
+    pub fn frame_options(mut self, frame_options: Option<FrameOptions>) -> Self {
+        self.frame_options = frame_options;
+        self
+    }
+    pub fn referrer_policy(mut self, policy: Option<ReferrerPolicy>) -> Self {
+        self.referrer_policy = policy;
+        self
+    }
+    /// Sets the `content-security-policy`, which is passed through as given.
+    pub fn content_security_policy<V: AsRef<str>>(mut self, policy: V) -> Result<Self, ValueError> {
+        self.content_security_policy = Some(Value::new(policy)?);
+        Ok(self)
+    }
+    fn headers(self) -> impl Iterator<Item = (&'static str, Value)> {
+        let hsts = self.hsts.map(|(max_age, subdomains)| {
+            let mut value = format!("max-age={}", max_age.as_secs());
+            if subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            ("strict-transport-security", value)
+        });
+        [
+            hsts,
+            self.nosniff.then(|| ("x-content-type-options", "nosniff".to_owned())),
+            self.frame_options.map(|o| ("x-frame-options", o.to_string())),
+            self.referrer_policy.map(|p| ("referrer-policy", p.to_string())),
+        ]
+            .into_iter()
+            .flatten()
+            .map(|(k, v)| (k, Value::new(v).unwrap()))
+            .chain(self.content_security_policy.map(|v| ("content-security-policy", v)))
+    }
+}
+
+impl<S: State, B> ResponseBuilder<S, B> {
+    /// Adds the headers of `preset` that the response does not set itself.
+    pub fn with_security_headers(mut self, preset: SecurityPreset) -> Self {
+        for (k, v) in preset.headers() {
+            if !self.headers.contains_key(k) {
+                self.headers.insert(Key::new(k).unwrap(), v);
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Response;
+    use super::*;
+
+    #[test]
+    fn default_preset() {
+        let response = Response::Ok.body("").with_security_headers(SecurityPreset::default());
+        assert_eq!(response.to_string(), "HTTP/1.0 200 OK\r\n\
+            strict-transport-security: max-age=31536000; includeSubDomains\r\n\
+            x-content-type-options: nosniff\r\n\
+            x-frame-options: DENY\r\n\
+            referrer-policy: strict-origin-when-cross-origin\r\n\r\n");
+    }
+    #[test]
+    fn handler_headers_win() {
+        let preset = SecurityPreset::default()
+            .hsts(None, false)
+            .content_security_policy("default-src 'self'").unwrap();
+        let response = Response::Ok
+            .header("X-Frame-Options", "SAMEORIGIN").unwrap()
+            .header("content-security-policy", "default-src *").unwrap()
+            .body("")
+            .with_security_headers(preset);
+        assert_eq!(response.to_string(), "HTTP/1.0 200 OK\r\n\
+            x-frame-options: SAMEORIGIN\r\n\
+            content-security-policy: default-src *\r\n\
+            x-content-type-options: nosniff\r\n\
+            referrer-policy: strict-origin-when-cross-origin\r\n\r\n");
+    }
+    #[test]
+    fn csp_validated() {
+        assert_eq!(
+            SecurityPreset::default().content_security_policy("script-src 'self'\r\nx: y"),
+            Err(ValueError::IllegalChars { input: "script-src 'self'\r\nx: y".to_owned(), found: '\r', at: 17 })
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    io::{self, Write},
+};
+
+use crate::ResponseCode;
+use super::{Complete, Incomplete, ResponseBuilder};
+
+#[derive(Debug, Clone, PartialEq)]
+/// A final response preceded by interim (1xx) responses, all written to
+/// the same connection, e.g. a `100 Continue` before the actual answer.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{Response, response::ResponseSequence};
+/// let mut stream = Vec::new();
+/// ResponseSequence::new(Response::Created.body("done")).unwrap()
+///     .interim(Response::Continue).unwrap()
+///     .write_to(&mut stream).unwrap();
+/// assert_eq!(stream, b"HTTP/1.0 100 Continue\r\n\r\nHTTP/1.0 201 Created\r\n\r\ndone");
+/// ```
+pub struct ResponseSequence {
+    interim: Vec<ResponseBuilder<Incomplete>>,
+    final_response: ResponseBuilder<Complete>,
+}
+impl ResponseSequence {
+    /// Starts a sequence ending in `final_response`, which may not be informational.
+    pub fn new(final_response: ResponseBuilder<Complete>) -> Result<Self, SequenceError> {
+        if (100..200).contains(&final_response.code()) {
+            return Err(SequenceError::InformationalFinal);
+        }
+        Ok(Self { interim: vec![], final_response })
+    }
+    /// Queues an interim response, written after the ones queued before and
+    /// ahead of the final response.
+    ///
+    /// Only 100, 102 and 103 are accepted. `content-length` and
+    /// `transfer-encoding` are dropped, as interim responses have no body.
+    /// HTTP/1.0 has no interim responses, so they are refused if either
+    /// response explicitly targets that version.
+    pub fn interim<R: Into<ResponseBuilder<Incomplete>>>(mut self, response: R) -> Result<Self, SequenceError> {
+        let mut response = response.into();
+        if [response.version, self.final_response.version].iter().flatten().any(|v| !v.supports_interim_responses()) {
+            return Err(SequenceError::InterimOnHttp10);
+        }
+        if !matches!(response.code(), 100 | 102 | 103) {
+            return Err(SequenceError::NotInterim(response.code()));
+        }
+        response.headers.remove("content-length");
+        response.headers.remove("transfer-encoding");
+        self.interim.push(response);
+        Ok(self)
+    }
+    /// Writes all interim responses and then the final one.
+    pub fn write_to<W: Write>(self, w: &mut W) -> io::Result<()> {
+        for response in self.interim {
+            response.write_to(w)?;
+        }
+        self.final_response.write_to(w)?;
+        w.flush()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceError {
+    /// An interim response has to be `100`, `102` or `103`
+    NotInterim(u16),
+    /// The final response may not be informational
+    InformationalFinal,
+    /// HTTP/1.0 clients do not understand interim responses
+    InterimOnHttp10,
+}
+impl Error for SequenceError {}
+impl Display for SequenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::NotInterim(code) => write!(f, "{code} is not an interim response code"),
+            Self::InformationalFinal => write!(f, "final response is informational"),
+            Self::InterimOnHttp10 => write!(f, "interim responses are not supported by HTTP/1.0"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Response, Version};
+    use super::*;
+
+    #[test]
+    fn status_lines_in_order() {
+        let mut stream = vec![];
+        ResponseSequence::new(Response::Ok.header("host", "x").unwrap().body("body")).unwrap()
+            .interim(Response::Continue).unwrap()
+            .interim(Response::EarlyHints.header("link", "</style.css>; rel=preload").unwrap()).unwrap()
+            .write_to(&mut stream).unwrap();
+        let stream = String::from_utf8(stream).unwrap();
+        let continue_at = stream.find("100 Continue").unwrap();
+        let hints_at = stream.find("103 Early Hints").unwrap();
+        let ok_at = stream.find("200 OK").unwrap();
+        assert!(continue_at < hints_at && hints_at < ok_at);
+        assert!(stream.ends_with("\r\n\r\nbody"));
+    }
+    #[test]
+    fn interim_drops_framing() {
+        let mut stream = vec![];
+        ResponseSequence::new(Response::NoContent.body("")).unwrap()
+            .interim(Response::Continue.header("content-length", "5").unwrap()).unwrap()
+            .write_to(&mut stream).unwrap();
+        assert_eq!(stream, b"HTTP/1.0 100 Continue\r\n\r\nHTTP/1.0 204 No Content\r\n\r\n");
+    }
+    #[test]
+    fn no_interim_at_one_zero() {
+        let sequence = ResponseSequence::new(Response::Ok.body("").version(Version(1, 0))).unwrap();
+        assert_eq!(sequence.interim(Response::EarlyHints), Err(SequenceError::InterimOnHttp10));
+        let sequence = ResponseSequence::new(Response::Ok.body("")).unwrap();
+        let hints = ResponseBuilder::from(Response::EarlyHints).version(Version(1, 0));
+        assert_eq!(sequence.interim(hints), Err(SequenceError::InterimOnHttp10));
+    }
+    #[test]
+    fn codes_enforced() {
+        assert_eq!(
+            ResponseSequence::new(Response::Continue.body("")),
+            Err(SequenceError::InformationalFinal)
+        );
+        let sequence = ResponseSequence::new(Response::Ok.body("")).unwrap();
+        assert_eq!(sequence.clone().interim(Response::Ok), Err(SequenceError::NotInterim(200)));
+        assert_eq!(sequence.interim(Response::SwitchingProtocols), Err(SequenceError::NotInterim(101)));
+    }
+}
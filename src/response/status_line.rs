@@ -0,0 +1,146 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    str::FromStr,
+};
+
+use crate::{line, Version};
+
+use super::{InvalidCode, Response};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// The first line of a response, e.g. `HTTP/1.1 404 Not Found`.
+///
+/// The code is kept as a number, so lines with codes unknown to
+/// [Response] can still be parsed.
+///
+/// # Examples
+/// ```
+/// # use heggemann_http::{response::StatusLine, Response, Version};
+/// let line: StatusLine = "HTTP/1.1 404 Not Found".parse().unwrap();
+/// assert_eq!(line.version, Version(1, 1));
+/// assert_eq!(line.phrase, "Not Found");
+/// assert_eq!(Response::try_from(line), Ok(Response::NotFound));
+/// ```
+pub struct StatusLine {
+    pub version: Version,
+    pub code: u16,
+    pub phrase: String,
+}
+
+impl Display for StatusLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        StatusLineRef {
+            version: self.version,
+            code: self.code,
+            phrase: &self.phrase,
+        }.fmt(f)
+    }
+}
+
+/// Borrowing form of [StatusLine], letting the serializer write status
+/// lines without allocating.
+pub(crate) struct StatusLineRef<'a> {
+    pub(crate) version: Version,
+    pub(crate) code: u16,
+    pub(crate) phrase: &'a str,
+}
+impl StatusLineRef<'_> {
+    /// Length of the formatted line, computed without formatting into a buffer.
+    pub(crate) fn len(&self) -> usize {
+        struct Counter(usize);
+        impl Write for Counter {
+            fn write_str(&mut self, s: &str) -> FmtResult {
+                self.0 += s.len();
+                Ok(())
+            }
+        }
+        let mut counter = Counter(0);
+        // Counting cannot fail
+        write!(counter, "{self}").unwrap();
+        counter.0
+    }
+}
+impl Display for StatusLineRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        line::format_status_line(self.version, self.code, self.phrase).fmt(f)
+    }
+}
+
+impl FromStr for StatusLine {
+    type Err = StatusLineError;
+    /// Parses a status line, with or without its line ending. The reason
+    /// phrase may be empty, in which case the space before it is optional.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (version, code, phrase) = line::parse_status_line(s)?;
+        Ok(StatusLine { version, code, phrase: phrase.to_owned() })
+    }
+}
+
+impl TryFrom<&str> for StatusLine {
+    type Error = StatusLineError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<StatusLine> for Response {
+    type Error = InvalidCode;
+    fn try_from(value: StatusLine) -> Result<Self, Self::Error> {
+        Response::try_from(value.code)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StatusLineError {
+    /// The line does not start with `HTTP/`
+    NoHttpWord,
+    /// The version is not of the form `[major].[minor]` with single digits
+    InvalidVersion,
+    /// The status code is not made of three digits
+    InvalidCode,
+    /// The reason phrase contains control characters
+    InvalidPhrase,
+}
+impl Error for StatusLineError {}
+impl Display for StatusLineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", match self {
+            Self::NoHttpWord => "no version",
+            Self::InvalidVersion => "version invalid",
+            Self::InvalidCode => "status code invalid",
+            Self::InvalidPhrase => "reason phrase invalid",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_word_phrase() {
+        let line: StatusLine = "HTTP/1.1 505 HTTP Version Not Supported\r\n".parse().unwrap();
+        assert_eq!(line, StatusLine {
+            version: Version(1, 1),
+            code: 505,
+            phrase: "HTTP Version Not Supported".to_owned(),
+        });
+        assert_eq!(line.to_string(), "HTTP/1.1 505 HTTP Version Not Supported");
+    }
+    #[test]
+    fn empty_phrase() {
+        for s in ["HTTP/1.0 299 ", "HTTP/1.0 299"] {
+            let line = StatusLine::try_from(s).unwrap();
+            assert_eq!((line.code, line.phrase.as_str()), (299, ""));
+            assert_eq!(Response::try_from(line), Err(InvalidCode));
+        }
+    }
+    #[test]
+    fn garbage() {
+        assert_eq!("hello there".parse::<StatusLine>(), Err(StatusLineError::NoHttpWord));
+        assert_eq!("HTTP/one 200 OK".parse::<StatusLine>(), Err(StatusLineError::InvalidVersion));
+        assert_eq!("HTTP/1.1 20 OK".parse::<StatusLine>(), Err(StatusLineError::InvalidCode));
+        assert_eq!("HTTP/1.1 +20 OK".parse::<StatusLine>(), Err(StatusLineError::InvalidCode));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::Request;
+
+use super::{Complete, Response, ResponseBuilder};
+
+/// Headers left out of a TRACE echo by [Response::trace_echo], as they
+/// carry credentials.
+pub const TRACE_REDACTED: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+impl Response {
+    /// Answers a TRACE request by echoing it back as a `message/http` body,
+    /// without the [credential headers][TRACE_REDACTED].
+    ///
+    /// # Examples
+    /// ```
+    /// # use heggemann_http::{Request, Response};
+    /// let request: Request = "TRACE / HTTP/1.1\r\nCookie: session=1\r\n\r\n".parse().unwrap();
+    /// let echo = Response::trace_echo(&request);
+    /// assert_eq!(echo.get_header("content-type").unwrap(), "message/http");
+    /// assert_eq!(echo.get_body(), b"TRACE / HTTP/1.1\r\n\r\n");
+    /// ```
+    pub fn trace_echo(request: &Request) -> ResponseBuilder<Complete> {
+        Self::trace_echo_redacting(request, TRACE_REDACTED)
+    }
+    /// [trace_echo][Self::trace_echo] leaving out the headers in `redacted`
+    /// instead, compared ignoring case.
+    pub fn trace_echo_redacting(request: &Request, redacted: &[&str]) -> ResponseBuilder<Complete> {
+        let mut echo = Request {
+            method: request.method,
+            path: request.path.clone(),
+            headers: request.headers.clone(),
+            version: request.version,
+            extensions: Default::default(),
+        };
+        echo.headers.retain(|k, _| !redacted.iter().any(|r| k == r));
+        let body = echo.to_bytes();
+        ResponseBuilder::new(Response::Ok)
+            .header("content-type", "message/http")
+            .unwrap()
+            .content_length(body.len())
+            .body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RequestMethod;
+    use super::*;
+
+    #[test]
+    fn credentials_stripped() {
+        let request: Request = "TRACE /x HTTP/1.1\r\nHost: a\r\nCookie: id=1\r\nAuthorization: Basic abc\r\n\r\n"
+            .parse()
+            .unwrap();
+        let echo = Response::trace_echo(&request);
+        assert_eq!(echo.get_status(), &Response::Ok);
+        assert_eq!(echo.get_header("content-length").unwrap().as_u64(), Some(echo.body_len() as u64));
+        let body = std::str::from_utf8(echo.get_body()).unwrap();
+        assert!(!body.to_ascii_lowercase().contains("cookie"));
+        let echoed: Request = body.parse().unwrap();
+        assert_eq!(echoed.method, RequestMethod::Trace);
+        assert_eq!(echoed.path, "/x");
+        assert_eq!(echoed.headers.len(), 1);
+        assert_eq!(echoed.headers.get("host").unwrap(), "a");
+    }
+    #[test]
+    fn custom_redaction() {
+        let request: Request = "TRACE / HTTP/1.1\r\nX-Api-Key: secret\r\nCookie: id=1\r\n\r\n".parse().unwrap();
+        let echo = Response::trace_echo_redacting(&request, &["X-API-KEY"]);
+        let echoed: Request = std::str::from_utf8(echo.get_body()).unwrap().parse().unwrap();
+        assert!(!echoed.headers.contains_key("x-api-key"));
+        assert_eq!(echoed.headers.get("cookie").unwrap(), "id=1");
+    }
+}
@@ -0,0 +1,286 @@
+//! Dispatching requests to handlers by method and path.
+//!
+//! Patterns are split into segments at `/`. A segment `:name` captures one
+//! segment of the path, a final `*name` captures the rest of it, and any
+//! other segment has to match exactly. The captures are stored as [Params]
+//! in the [extensions][Request::extensions] of the request.
+//!
+//! # Examples
+//! ```
+//! # use heggemann_http::{router::{Params, Router}, Request, RequestMethod, Response, ResponseCode};
+//! let router = Router::new()
+//!     .route(RequestMethod::Get, "/users/:id", |request: Request| {
+//!         let id = request.extensions.get::<Params>().unwrap().get("id").unwrap().to_owned();
+//!         Response::Ok.body(id)
+//!     });
+//! let request: Request = "GET /users/7 HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap();
+//! assert_eq!(router.handle(request).get_body(), b"7");
+//! let request: Request = "DELETE /users/7 HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap();
+//! assert_eq!(router.handle(request).code(), 405);
+//! ```
+//!
+//! A router is itself a [Handler], e.g. for [server::serve][crate::server::serve]:
+//! ```no_run
+//! # use std::net::TcpListener;
+//! # use heggemann_http::{router::Router, server, RequestMethod, Response};
+//! let router = Router::new().route(RequestMethod::Get, "/", |_| Response::Ok.body("home"));
+//! server::serve(TcpListener::bind("127.0.0.1:8080").unwrap(), router).unwrap();
+//! ```
+
+use crate::{
+    handler::Handler,
+    response::{Complete, ResponseBuilder},
+    Request,
+    RequestMethod,
+    Response,
+};
+
+type BoxedHandler = Box<dyn Handler + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The segments captured by the matched pattern, percent-decoded.
+pub struct Params(Vec<(String, String)>);
+
+impl Params {
+    /// The value captured by `:name` or `*name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+impl Segment {
+    /// Lower is more specific.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Literal(_) => 0,
+            Self::Param(_) => 1,
+            Self::Rest(_) => 2,
+        }
+    }
+}
+
+struct Route {
+    method: RequestMethod,
+    segments: Vec<Segment>,
+    handler: BoxedHandler,
+}
+
+impl Route {
+    fn captures(&self, path: &[String]) -> Option<Params> {
+        let mut params = Vec::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Rest(name) => {
+                    params.push((name.clone(), path.get(i..).unwrap_or_default().join("/")));
+                    return Some(Params(params));
+                }
+                Segment::Literal(literal) if path.get(i) != Some(literal) => return None,
+                Segment::Literal(_) => {}
+                Segment::Param(name) => params.push((name.clone(), path.get(i)?.clone())),
+            }
+        }
+        (path.len() == self.segments.len()).then_some(Params(params))
+    }
+    fn specificity(&self) -> Vec<u8> {
+        self.segments.iter().map(Segment::rank).collect()
+    }
+}
+
+#[derive(Default)]
+/// Routes requests to the handler of the most specific matching pattern:
+/// literal segments win over `:name`, which wins over `*name`. Routes
+/// equally specific are tried in the order they were added.
+///
+/// A path some route matches but not with the method of the request is
+/// answered with a [405][Response::MethodNotAllowed] listing the methods
+/// it has. A `HEAD` is handled by a `GET` route if there is no `HEAD` one.
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Option<BoxedHandler>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a route for `method` on `pattern`.
+    ///
+    /// # Panics
+    /// When a `*name` segment is not the last one.
+    pub fn route<H>(mut self, method: RequestMethod, pattern: &str, handler: H) -> Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        let segments: Vec<_> = split(pattern).map(|segment| match segment.as_bytes().first() {
+            Some(b':') => Segment::Param(segment[1..].to_owned()),
+            Some(b'*') => Segment::Rest(segment[1..].to_owned()),
+            _ => Segment::Literal(decode(segment)),
+        }).collect();
+        let last = segments.len().saturating_sub(1);
+        assert!(
+            !segments.iter().take(last).any(|s| matches!(s, Segment::Rest(_))),
+            "a * segment has to be the last one in {pattern}",
+        );
+        self.routes.push(Route { method, segments, handler: Box::new(handler) });
+        self
+    }
+    /// Sets the handler of requests no route matches, a
+    /// [404][Response::NotFound] by default.
+    pub fn fallback<H>(mut self, handler: H) -> Self
+    where
+        H: Handler + Send + Sync + 'static,
+    {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+    pub fn handle(&self, mut request: Request) -> ResponseBuilder<Complete> {
+        let path = request.path.split(['?', '#']).next().unwrap_or_default();
+        let path: Vec<_> = split(path).map(decode).collect();
+        let mut matching: Vec<_> = self.routes.iter()
+            .filter_map(|route| route.captures(&path).map(|params| (route, params)))
+            .collect();
+        // Stable, so registration order breaks ties
+        matching.sort_by_cached_key(|(route, _)| route.specificity());
+        let chosen = matching.iter().position(|(route, _)| route.method == request.method)
+            .or_else(|| match request.method {
+                RequestMethod::Head => matching.iter().position(|(route, _)| route.method == RequestMethod::Get),
+                _ => None,
+            });
+        if let Some(index) = chosen {
+            let (route, params) = matching.swap_remove(index);
+            request.extensions.insert(params);
+            return route.handler.handle(request);
+        }
+        if !matching.is_empty() {
+            let mut allowed: Vec<_> = matching.iter().map(|(route, _)| route.method).collect();
+            if allowed.contains(&RequestMethod::Get) {
+                allowed.push(RequestMethod::Head);
+            }
+            allowed.sort_by_key(RequestMethod::as_str);
+            // Not empty, and the builder removes duplicates
+            return Response::method_not_allowed(&allowed).unwrap().body("");
+        }
+        match &self.fallback {
+            Some(fallback) => fallback.handle(request),
+            None => Response::NotFound.body(""),
+        }
+    }
+}
+
+impl Handler for Router {
+    fn handle(&self, request: Request) -> ResponseBuilder<Complete> {
+        Router::handle(self, request)
+    }
+}
+
+/// The segments between slashes, ignoring empty ones so that `/a//b/` is
+/// `/a/b`.
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Decodes `%XX` escapes, leaving malformed ones as they are.
+pub(crate) fn decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ResponseCode;
+
+    fn request(method: &str, path: &str) -> Request {
+        format!("{method} {path} HTTP/1.1\r\nHost: x\r\n\r\n").parse().unwrap()
+    }
+    fn show(name: &'static str) -> impl Fn(Request) -> ResponseBuilder<Complete> {
+        move |request| {
+            let params = request.extensions.get::<Params>().unwrap();
+            let captured: Vec<_> = params.iter().map(|(n, v)| format!("{n}={v}")).collect();
+            Response::Ok.body(format!("{name} {}", captured.join(" ")))
+        }
+    }
+    fn body(response: ResponseBuilder<Complete>) -> String {
+        String::from_utf8(response.get_body().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn most_specific_wins() {
+        use RequestMethod::*;
+        let router = Router::new()
+            .route(Get, "/users/*rest", show("rest"))
+            .route(Get, "/users/:id", show("id"))
+            .route(Get, "/users/new", show("new"))
+            .route(Get, "/users/:id/posts/:post", show("post"));
+        assert_eq!(body(router.handle(request("GET", "/users/new"))), "new ");
+        assert_eq!(body(router.handle(request("GET", "/users/7?full"))), "id id=7");
+        assert_eq!(body(router.handle(request("GET", "/users/7/posts/3"))), "post id=7 post=3");
+        assert_eq!(body(router.handle(request("GET", "/users/7/likes/3"))), "rest rest=7/likes/3");
+        assert_eq!(body(router.handle(request("HEAD", "/users/new"))), "new ");
+    }
+    #[test]
+    fn percent_encoded_segments() {
+        let router = Router::new()
+            .route(RequestMethod::Get, "/files/a%20b", show("literal"))
+            .route(RequestMethod::Get, "/files/:name/raw", show("name"));
+        assert_eq!(body(router.handle(request("GET", "/files/a%20b"))), "literal ");
+        // An encoded slash stays within its segment
+        assert_eq!(body(router.handle(request("GET", "/files/x%2Fy/raw"))), "name name=x/y");
+        assert_eq!(body(router.handle(request("GET", "/files/%zz/raw"))), "name name=%zz");
+    }
+    #[test]
+    fn method_not_allowed() {
+        use RequestMethod::*;
+        let router = Router::new()
+            .route(Post, "/users/:id", show("post"))
+            .route(Get, "/users/:id", show("get"))
+            .route(Delete, "/users/new", show("delete"));
+        let response = router.handle(request("PUT", "/users/7"));
+        assert_eq!(response.code(), 405);
+        assert_eq!(response.get_header("allow").unwrap(), "GET, HEAD, POST");
+        let response = router.handle(request("PUT", "/users/new"));
+        assert_eq!(response.get_header("allow").unwrap(), "DELETE, GET, HEAD, POST");
+    }
+    #[test]
+    fn not_found() {
+        let router = Router::new().route(RequestMethod::Get, "/", show("root"));
+        assert_eq!(router.handle(request("GET", "/missing")).code(), 404);
+        assert_eq!(body(router.handle(request("GET", "/"))), "root ");
+        let router = router.fallback(|request: Request| Response::NotFound.body(request.path));
+        assert_eq!(body(router.handle(request("GET", "/missing"))), "/missing");
+    }
+}
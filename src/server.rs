@@ -0,0 +1,581 @@
+//! A blocking server answering requests on a [TcpListener] with a
+//! [Handler], one thread per connection.
+//!
+//! # Examples
+//! ```no_run
+//! # use std::net::TcpListener;
+//! # use heggemann_http::{server, Request, Response};
+//! let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+//! server::serve(listener, |request: Request| Response::Ok.body(format!("you asked for {}", request.path))).unwrap();
+//! ```
+
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use crate::{
+    connection::{Connection, KeepAlivePolicy, ReadTimeout},
+    handler::Handler,
+    metrics::Metrics,
+    request::RequestParseError,
+    response::{Complete, ResponseBuilder},
+    router,
+    Request,
+    RequestMethod,
+    Response,
+    ResponseCode,
+};
+
+#[cfg(feature = "tokio")]
+use crate::connection::{parse_error, Next, Session};
+
+pub mod log;
+
+/// Receives a [combined][log::format_combined] access log line per
+/// exchange, see [Server::access_log].
+type LogSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// The body of a request read by the server, found in the
+/// [extensions][Request::extensions] of the request the handler gets.
+pub struct Body(pub Vec<u8>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The address of the client, found in the
+/// [extensions][Request::extensions] of requests the server reads from TCP.
+pub struct Peer(pub SocketAddr);
+
+/// Answers requests on `listener` with `handler` until the process ends.
+/// See [Server] for a server that can be shut down or
+/// [log accesses][Server::access_log].
+pub fn serve<H>(listener: TcpListener, handler: H) -> io::Result<()>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    Server::new(listener, handler).run()
+}
+
+/// A listener a [Server] accepts connections on.
+pub trait Listener {
+    type Stream: Read + Write + ReadTimeout + Send + 'static;
+    /// The next connection, with the client's address if it has one.
+    fn accept(&self) -> io::Result<(Self::Stream, Option<SocketAddr>)>;
+}
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+    fn accept(&self) -> io::Result<(TcpStream, Option<SocketAddr>)> {
+        TcpListener::accept(self).map(|(stream, peer)| (stream, Some(peer)))
+    }
+}
+#[cfg(unix)]
+impl Listener for UnixListener {
+    type Stream = UnixStream;
+    fn accept(&self) -> io::Result<(UnixStream, Option<SocketAddr>)> {
+        UnixListener::accept(self).map(|(stream, _)| (stream, None))
+    }
+}
+
+/// A server answering requests on a listener with a handler.
+///
+/// Every connection gets its own thread, which reads requests and writes
+/// the handler's responses until either side closes the connection.
+/// Responses are answered in the version of the request, framed with a
+/// `content-length` if they have none and get a `connection` header if
+/// keep-alive differs from the default of that version. Requests the
+/// server cannot parse are answered with their
+/// [appropriate_response][RequestParseError::appropriate_response]
+/// before the connection is closed.
+/// Clients waiting for a `100 Continue` get it right away, see
+/// [Connection::always_continue].
+///
+/// Without timeouts, a client sending half a request and going silent
+/// keeps its thread busy until it closes the connection.
+pub struct Server<H, L = TcpListener> {
+    listener: L,
+    handler: Arc<H>,
+    shutdown: Arc<AtomicBool>,
+    settings: Settings,
+}
+
+#[derive(Clone, Default)]
+/// What a [Server] applies to every connection.
+struct Settings {
+    read_timeout: Option<Duration>,
+    header_timeout: Option<Duration>,
+    max_body_size: Option<u64>,
+    keep_alive: KeepAlivePolicy,
+    access_log: Option<LogSink>,
+    metrics: Option<Arc<dyn Metrics>>,
+    #[cfg(feature = "tls")]
+    tls: Option<Arc<rustls::ServerConfig>>,
+}
+
+impl<H, L> Server<H, L>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    pub fn new(listener: L, handler: H) -> Self {
+        Self {
+            listener,
+            handler: Arc::new(handler),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            settings: Settings::default(),
+        }
+    }
+    /// Bounds the wait for each read, see [Connection::set_read_timeout].
+    /// With [TLS][Self::tls], this bounds the handshake's reads too.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.read_timeout = Some(timeout);
+        self
+    }
+    /// Bounds the time a request head may take to arrive, see
+    /// [Connection::set_header_timeout].
+    pub fn header_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.header_timeout = Some(timeout);
+        self
+    }
+    /// Answers requests with a body over `max` bytes with a
+    /// [413][Response::PayloadTooLarge] without calling the handler, see
+    /// [Connection::set_max_body_size].
+    pub fn max_body_size(mut self, max: u64) -> Self {
+        self.settings.max_body_size = Some(max);
+        self
+    }
+    /// Limits how long and for how many requests connections are kept
+    /// open, see [Connection::set_keep_alive].
+    pub fn keep_alive(mut self, policy: KeepAlivePolicy) -> Self {
+        self.settings.keep_alive = policy;
+        self
+    }
+    /// Passes a line in the [Combined Log Format][log::format_combined] to
+    /// `sink` for every request the handler answers.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::net::TcpListener;
+    /// # use heggemann_http::{server::Server, Response};
+    /// let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+    /// Server::new(listener, |_| Response::Ok.body("hi"))
+    ///     .access_log(|line| eprintln!("{line}"))
+    ///     .run().unwrap();
+    /// ```
+    pub fn access_log<F: Fn(&str) + Send + Sync + 'static>(mut self, sink: F) -> Self {
+        self.settings.access_log = Some(Arc::new(sink));
+        self
+    }
+    /// Reports every connection's requests to `metrics`, see
+    /// [Connection::set_metrics].
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.settings.metrics = Some(metrics);
+        self
+    }
+    /// Serves over TLS, completing the handshake before the first request
+    /// is read. ALPN is restricted to `http/1.1`, replacing any protocols
+    /// in `config`.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, config: Arc<rustls::ServerConfig>) -> Self {
+        let mut config = (*config).clone();
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        self.settings.tls = Some(Arc::new(config));
+        self
+    }
+}
+
+impl<H, L> Server<H, L>
+where
+    H: Handler + Send + Sync + 'static,
+    L: Listener,
+{
+    /// Accepts connections until [shut down][ShutdownHandle::shutdown].
+    /// Connections already accepted are served to their end.
+    pub fn run(self) -> io::Result<()> {
+        loop {
+            let accepted = self.listener.accept();
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            let (stream, peer) = match accepted {
+                Ok(accepted) => accepted,
+                // The client gave up before being accepted
+                Err(e) if aborted(&e) => continue,
+                Err(e) => return Err(e),
+            };
+            let (handler, settings) = (Arc::clone(&self.handler), self.settings.clone());
+            // Errors only end the connection they happened on
+            thread::spawn(move || settings.serve(stream, &*handler, peer));
+        }
+        Ok(())
+    }
+}
+
+impl<H> Server<H, TcpListener>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    /// A handle stopping [run][Self::run] from another thread.
+    pub fn shutdown_handle(&self) -> io::Result<ShutdownHandle> {
+        let mut addr = self.listener.local_addr()?;
+        // Wake the listener through loopback if it listens on all interfaces
+        match addr.ip() {
+            ip if !ip.is_unspecified() => {}
+            ip if ip.is_ipv4() => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+            _ => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+        }
+        Ok(ShutdownHandle { flag: Arc::clone(&self.shutdown), addr })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<H> Server<H, tokio::net::TcpListener>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    /// Accepts connections on the current tokio runtime, one task per
+    /// connection, see [serve_async]. The settings apply as they do for
+    /// [run][Self::run], except that [TLS][Self::tls] is refused with
+    /// [Unsupported][io::ErrorKind::Unsupported].
+    pub async fn run_async(self) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        if self.settings.tls.is_some() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "TLS is only served by Server::run"));
+        }
+        loop {
+            let (stream, peer) = match self.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) if aborted(&e) => continue,
+                Err(e) => return Err(e),
+            };
+            let (handler, settings) = (Arc::clone(&self.handler), self.settings.clone());
+            tokio::spawn(async move { serve_connection_async(stream, peer, &*handler, &settings).await });
+        }
+    }
+}
+
+impl Settings {
+    /// Serves the connection `stream`, after the TLS handshake if
+    /// configured.
+    fn serve<S, H>(&self, stream: S, handler: &H, peer: Option<SocketAddr>) -> io::Result<()>
+    where
+        S: Read + Write + ReadTimeout,
+        H: Handler,
+    {
+        #[cfg(feature = "tls")]
+        if let Some(config) = &self.tls {
+            let mut stream = stream;
+            // A client going silent must not hold the thread in the handshake
+            stream.set_read_timeout(self.read_timeout)?;
+            let mut tls = rustls::ServerConnection::new(Arc::clone(config)).map_err(io::Error::other)?;
+            while tls.is_handshaking() {
+                tls.complete_io(&mut stream)?;
+            }
+            return serve_connection(self.connection(rustls::StreamOwned::new(tls, stream))?, handler, peer, self.access_log.as_ref());
+        }
+        serve_connection(self.connection(stream)?, handler, peer, self.access_log.as_ref())
+    }
+    #[cfg(feature = "tokio")]
+    fn session(&self) -> Session {
+        let mut session = Session::new();
+        session.set_header_timeout(self.header_timeout);
+        session.driver.set_keep_alive(self.keep_alive);
+        if let Some(max) = self.max_body_size {
+            session.driver.set_max_body_size(max);
+        }
+        if let Some(metrics) = &self.metrics {
+            session.set_metrics(Arc::clone(metrics));
+        }
+        session.always_continue();
+        session
+    }
+    fn connection<S: Read + Write + ReadTimeout>(&self, stream: S) -> io::Result<Connection<S>> {
+        let mut connection = Connection::new(stream);
+        connection.set_read_timeout(self.read_timeout)?;
+        connection.set_header_timeout(self.header_timeout);
+        connection.set_keep_alive(self.keep_alive);
+        if let Some(max) = self.max_body_size {
+            connection.set_max_body_size(max);
+        }
+        if let Some(metrics) = &self.metrics {
+            connection.set_metrics(Arc::clone(metrics));
+        }
+        Ok(connection)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Stops a [Server], see [Server::shutdown_handle].
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+    addr: SocketAddr,
+}
+impl ShutdownHandle {
+    /// Makes the server stop accepting connections. It returns from
+    /// [run][Server::run] once it notices, which this ensures by connecting
+    /// to it.
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+        // Failing to connect means the listener is gone already
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Answers requests on the Unix domain socket `listener` with `handler`
+/// until the process ends, like [Server::new] with a [UnixListener].
+///
+/// HTTP/1.1 requests still need a `host` header, for which clients such
+/// as curl and [send_unix][crate::client::send_unix] send `localhost`.
+#[cfg(unix)]
+pub fn serve_unix<H>(listener: UnixListener, handler: H) -> io::Result<()>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    Server::new(listener, handler).run()
+}
+
+/// Answers requests on `listener` over TLS with `handler` until the
+/// process ends, see [Server::tls].
+#[cfg(feature = "tls")]
+pub fn serve_tls<H>(listener: TcpListener, tls_config: Arc<rustls::ServerConfig>, handler: H) -> io::Result<()>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    Server::new(listener, handler).tls(tls_config).run()
+}
+
+/// Answers requests on `listener` with `handler` on the current tokio
+/// runtime, one task per connection, like [Server] does with threads.
+///
+/// The handler runs on the runtime's worker threads, so it should not
+/// block.
+#[cfg(feature = "tokio")]
+pub async fn serve_async<H>(listener: tokio::net::TcpListener, handler: H) -> io::Result<()>
+where
+    H: Handler + Send + Sync + 'static,
+{
+    Server::new(listener, handler).run_async().await
+}
+
+/// A handler serving the files below `root`, usable with [serve] or a
+/// [Router][crate::router::Router], answering like
+/// [Response::from_file].
+///
+/// The request path is percent-decoded and `.` and `..` are resolved
+/// before it is joined to `root`. Paths escaping `root` that way are
+/// answered with a [403][Response::Forbidden]; symbolic links below
+/// `root` are followed. A directory is served by its `index.html`. Only
+/// `GET` and `HEAD` are allowed.
+///
+/// # Examples
+/// ```no_run
+/// # use std::net::TcpListener;
+/// # use heggemann_http::server;
+/// let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+/// server::serve(listener, server::static_files("public")).unwrap();
+/// ```
+pub fn static_files<P: AsRef<Path>>(root: P) -> impl Fn(Request) -> ResponseBuilder<Complete> + Send + Sync + 'static {
+    let root = root.as_ref().to_path_buf();
+    move |request| {
+        if !matches!(request.method, RequestMethod::Get | RequestMethod::Head) {
+            // Not empty
+            return Response::method_not_allowed(&[RequestMethod::Get, RequestMethod::Head]).unwrap().body("");
+        }
+        let Some(relative) = within_root(&request.path) else {
+            return Response::Forbidden.body("");
+        };
+        let mut path = root.join(relative);
+        if path.is_dir() {
+            path.push("index.html");
+        }
+        match Response::from_file(&path, &request) {
+            Ok(response) => response,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Response::NotFound.body(""),
+            Err(_) => Response::ServerError.body(""),
+        }
+    }
+}
+
+/// The path relative to the root a request `target` names, or `None` if
+/// it leaves the root or a decoded segment would split into several.
+fn within_root(target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()).map(router::decode) {
+        match segment.as_str() {
+            "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            s if s.contains(['/', '\\', '\0', ':']) => return None,
+            _ => segments.push(segment),
+        }
+    }
+    Some(segments.iter().collect())
+}
+
+/// Whether accepting failed only because the client gave up before
+/// being accepted.
+fn aborted(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted)
+}
+
+fn serve_connection<S, H>(
+    mut connection: Connection<S>,
+    handler: &H,
+    peer: Option<SocketAddr>,
+    access_log: Option<&LogSink>,
+) -> io::Result<()>
+where
+    S: Read + Write,
+    H: Handler,
+{
+    connection.always_continue();
+    loop {
+        match connection.next_request() {
+            Ok(Some(mut request)) => {
+                if let Some(peer) = peer {
+                    request.extensions.insert(Peer(peer));
+                }
+                // The handler consumes the request, so the log keeps its head
+                let logged = access_log.map(|_| (SystemTime::now(), head_of(&request)));
+                let response = handler.handle(request);
+                if let (Some(sink), Some((when, head))) = (access_log, logged) {
+                    let sent = if head.method == RequestMethod::Head { 0 } else { response.body_len() };
+                    sink(&log::format_combined(peer.as_ref(), &head, response.code(), sent as u64, when));
+                }
+                connection.respond(response)?
+            }
+            // Closed by the client between requests, or by a response
+            Ok(None) => return Ok(()),
+            Err(RequestParseError::Io(kind)) => return Err(kind.into()),
+            Err(e) => {
+                if let Some(response) = e.appropriate_response() {
+                    connection.respond(response.body(""))?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The request without its body and extensions.
+pub(crate) fn head_of(request: &Request) -> Request {
+    Request {
+        method: request.method,
+        path: request.path.clone(),
+        headers: request.headers.clone(),
+        version: request.version,
+        extensions: Default::default(),
+    }
+}
+
+#[cfg(feature = "tokio")]
+async fn serve_connection_async<H: Handler>(
+    mut stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    handler: &H,
+    settings: &Settings,
+) -> io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    let mut session = settings.session();
+    loop {
+        // Responses are put together in memory, then written at once
+        let mut out = Vec::new();
+        let open = match next_request_async(&mut session, &mut stream, settings).await {
+            Ok(Some(mut request)) => {
+                request.extensions.insert(Peer(peer));
+                // The handler consumes the request, so the log keeps its head
+                let logged = settings.access_log.as_ref().map(|_| (SystemTime::now(), head_of(&request)));
+                let response = handler.handle(request);
+                if let (Some(sink), Some((when, head))) = (&settings.access_log, logged) {
+                    let sent = if head.method == RequestMethod::Head { 0 } else { response.body_len() };
+                    sink(&log::format_combined(Some(&peer), &head, response.code(), sent as u64, when));
+                }
+                session.respond(&mut out, response)?;
+                true
+            }
+            // Closed by the client between requests, or by a response
+            Ok(None) => false,
+            Err(RequestParseError::Io(kind)) => return Err(kind.into()),
+            Err(e) => {
+                if let Some(response) = e.appropriate_response() {
+                    session.respond(&mut out, response.body(""))?;
+                }
+                false
+            }
+        };
+        stream.write_all(&out).await?;
+        if !open {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads the next request on `stream` like [Connection::next_request].
+#[cfg(feature = "tokio")]
+async fn next_request_async(
+    session: &mut Session,
+    stream: &mut tokio::net::TcpStream,
+    settings: &Settings,
+) -> Result<Option<Request>, RequestParseError> {
+    if session.is_closing() {
+        return Ok(None);
+    }
+    let read = read_request_async(session, stream, settings).await;
+    session.finish(read)
+}
+
+#[cfg(feature = "tokio")]
+async fn read_request_async(
+    session: &mut Session,
+    stream: &mut tokio::net::TcpStream,
+    settings: &Settings,
+) -> Result<Option<Request>, RequestParseError> {
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut buf = [0; 8 * 1024];
+    let mut len = 0;
+    loop {
+        // Bytes the driver kept from before come first
+        let mut interim = Vec::new();
+        session.process(&buf[..len], &mut interim)?;
+        stream.write_all(&interim).await.map_err(parse_error)?;
+        match session.next()? {
+            Next::Request(request) => return Ok(Some(request)),
+            Next::Closed => return Ok(None),
+            Next::Read => {}
+        }
+        // Waiting for a request to start is bounded by the idle timeout,
+        // reading the rest of it by the read timeout and a head by the
+        // header timeout as well
+        let waiting = session.is_waiting();
+        let limit = if waiting { settings.keep_alive.get_idle_timeout().or(settings.read_timeout) } else { settings.read_timeout };
+        let head_left = session.head_deadline().map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        len = match within(limit.into_iter().chain(head_left).min(), stream.read(&mut buf)).await {
+            // An idle connection timing out ends like one closed by the client
+            None if waiting => return Ok(None),
+            None => return Err(RequestParseError::Timeout),
+            Some(Ok(0)) => return session.eof(),
+            Some(Ok(len)) => len,
+            Some(Err(e)) if e.kind() == io::ErrorKind::Interrupted => 0,
+            Some(Err(e)) => return Err(parse_error(e)),
+        };
+    }
+}
+
+/// Runs `future` to its end, or until `limit` passes, giving `None`.
+#[cfg(feature = "tokio")]
+async fn within<F: std::future::Future>(limit: Option<Duration>, future: F) -> Option<F::Output> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future).await.ok(),
+        None => Some(future.await),
+    }
+}
@@ -0,0 +1,119 @@
+//! Access log lines in the Common and Combined Log Formats of Apache and
+//! nginx, with times in UTC.
+//!
+//! # Examples
+//! ```
+//! # use std::{net::SocketAddr, time::{Duration, UNIX_EPOCH}};
+//! # use heggemann_http::{server::log, Request};
+//! let peer: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+//! let request: Request = "GET /path HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap();
+//! let when = UNIX_EPOCH + Duration::from_secs(971_211_336);
+//! assert_eq!(log::format_common(Some(&peer), &request, 200, 2326, when),
+//!     r#"127.0.0.1 - - [10/Oct/2000:20:55:36 +0000] "GET /path HTTP/1.1" 200 2326"#);
+//! ```
+
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{header::date, line, Request};
+
+/// `host - - [time] "request line" status bytes`, with `-` for an empty
+/// body and for the host of a client without an address, such as one on
+/// a Unix domain socket. The remote user and identity are not known and
+/// always `-`.
+pub fn format_common(peer: Option<&SocketAddr>, request: &Request, status: u16, body_len: u64, when: SystemTime) -> String {
+    let bytes = match body_len {
+        0 => "-".to_owned(),
+        len => len.to_string(),
+    };
+    let line = line::format_request_line(request.method, &request.path, request.version).to_string();
+    let host = peer.map_or("-".to_owned(), |peer| peer.ip().to_string());
+    format!("{host} - - [{}] \"{}\" {status} {bytes}", timestamp(when), escape(&line))
+}
+
+/// [format_common] followed by the quoted `referer` and `user-agent`
+/// headers, `-` when absent.
+pub fn format_combined(peer: Option<&SocketAddr>, request: &Request, status: u16, body_len: u64, when: SystemTime) -> String {
+    let mut line = format_common(peer, request, status, body_len, when);
+    for key in ["referer", "user-agent"] {
+        let value = request.headers.get(key).map_or("-".into(), |v| v.as_str_lossy());
+        // Writing to a String cannot fail
+        write!(line, " \"{}\"", escape(&value)).unwrap();
+    }
+    line
+}
+
+/// `10/Oct/2000:13:55:36 +0000`
+fn timestamp(when: SystemTime) -> String {
+    let secs = when.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (days, rem) = (secs / 86400, secs % 86400);
+    let (year, month, day) = date::civil_from_days(days as i64);
+    format!("{day:02}/{}/{year:04}:{:02}:{:02}:{:02} +0000",
+        date::MONTHS[month as usize - 1], rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Escapes quotes and backslashes with a backslash, and control and
+/// non-ASCII characters as `\xhh` per byte, so that a line cannot be forged
+/// or broken up.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => {
+                let mut utf8 = [0; 4];
+                for byte in c.encode_utf8(&mut utf8).bytes() {
+                    // Writing to a String cannot fail
+                    write!(escaped, "\\x{byte:02x}").unwrap();
+                }
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn when() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(971_211_336)
+    }
+
+    #[test]
+    fn without_optional_headers() {
+        let peer: SocketAddr = "[::1]:8080".parse().unwrap();
+        let request: Request = "HEAD /a?b=c HTTP/1.0\r\n\r\n".parse().unwrap();
+        assert_eq!(format_common(Some(&peer), &request, 304, 0, when()),
+            r#"::1 - - [10/Oct/2000:20:55:36 +0000] "HEAD /a?b=c HTTP/1.0" 304 -"#);
+        assert_eq!(format_combined(Some(&peer), &request, 304, 0, when()),
+            r#"::1 - - [10/Oct/2000:20:55:36 +0000] "HEAD /a?b=c HTTP/1.0" 304 - "-" "-""#);
+    }
+    #[test]
+    fn with_optional_headers() {
+        let peer: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        let request: Request = "GET /x HTTP/1.1\r\nHost: h\r\nReferer: http://h/\"quoted\"\r\n\
+            User-Agent: curl/8.0 \\ test\r\n\r\n".parse().unwrap();
+        assert_eq!(format_combined(Some(&peer), &request, 200, 2326, when()),
+            r#"10.0.0.1 - - [10/Oct/2000:20:55:36 +0000] "GET /x HTTP/1.1" 200 2326 "http://h/\"quoted\"" "curl/8.0 \\ test""#);
+    }
+    #[test]
+    fn without_peer() {
+        let request: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(format_common(None, &request, 200, 2, when()),
+            r#"- - - [10/Oct/2000:20:55:36 +0000] "GET / HTTP/1.1" 200 2"#);
+    }
+    #[test]
+    fn escapes_control_characters() {
+        assert_eq!(escape("a\tb\u{7f}\u{e9}"), r"a\x09b\x7f\xc3\xa9");
+    }
+}
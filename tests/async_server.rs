@@ -0,0 +1,199 @@
+#![cfg(feature = "tokio")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use heggemann_http::{
+    connection::KeepAlivePolicy,
+    metrics::AtomicMetrics,
+    server::{self, Body, Server},
+    Request,
+    Response,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[tokio::test]
+async fn pipelined_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(server::serve_async(listener, |request: Request| {
+        let body = request.extensions.get::<Body>().map_or(0, |b| b.0.len());
+        Response::Ok.body(format!("{} {}", request.path, body))
+    }));
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"\
+        POST /first HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n\
+        GET /second HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).await.unwrap();
+    assert_eq!(responses, "\
+        HTTP/1.1 200 OK\r\ncontent-length: 8\r\n\r\n/first 5\
+        HTTP/1.1 200 OK\r\ncontent-length: 9\r\nconnection: close\r\n\r\n/second 0");
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+}
+
+#[tokio::test]
+async fn expect_continue() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(server::serve_async(listener, |request: Request| {
+        let body = request.extensions.get::<Body>().map_or(0, |b| b.0.len());
+        Response::Ok.body(body.to_string())
+    }));
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n")
+        .await.unwrap();
+    // The body only follows the interim response
+    let mut interim = [0; 25];
+    stream.read_exact(&mut interim).await.unwrap();
+    assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+    stream.write_all(b"hello").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "HTTP/1.1 200 OK\r\ncontent-length: 1\r\nconnection: close\r\n\r\n5");
+}
+
+#[tokio::test]
+async fn bodies_over_max_body_size() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |request: Request| {
+        let body = request.extensions.get::<Body>().map_or(0, |b| b.0.len());
+        Response::Ok.body(body.to_string())
+    });
+    tokio::spawn(server.max_body_size(3).run_async());
+
+    for (request, expected) in [
+        (&b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\nConnection: close\r\n\r\nabc"[..], "HTTP/1.1 200 OK"),
+        (b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 4\r\n\r\nabcd", "HTTP/1.1 413 "),
+        (b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nab\r\n2\r\ncd\r\n0\r\n\r\n", "HTTP/1.1 413 "),
+    ] {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with(expected), "{response}");
+    }
+}
+
+#[tokio::test]
+async fn slow_request_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |_| Response::Ok.body("fast enough"))
+        .read_timeout(Duration::from_secs(10))
+        .header_timeout(Duration::from_millis(50));
+    tokio::spawn(server.run_async());
+
+    // Trickling bytes in keeps every read short, but not the head
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    for byte in b"GET / HTTP/1.1\r\n" {
+        if stream.write_all(&[*byte]).await.is_err() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.ends_with("\r\n\r\nfast enough"));
+}
+
+#[tokio::test]
+async fn silent_client_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |_| Response::Ok.body("")).read_timeout(Duration::from_millis(50));
+    tokio::spawn(server.run_async());
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nab").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+}
+
+#[tokio::test]
+async fn keep_alive_limits() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let policy = KeepAlivePolicy::new().idle_timeout(Duration::from_millis(100)).max_requests(2);
+    let server = Server::new(listener, |request: Request| Response::Ok.body(request.path))
+        .read_timeout(Duration::from_secs(10))
+        .keep_alive(policy);
+    tokio::spawn(server.run_async());
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"\
+        GET /1 HTTP/1.1\r\nHost: a\r\nConnection: keep-alive\r\n\r\n\
+        GET /2 HTTP/1.1\r\nHost: a\r\nConnection: keep-alive\r\n\r\n\
+        GET /3 HTTP/1.1\r\nHost: a\r\n\r\n").await.unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).await.unwrap();
+    assert_eq!(responses, "\
+        HTTP/1.1 200 OK\r\ncontent-length: 2\r\nkeep-alive: max=1\r\n\r\n/1\
+        HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\n/2");
+
+    // An idle connection is closed long before the read timeout
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"GET /1 HTTP/1.1\r\nHost: a\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert_eq!(response, "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n/1");
+}
+
+#[tokio::test]
+async fn metrics_and_access_log() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let metrics = Arc::new(AtomicMetrics::new());
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&lines);
+    let server = Server::new(listener, |request: Request| match request.path.as_str() {
+        "/" => Response::Ok.body("home"),
+        _ => Response::NotFound.body(""),
+    })
+        .metrics(metrics.clone())
+        .access_log(move |line| sink.lock().unwrap().push(line.to_owned()));
+    tokio::spawn(server.run_async());
+
+    let requests = "\
+        POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\n\r\nabc\
+        GET /missing HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n";
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(requests.as_bytes()).await.unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).await.unwrap();
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+    stream.read_to_string(&mut String::new()).await.unwrap();
+
+    assert_eq!(metrics.requests_started(), 2);
+    assert_eq!(metrics.requests(), 2);
+    assert_eq!((metrics.status_class(2), metrics.status_class(4)), (1, 1));
+    assert_eq!(metrics.bytes_in(), requests.len() as u64);
+    assert_eq!(metrics.bytes_out(), responses.len() as u64);
+    // The request without host
+    assert_eq!(metrics.parse_errors(), 1);
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with(r#"] "POST / HTTP/1.1" 200 4 "-" "-""#), "{}", lines[0]);
+    assert!(lines[1].ends_with(r#"] "GET /missing HTTP/1.1" 404 - "-" "-""#), "{}", lines[1]);
+}
@@ -0,0 +1,63 @@
+use std::{
+    io::{BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use heggemann_http::{
+    client::{self, ClientError},
+    server::{Body, Server},
+    Request,
+    Response,
+};
+
+#[test]
+fn against_own_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = Server::new(listener, |request: Request| {
+        match request.path.as_str() {
+            "/chunked" => Response::Ok
+                .header("transfer-encoding", "chunked").unwrap()
+                .body("5\r\nhello\r\n0\r\n\r\n"),
+            path => {
+                let body = request.extensions.get::<Body>().map_or(&[][..], |b| &b.0);
+                Response::Ok.body(format!("{path} {}", String::from_utf8_lossy(body)))
+            }
+        }
+    });
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let response = client::get("127.0.0.1", port, "/health").unwrap();
+    assert_eq!(response.status.code, 200);
+    assert_eq!(response.body, b"/health ");
+    assert_eq!(response.headers.get("connection").unwrap(), "close");
+
+    let response = client::get("127.0.0.1", port, "/chunked").unwrap();
+    assert_eq!(response.body, b"hello");
+
+    let mut stream = BufReader::new(std::net::TcpStream::connect(("127.0.0.1", port)).unwrap());
+    let mut request: heggemann_http::Request = "POST /echo HTTP/1.1\r\n\r\n".parse().unwrap();
+    request.extensions.insert(Body(b"ping".to_vec()));
+    let response = client::send(&mut stream, &request, "localhost").unwrap();
+    assert_eq!(response.body, b"/echo ping");
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn eof_before_complete_response() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let truncating = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut head = [0; 1024];
+        let _ = stream.read(&mut head).unwrap();
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhalf").unwrap();
+    });
+    let result = client::get("127.0.0.1", port, "/");
+    assert!(matches!(result, Err(ClientError::UnexpectedEof)), "{result:?}");
+    truncating.join().unwrap();
+}
@@ -0,0 +1,221 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use heggemann_http::{
+    connection::KeepAlivePolicy,
+    metrics::AtomicMetrics,
+    server::{Body, Server},
+    Request,
+    Response,
+};
+
+#[test]
+fn pipelined_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |request: Request| {
+        let body = request.extensions.get::<Body>().map_or(0, |b| b.0.len());
+        Response::Ok.body(format!("{} {}", request.path, body))
+    });
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"\
+        POST /first HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello\
+        GET /second HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).unwrap();
+    assert_eq!(responses, "\
+        HTTP/1.1 200 OK\r\ncontent-length: 8\r\n\r\n/first 5\
+        HTTP/1.1 200 OK\r\ncontent-length: 9\r\nconnection: close\r\n\r\n/second 0");
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "HTTP/1.1 400 Bad Request\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn slow_request_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |_| Response::Ok.body("fast enough"))
+        .read_timeout(Duration::from_millis(50))
+        .header_timeout(Duration::from_secs(1));
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HT").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "HTTP/1.1 408 Request Timeout\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.ends_with("\r\n\r\nfast enough"));
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn keep_alive_limits() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let policy = KeepAlivePolicy::new().idle_timeout(Duration::from_millis(100)).max_requests(2);
+    let server = Server::new(listener, |request: Request| Response::Ok.body(request.path))
+        .read_timeout(Duration::from_secs(10))
+        .keep_alive(policy);
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"\
+        GET /1 HTTP/1.1\r\nHost: a\r\nConnection: keep-alive\r\n\r\n\
+        GET /2 HTTP/1.1\r\nHost: a\r\nConnection: keep-alive\r\n\r\n\
+        GET /3 HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).unwrap();
+    assert_eq!(responses, "\
+        HTTP/1.1 200 OK\r\ncontent-length: 2\r\nkeep-alive: max=1\r\n\r\n/1\
+        HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\n/2");
+
+    // An idle connection is closed long before the read timeout
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /1 HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n/1");
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn metrics_count_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let metrics = Arc::new(AtomicMetrics::new());
+    let server = Server::new(listener, |request: Request| match request.path.as_str() {
+        "/" => Response::Ok.body("home"),
+        _ => Response::NotFound.body(""),
+    })
+        .metrics(metrics.clone());
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let requests = "\
+        POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\n\r\nabc\
+        GET /missing HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n";
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(requests.as_bytes()).unwrap();
+    let mut responses = String::new();
+    stream.read_to_string(&mut responses).unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    stream.read_to_string(&mut String::new()).unwrap();
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+
+    assert_eq!(metrics.requests_started(), 2);
+    assert_eq!(metrics.requests(), 2);
+    assert_eq!((metrics.status_class(2), metrics.status_class(4)), (1, 1));
+    assert_eq!(metrics.bytes_in(), requests.len() as u64);
+    assert_eq!(metrics.bytes_out(), responses.len() as u64);
+    // The request without host
+    assert_eq!(metrics.parse_errors(), 1);
+}
+
+#[test]
+fn oversized_body_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let called = Arc::new(Mutex::new(0));
+    let calls = Arc::clone(&called);
+    let server = Server::new(listener, move |_| {
+        *calls.lock().unwrap() += 1;
+        Response::Ok.body("")
+    })
+        .max_body_size(1024);
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    // The client waits for a go-ahead that never comes
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"PUT /up HTTP/1.1\r\nHost: a\r\nContent-Length: 1025\r\nExpect: 100-continue\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "HTTP/1.1 413 Content Too Large\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+    assert_eq!(*called.lock().unwrap(), 0);
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
+
+#[test]
+fn access_log_per_exchange() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&lines);
+    let server = Server::new(listener, |request: Request| Response::Ok.body(request.path))
+        .access_log(move |line| sink.lock().unwrap().push(line.to_owned()));
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"\
+        GET /first HTTP/1.1\r\nHost: a\r\nUser-Agent: test\r\n\r\n\
+        HEAD /second HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+    stream.read_to_end(&mut Vec::new()).unwrap();
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("127.0.0.1 - - ["), "{}", lines[0]);
+    assert!(lines[0].ends_with(r#"] "GET /first HTTP/1.1" 200 6 "-" "test""#), "{}", lines[0]);
+    assert!(lines[1].ends_with(r#"] "HEAD /second HTTP/1.1" 200 - "-" "-""#), "{}", lines[1]);
+}
+
+#[test]
+fn body_sent_after_continue() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |request: Request| {
+        let body = request.extensions.get::<Body>().map_or(0, |b| b.0.len());
+        Response::Ok.body(format!("{body} bytes"))
+    });
+    let shutdown = server.shutdown_handle().unwrap();
+    let running = thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    // Failing to get the 100 fails the test instead of hanging it
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    stream.write_all(b"POST /upload HTTP/1.1\r\nHost: a\r\nExpect: 100-continue\r\n\
+        Content-Length: 5\r\nConnection: close\r\n\r\n").unwrap();
+    let mut interim = [0; 25];
+    stream.read_exact(&mut interim).unwrap();
+    assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+    stream.write_all(b"hello").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\n5 bytes"));
+
+    shutdown.shutdown();
+    running.join().unwrap().unwrap();
+}
@@ -0,0 +1,135 @@
+use std::{fs, path::PathBuf};
+
+use heggemann_http::{header::date, server, Request, Response, ResponseCode};
+
+/// A fresh directory with `site/hello.txt`, `site/image.png`,
+/// `site/docs/index.html` and a `secret` next to `site`.
+fn tree(name: &str) -> PathBuf {
+    let base = std::env::temp_dir().join(format!("heggemann-static-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(base.join("site/docs")).unwrap();
+    fs::write(base.join("site/hello.txt"), "hello, world").unwrap();
+    fs::write(base.join("site/image.png"), [0x89, b'P', b'N', b'G', 0, 255, 13, 10]).unwrap();
+    fs::write(base.join("site/docs/index.html"), "<h1>docs</h1>").unwrap();
+    fs::write(base.join("secret"), "password").unwrap();
+    base
+}
+
+fn get(path: &str, extra: &str) -> Request {
+    format!("GET {path} HTTP/1.1\r\nHost: x\r\n{extra}\r\n").parse().unwrap()
+}
+
+#[test]
+fn text_and_binary() {
+    let base = tree("types");
+    let files = server::static_files(base.join("site"));
+    let text = files(get("/hello.txt?v=2", ""));
+    assert_eq!(text.code(), 200);
+    assert_eq!(text.get_body(), b"hello, world");
+    assert_eq!(text.get_header("content-type").unwrap(), "text/plain; charset=utf-8");
+    assert_eq!(text.get_header("content-length").unwrap(), "12");
+    assert_eq!(text.get_header("accept-ranges").unwrap(), "bytes");
+    assert!(text.get_header("last-modified").unwrap().as_http_date().is_some());
+    let image = files(get("/image.png", ""));
+    assert_eq!(image.get_header("content-type").unwrap(), "image/png");
+    assert_eq!(image.get_body(), [0x89, b'P', b'N', b'G', 0, 255, 13, 10]);
+    let index = files(get("/docs/", ""));
+    assert_eq!(index.get_body(), b"<h1>docs</h1>");
+    assert_eq!(index.get_header("content-type").unwrap(), "text/html; charset=utf-8");
+    assert_eq!(files(get("/missing.txt", "")).code(), 404);
+    let post: Request = "POST /hello.txt HTTP/1.1\r\nHost: x\r\n\r\n".parse().unwrap();
+    assert_eq!(files(post).get_header("allow").unwrap(), "GET, HEAD");
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn ranges() {
+    let base = tree("ranges");
+    let files = server::static_files(base.join("site"));
+    let partial = files(get("/hello.txt", "Range: bytes=7-\r\n"));
+    assert_eq!(partial.code(), 206);
+    assert_eq!(partial.get_body(), b"world");
+    assert_eq!(partial.get_header("content-range").unwrap(), "bytes 7-11/12");
+    assert_eq!(partial.get_header("content-type").unwrap(), "text/plain; charset=utf-8");
+    let unsatisfiable = files(get("/hello.txt", "Range: bytes=50-\r\n"));
+    assert_eq!(unsatisfiable.code(), 416);
+    assert_eq!(unsatisfiable.get_header("content-range").unwrap(), "bytes */12");
+    let multiple = files(get("/hello.txt", "Range: bytes=0-1, 7-8\r\n"));
+    assert!(multiple.get_header("content-type").unwrap().to_string().starts_with("multipart/byteranges"));
+    // A range header that is no range is ignored
+    assert_eq!(files(get("/hello.txt", "Range: lines=1-2\r\n")).code(), 200);
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn unreasonable_ranges() {
+    let base = tree("unreasonable");
+    let files = server::static_files(base.join("site"));
+    // Overlapping and adjacent ranges become one
+    let overlapping = files(get("/hello.txt", &format!("Range: bytes={}\r\n", ["0-"; 50].join(","))));
+    assert_eq!(overlapping.code(), 206);
+    assert_eq!(overlapping.get_body(), b"hello, world");
+    // More than the parser takes are ignored
+    let endless = files(get("/hello.txt", &format!("Range: bytes={}\r\n", ["0-"; 300].join(","))));
+    assert_eq!(endless.code(), 200);
+    let adjacent = files(get("/hello.txt", "Range: bytes=7-8, 0-2, 9-, 2-4\r\n"));
+    let parts = adjacent.get_header("content-type").unwrap().to_string();
+    assert!(parts.starts_with("multipart/byteranges"));
+    let body = String::from_utf8(adjacent.get_body().to_vec()).unwrap();
+    assert!(body.contains("content-range: bytes 0-4/12\r\n\r\nhello\r\n"));
+    assert!(body.contains("content-range: bytes 7-11/12\r\n\r\nworld\r\n"));
+    // Too many separate ones get the whole file
+    fs::write(base.join("site/big.txt"), "x".repeat(100)).unwrap();
+    let spread = |parts: u64| (0..parts).map(|i| format!("{0}-{0}", i * 2)).collect::<Vec<_>>().join(",");
+    assert_eq!(files(get("/big.txt", &format!("Range: bytes={}\r\n", spread(16)))).code(), 206);
+    let whole = files(get("/big.txt", &format!("Range: bytes={}\r\n", spread(17))));
+    assert_eq!(whole.code(), 200);
+    assert_eq!(whole.get_body().len(), 100);
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn head_is_not_read() {
+    let base = tree("head");
+    let files = server::static_files(base.join("site"));
+    let head: Request = "HEAD /hello.txt HTTP/1.1\r\nHost: x\r\nRange: bytes=0-1\r\n\r\n".parse().unwrap();
+    let response = files(head);
+    assert_eq!(response.code(), 200);
+    assert!(response.get_body().is_empty());
+    assert_eq!(response.get_header("content-length").unwrap(), "12");
+    assert_eq!(response.get_header("content-type").unwrap(), "text/plain; charset=utf-8");
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn conditional() {
+    let base = tree("conditional");
+    let files = server::static_files(base.join("site"));
+    let modified = fs::metadata(base.join("site/hello.txt")).unwrap().modified().unwrap();
+    let since = format!("If-Modified-Since: {}\r\n", date::format(modified));
+    let unchanged = files(get("/hello.txt", &since));
+    assert_eq!(unchanged.code(), 304);
+    assert!(unchanged.get_body().is_empty());
+    assert!(unchanged.get_header("last-modified").is_some());
+    let old = "If-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n";
+    assert_eq!(files(get("/hello.txt", old)).code(), 200);
+    let with_tag = format!("{since}If-None-Match: \"x\"\r\n");
+    assert_eq!(files(get("/hello.txt", &with_tag)).code(), 200);
+    fs::remove_dir_all(base).unwrap();
+}
+
+#[test]
+fn traversal() {
+    let base = tree("traversal");
+    let files = server::static_files(base.join("site"));
+    assert_eq!(files(get("/../../etc/passwd", "")).code(), 403);
+    assert_eq!(files(get("/../secret", "")).code(), 403);
+    assert_eq!(files(get("/docs/../../secret", "")).code(), 403);
+    assert_eq!(files(get("/%2e%2e/secret", "")).code(), 403);
+    assert_eq!(files(get("/docs%2F..%2F..%2Fsecret", "")).code(), 403);
+    assert_eq!(files(get("/..%5Csecret", "")).code(), 403);
+    // Staying inside is fine
+    assert_eq!(files(get("/docs/../hello.txt", "")).get_body(), b"hello, world");
+    assert_eq!(Response::from_file(base.join("secret"), &get("/", "")).unwrap().get_body(), b"password");
+    fs::remove_dir_all(base).unwrap();
+}
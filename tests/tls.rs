@@ -0,0 +1,79 @@
+#![cfg(feature = "tls")]
+
+use std::{
+    io::Read,
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use heggemann_http::{
+    client,
+    rustls::{self, pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer}},
+    server::{self, Server},
+    Request,
+    Response,
+};
+
+/// A self-signed certificate for `localhost` and a server configuration
+/// using it.
+fn certified() -> (rustls::pki_types::CertificateDer<'static>, rustls::ServerConfig) {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let cert = certified.cert.der().clone();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.signing_key.serialize_der()));
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions().unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.clone()], key).unwrap();
+    (cert, config)
+}
+
+#[test]
+fn request_over_tls() {
+    let (cert, server_config) = certified();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || server::serve_tls(listener, Arc::new(server_config), |request: Request| {
+        Response::Ok.body(format!("secret {}", request.path))
+    }));
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert).unwrap();
+    let request: Request = "GET /vault HTTP/1.1\r\nConnection: close\r\n\r\n".parse().unwrap();
+    let response = client::send_tls_with(client::tls_config(roots), "localhost", port, &request).unwrap();
+    assert_eq!(response.status.code, 200);
+    assert_eq!(response.body, b"secret /vault");
+
+    let untrusted = client::send_tls("localhost", port, &request);
+    assert!(matches!(untrusted, Err(client::ClientError::Io(_))));
+}
+
+#[test]
+fn silent_handshake_times_out() {
+    let (_, config) = certified();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Server::new(listener, |_| Response::Ok.body(""))
+        .tls(Arc::new(config))
+        .read_timeout(Duration::from_millis(100));
+    thread::spawn(move || server.run());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let start = Instant::now();
+    // Closed by the server instead of waiting for a ClientHello forever
+    assert!(stream.read(&mut [0; 16]).is_ok_and(|len| len == 0));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn run_async_refuses_tls() {
+    let (_, server_config) = certified();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server = Server::new(listener, |_| Response::Ok.body("")).tls(Arc::new(server_config));
+    assert_eq!(server.run_async().await.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+}
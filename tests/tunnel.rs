@@ -0,0 +1,46 @@
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread,
+};
+
+use heggemann_http::{
+    connection::{Connection, Tunnel},
+    RequestMethod,
+};
+
+#[test]
+fn buffered_bytes_forwarded_first() {
+    let upstream = TcpListener::bind("127.0.0.1:0").unwrap();
+    let upstream_addr = upstream.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = upstream.accept().unwrap();
+        let mut received = vec![];
+        stream.read_to_end(&mut received).unwrap();
+        stream.write_all(b"upstream saw: ").unwrap();
+        stream.write_all(&received).unwrap();
+    });
+
+    let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_addr = proxy.local_addr().unwrap();
+    let tunnel = thread::spawn(move || {
+        let (stream, _) = proxy.accept().unwrap();
+        let mut connection = Connection::new(stream);
+        let request = connection.next_request().unwrap().unwrap();
+        assert_eq!((request.method, request.path.as_str()), (RequestMethod::Connect, "upstream:443"));
+        assert_eq!(connection.remaining_buffer(), b"early ");
+        connection.into_tunnel(TcpStream::connect(upstream_addr).unwrap()).unwrap()
+    });
+
+    let mut client = TcpStream::connect(proxy_addr).unwrap();
+    client.write_all(b"CONNECT upstream:443 HTTP/1.1\r\nHost: upstream:443\r\n\r\nearly ").unwrap();
+    let mut established = [0; 19];
+    client.read_exact(&mut established).unwrap();
+    assert_eq!(&established, b"HTTP/1.1 200 OK\r\n\r\n");
+    client.write_all(b"late").unwrap();
+    client.shutdown(Shutdown::Write).unwrap();
+    let mut relayed = String::new();
+    client.read_to_string(&mut relayed).unwrap();
+    assert_eq!(relayed, "upstream saw: early late");
+    assert_eq!(tunnel.join().unwrap(), Tunnel { from_client: 10, from_upstream: 24 });
+}
@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+use std::{
+    fs,
+    os::unix::net::UnixListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use heggemann_http::{client, server::{self, Body, Server}, Request, Response};
+
+#[test]
+fn request_over_unix_socket() {
+    let dir = std::env::temp_dir().join(format!("heggemann-unix-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("http.sock");
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || server::serve_unix(listener, |request: Request| {
+        let host = request.headers.get("host").map(|h| h.to_string()).unwrap_or_default();
+        Response::Ok.body(format!("{} via {host}", request.path))
+    }));
+
+    let request: Request = "GET /status HTTP/1.1\r\nConnection: close\r\n\r\n".parse().unwrap();
+    let response = client::send_unix(&path, &request).unwrap();
+    assert_eq!(response.status.code, 200);
+    assert_eq!(response.body, b"/status via localhost");
+    // A host of the caller's choosing is kept
+    let request: Request = "GET /status HTTP/1.1\r\nHost: sidecar\r\nConnection: close\r\n\r\n".parse().unwrap();
+    assert_eq!(client::send_unix(&path, &request).unwrap().body, b"/status via sidecar");
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_dir(&dir).unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn configured_server_over_unix_socket() {
+    let dir = std::env::temp_dir().join(format!("heggemann-unix-server-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("http.sock");
+    let _ = fs::remove_file(&path);
+    let server = Server::new(UnixListener::bind(&path).unwrap(), |_| Response::Ok.body("")).max_body_size(3);
+    thread::spawn(move || server.run());
+
+    let mut request: Request = "POST /upload HTTP/1.1\r\n\r\n".parse().unwrap();
+    request.extensions.insert(Body(b"four".to_vec()));
+    assert_eq!(client::send_unix(&path, &request).unwrap().status.code, 413);
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn access_log_over_unix_socket() {
+    let dir = std::env::temp_dir().join(format!("heggemann-unix-log-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("http.sock");
+    let _ = fs::remove_file(&path);
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&lines);
+    let server = Server::new(UnixListener::bind(&path).unwrap(), |_| Response::Ok.body("hi"))
+        .access_log(move |line| sink.lock().unwrap().push(line.to_owned()));
+    thread::spawn(move || server.run());
+
+    let request: Request = "GET /logged HTTP/1.1\r\nConnection: close\r\n\r\n".parse().unwrap();
+    assert_eq!(client::send_unix(&path, &request).unwrap().status.code, 200);
+    // The line is written before the response
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("- - - ["), "{}", lines[0]);
+    assert!(lines[0].ends_with(r#"] "GET /logged HTTP/1.1" 200 2 "-" "-""#), "{}", lines[0]);
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_dir(&dir).unwrap();
+}
@@ -0,0 +1,25 @@
+use heggemann_http::{
+    header::{HeaderMap, Key, Value, ValueError},
+    response::ResponseBuilder,
+    Response,
+};
+
+#[test]
+fn construct_and_append() {
+    let mut value = Value::new("gzip").unwrap();
+    value.append(" br ").unwrap();
+    assert_eq!(value.to_str(), Some("gzip,br"));
+    assert!(matches!(value.append("a\nb"), Err(ValueError::IllegalChars { found: '\n', at: 1, .. })));
+    assert_eq!(value, "gzip,br");
+    assert!(matches!(Value::new("späti"), Err(ValueError::NonAsciiChars { at: 2, .. })));
+}
+
+#[test]
+fn build_header_map() {
+    let mut headers = HeaderMap::new();
+    headers.insert(Key::new("content-type").unwrap(), Value::new("text/plain").unwrap());
+    let response = Response::Ok.body("hi");
+    let (status, _, body) = response.into_parts();
+    let response = ResponseBuilder::from_parts(status, headers, body);
+    assert_eq!(response.get_header("content-type"), Some(&Value::new("text/plain").unwrap()));
+}